@@ -18,10 +18,11 @@
 
 use grandpa::AuthorityId as GrandpaId;
 use hex_literal::hex;
-use node_primitives::{AccountId, AuraId, Balance};
+use node_rpc::session::{babe_key, grandpa_key};
+use node_primitives::{AccountId, AuraId, BabeId, Balance};
 use node_runtime::{
-    AuraConfig, BalancesConfig, ContractsConfig, DAYS,
-    COIN, GrandpaConfig, IndicesConfig, MILLI,
+    AuraConfig, BabeConfig, BalancesConfig, ContractsConfig, DAYS, EthBridgeConfig, EthRelayConfig,
+    COIN, GrandpaConfig, ImOnlineConfig, IndicesConfig, MILLI,
     Perbill, SECS_PER_BLOCK, KtonConfig,
     SessionConfig, SessionKeys, StakerStatus,
     StakingConfig, SudoConfig, SystemConfig, TimestampConfig,
@@ -36,6 +37,9 @@ use serde_json::de::ParserNumber;
 
 const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// The RLP encoded header of the Ethereum genesis block.
+const ETHEREUM_GENESIS_HEADER: &[u8] = &hex!("f90214a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000850400000000808213888080a011bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82faa00000000000000000000000000000000000000000000000000000000000000000880000000000000042");
+
 /// Specialized `ChainSpec`.
 pub type ChainSpec = substrate_service::ChainSpec<GenesisConfig>;
 
@@ -44,56 +48,47 @@ pub fn flaming_fir_config() -> Result<ChainSpec, String> {
     ChainSpec::from_embedded(include_bytes!("../res/flaming-fir.json"))
 }
 
+/// The environment variable holding the secret the keys of the staging authorities are derived
+/// from, needed to build the staging chain spec.
+const STAGING_SECRET_VAR: &str = "DARWINIA_STAGING_SECRET";
+
+/// Stash, controller and session keys of the staging authority `i`, derived from `secret`:
+/// `"$secret"/fir/stash/$i`, `"$secret"/fir/controller/$i` and the ed25519 session key
+/// `"$secret"//fir//session//$i`, run by the node with `--key`, of which the GRANDPA and
+/// BABE keys are derived like the node does.
+fn staging_authority_keys(secret: &str, i: u32) -> (AccountId, AccountId, AuraId, GrandpaId, BabeId) {
+    let account = |role: &str| {
+        sr25519::Pair::from_string(&format!("{}/fir/{}/{}", secret, role, i), None)
+            .expect("the secret was checked by `staging_testnet_config`; qed")
+            .public()
+    };
+    let session = ed25519::Pair::from_string(&format!("{}//fir//session//{}", secret, i), None)
+        .expect("the secret was checked by `staging_testnet_config`; qed");
+
+    (
+        account("stash"),
+        account("controller"),
+        session.public(),
+        grandpa_key(&session).public(),
+        babe_key(&session).public(),
+    )
+}
+
 fn staging_testnet_config_genesis() -> GenesisConfig {
-    // stash, controller, session-key
-    // generated with secret:
-    // for i in 1 2 3 4 ; do for j in stash controller; do subkey inspect "$secret"/fir/$j/$i; done; done
-    // and
-    // for i in 1 2 3 4 ; do for j in session; do subkey --ed25519 inspect "$secret"//fir//$j//$i; done; done
-
-    let initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId)> =
-        vec![(
-                 // 5Fbsd6WXDGiLTxunqeK5BATNiocfCqu9bS1yArVjCgeBLkVy
-                 hex!["9c7a2ee14e565db0c69f78c7b4cd839fbf52b607d867e9e9c5a79042898a0d12"].unchecked_into(),
-                 // 5EnCiV7wSHeNhjW3FSUwiJNkcc2SBkPLn5Nj93FmbLtBjQUq
-                 hex!["781ead1e2fa9ccb74b44c19d29cb2a7a4b5be3972927ae98cd3877523976a276"].unchecked_into(),
-                 // 5Fb9ayurnxnaXj56CjmyQLBiadfRCqUbL2VWNbbe1nZU6wiC
-                 hex!["9becad03e6dcac03cee07edebca5475314861492cdfc96a2144a67bbe9699332"].unchecked_into(),
-                 // 5Fb9ayurnxnaXj56CjmyQLBiadfRCqUbL2VWNbbe1nZU6wiC
-                 hex!["9becad03e6dcac03cee07edebca5475314861492cdfc96a2144a67bbe9699332"].unchecked_into(),
-             ), (
-                 // 5ERawXCzCWkjVq3xz1W5KGNtVx2VdefvZ62Bw1FEuZW4Vny2
-                 hex!["68655684472b743e456907b398d3a44c113f189e56d1bbfd55e889e295dfde78"].unchecked_into(),
-                 // 5Gc4vr42hH1uDZc93Nayk5G7i687bAQdHHc9unLuyeawHipF
-                 hex!["c8dc79e36b29395413399edaec3e20fcca7205fb19776ed8ddb25d6f427ec40e"].unchecked_into(),
-                 // 5EockCXN6YkiNCDjpqqnbcqd4ad35nU4RmA1ikM4YeRN4WcE
-                 hex!["7932cff431e748892fa48e10c63c17d30f80ca42e4de3921e641249cd7fa3c2f"].unchecked_into(),
-                 // 5EockCXN6YkiNCDjpqqnbcqd4ad35nU4RmA1ikM4YeRN4WcE
-                 hex!["7932cff431e748892fa48e10c63c17d30f80ca42e4de3921e641249cd7fa3c2f"].unchecked_into(),
-             ), (
-                 // 5DyVtKWPidondEu8iHZgi6Ffv9yrJJ1NDNLom3X9cTDi98qp
-                 hex!["547ff0ab649283a7ae01dbc2eb73932eba2fb09075e9485ff369082a2ff38d65"].unchecked_into(),
-                 // 5FeD54vGVNpFX3PndHPXJ2MDakc462vBCD5mgtWRnWYCpZU9
-                 hex!["9e42241d7cd91d001773b0b616d523dd80e13c6c2cab860b1234ef1b9ffc1526"].unchecked_into(),
-                 // 5E1jLYfLdUQKrFrtqoKgFrRvxM3oQPMbf6DfcsrugZZ5Bn8d
-                 hex!["5633b70b80a6c8bb16270f82cca6d56b27ed7b76c8fd5af2986a25a4788ce440"].unchecked_into(),
-                 // 5E1jLYfLdUQKrFrtqoKgFrRvxM3oQPMbf6DfcsrugZZ5Bn8d
-                 hex!["5633b70b80a6c8bb16270f82cca6d56b27ed7b76c8fd5af2986a25a4788ce440"].unchecked_into(),
-             ), (
-                 // 5HYZnKWe5FVZQ33ZRJK1rG3WaLMztxWrrNDb1JRwaHHVWyP9
-                 hex!["f26cdb14b5aec7b2789fd5ca80f979cef3761897ae1f37ffb3e154cbcc1c2663"].unchecked_into(),
-                 // 5EPQdAQ39WQNLCRjWsCk5jErsCitHiY5ZmjfWzzbXDoAoYbn
-                 hex!["66bc1e5d275da50b72b15de072a2468a5ad414919ca9054d2695767cf650012f"].unchecked_into(),
-                 // 5DMa31Hd5u1dwoRKgC4uvqyrdK45RHv3CpwvpUC1EzuwDit4
-                 hex!["3919132b851ef0fd2dae42a7e734fe547af5a6b809006100f48944d7fae8e8ef"].unchecked_into(),
-                 // 5DMa31Hd5u1dwoRKgC4uvqyrdK45RHv3CpwvpUC1EzuwDit4
-                 hex!["3919132b851ef0fd2dae42a7e734fe547af5a6b809006100f48944d7fae8e8ef"].unchecked_into(),
-             )];
-
-    // generated with secret: subkey inspect "$secret"/fir
+    let secret = std::env::var(STAGING_SECRET_VAR)
+        .expect("the secret was checked by `staging_testnet_config`; qed");
+    staging_testnet_genesis(&secret)
+}
+
+fn staging_testnet_genesis(secret: &str) -> GenesisConfig {
+    let initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId, BabeId)> =
+        (1..=4).map(|i| staging_authority_keys(secret, i)).collect();
+
+    // the root account, `"$secret"/fir`
     let endowed_accounts: Vec<AccountId> = vec![
-        // 5Ff3iXP75ruzroPWRP2FYBHWnmGGBSb63857BgnzCoXNxfPo
-        hex!["9ee5e5bdc0ec239eb164f865ecc345ce4c88e76ee002e0f7e318097347471809"].unchecked_into(),
+        sr25519::Pair::from_string(&format!("{}/fir", secret), None)
+            .expect("the secret was checked by `staging_testnet_config`; qed")
+            .public(),
     ];
 
     const ENDOWMENT: Balance = 10_000_000 * COIN;
@@ -125,7 +120,7 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
         }),
         session: Some(SessionConfig {
             validators: initial_authorities.iter().map(|x| x.1.clone()).collect(),
-            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.2.clone(), x.2.clone()))).collect::<Vec<_>>(),
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.3.clone(), x.2.clone(), x.4.clone()))).collect::<Vec<_>>(),
         }),
         staking: Some(StakingConfig {
             current_era: 0,
@@ -145,12 +140,20 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
             current_schedule: Default::default(),
             gas_price: 1 * MILLI,
         }),
+        eth_relay: Some(eth_relay_genesis()),
+        eth_bridge: Some(eth_bridge_genesis(vec![])),
         sudo: Some(SudoConfig {
             key: endowed_accounts[0].clone(),
         }),
         aura: Some(AuraConfig {
             authorities: initial_authorities.iter().map(|x| x.2.clone()).collect(),
         }),
+        babe: Some(BabeConfig {
+            authorities: initial_authorities.iter().map(|x| (x.4.clone(), 1)).collect(),
+        }),
+        im_online: Some(ImOnlineConfig {
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), x.2.clone())).collect(),
+        }),
         grandpa: Some(GrandpaConfig {
             authorities: initial_authorities.iter().map(|x| (x.3.clone(), 1)).collect(),
         }),
@@ -158,10 +161,16 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
     }
 }
 
-/// Staging testnet config.
-pub fn staging_testnet_config() -> ChainSpec {
+/// Staging testnet config, whose authorities are derived from the secret in
+/// `DARWINIA_STAGING_SECRET`.
+pub fn staging_testnet_config() -> Result<ChainSpec, String> {
+    let secret = std::env::var(STAGING_SECRET_VAR)
+        .map_err(|_| format!("The staging chain spec is built with the secret of its authorities in {}", STAGING_SECRET_VAR))?;
+    sr25519::Pair::from_string(&format!("{}/fir", secret), None)
+        .map_err(|e| format!("Invalid secret in {}: {:?}", STAGING_SECRET_VAR, e))?;
+
     let boot_nodes = vec![];
-    ChainSpec::from_genesis(
+    Ok(ChainSpec::from_genesis(
         "Staging Testnet",
         "staging_testnet",
         staging_testnet_config_genesis,
@@ -170,7 +179,7 @@ pub fn staging_testnet_config() -> ChainSpec {
         None,
         None,
         None,
-    )
+    ))
 }
 
 /// Helper function to generate AccountId from seed
@@ -187,26 +196,59 @@ pub fn get_aura_id_from_seed(seed: &str) -> AuraId {
         .public()
 }
 
-/// Helper function to generate GrandpaId from seed
+/// Helper function to generate GrandpaId from seed, derived from the Aura key like the node does
 pub fn get_grandpa_id_from_seed(seed: &str) -> GrandpaId {
-    ed25519::Pair::from_string(&format!("//{}", seed), None)
-        .expect("static values are valid; qed")
-        .public()
+    let authority_key = ed25519::Pair::from_string(&format!("//{}", seed), None)
+        .expect("static values are valid; qed");
+    grandpa_key(&authority_key).public()
+}
+
+/// Helper function to generate BabeId from seed, derived from the Aura key like the node does
+pub fn get_babe_id_from_seed(seed: &str) -> BabeId {
+    let authority_key = ed25519::Pair::from_string(&format!("//{}", seed), None)
+        .expect("static values are valid; qed");
+    babe_key(&authority_key).public()
 }
 
 /// Helper function to generate stash, controller and session key from seed
-pub fn get_authority_keys_from_seed(seed: &str) -> (AccountId, AccountId, AuraId, GrandpaId) {
+pub fn get_authority_keys_from_seed(seed: &str) -> (AccountId, AccountId, AuraId, GrandpaId, BabeId) {
     (
         get_account_id_from_seed(&format!("{}//stash", seed)),
         get_account_id_from_seed(seed),
         get_aura_id_from_seed(seed),
-        get_grandpa_id_from_seed(seed)
+        get_grandpa_id_from_seed(seed),
+        get_babe_id_from_seed(seed),
     )
 }
 
+/// Ethereum headers are relayed from the genesis block. The dataset root of epoch 0 is known
+/// from genesis, so the first headers can be relayed at once. The roots of later epochs are set
+/// by root, as the relayers generate them.
+fn eth_relay_genesis() -> EthRelayConfig {
+    EthRelayConfig {
+        genesis_header: ETHEREUM_GENESIS_HEADER.to_vec(),
+        dataset_roots: vec![(0, hex!["2f78e10c4ceadeec764fe0467c8c3261e53685c050ec0bf05220f77f5828816a"].into())],
+    }
+}
+
+/// The bridge is given the locking contracts by root once they are deployed. Until the relays
+/// are trusted, more than two thirds of `authorities` attest locks. At most a million RING and ten thousand KTON are minted a day.
+fn eth_bridge_genesis(authorities: Vec<AccountId>) -> EthBridgeConfig {
+    let threshold = if authorities.is_empty() { 0 } else { authorities.len() as u32 * 2 / 3 + 1 };
+    EthBridgeConfig {
+        locker_contract: Default::default(),
+        ring_contract: hex!["9469d013805bffb7d3debe5e7839237e535ec483"].into(),
+        kton_contract: hex!["9f284e1337a815fe77d2ff4ae46544645b20c5ff"].into(),
+        authorities,
+        threshold,
+        ring_mint_limit: 1_000_000 * COIN,
+        kton_mint_limit: 10_000 * COIN,
+    }
+}
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
-    initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId)>,
+    initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId, BabeId)>,
     root_key: AccountId,
     endowed_accounts: Option<Vec<AccountId>>,
     enable_println: bool,
@@ -252,7 +294,7 @@ pub fn testnet_genesis(
         }),
         session: Some(SessionConfig {
             validators: initial_authorities.iter().map(|x| x.1.clone()).collect(),
-            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.2.clone(), x.2.clone()))).collect::<Vec<_>>(),
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.3.clone(), x.2.clone(), x.4.clone()))).collect::<Vec<_>>(),
         }),
         staking: Some(StakingConfig {
             current_era: 0,
@@ -276,12 +318,20 @@ pub fn testnet_genesis(
             },
             gas_price: 1 * MILLI,
         }),
+        eth_relay: Some(eth_relay_genesis()),
+        eth_bridge: Some(eth_bridge_genesis(vec![])),
         sudo: Some(SudoConfig {
             key: root_key,
         }),
         aura: Some(AuraConfig {
             authorities: initial_authorities.iter().map(|x| x.2.clone()).collect(),
         }),
+        babe: Some(BabeConfig {
+            authorities: initial_authorities.iter().map(|x| (x.4.clone(), 1)).collect(),
+        }),
+        im_online: Some(ImOnlineConfig {
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), x.2.clone())).collect(),
+        }),
         grandpa: Some(GrandpaConfig {
             authorities: initial_authorities.iter().map(|x| (x.3.clone(), 1)).collect(),
         }),
@@ -319,7 +369,7 @@ fn crayfish_config_genesis() -> GenesisConfig {
 
 /// Helper function to create GenesisConfig for testing
 pub fn crayfish_testnet_genesis(
-    initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId)>,
+    initial_authorities: Vec<(AccountId, AccountId, AuraId, GrandpaId, BabeId)>,
     root_key: AccountId,
     endowed_accounts: Option<Vec<AccountId>>,
     enable_println: bool,
@@ -365,7 +415,7 @@ pub fn crayfish_testnet_genesis(
         }),
         session: Some(SessionConfig {
             validators: initial_authorities.iter().map(|x| x.1.clone()).collect(),
-            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.2.clone(), x.2.clone()))).collect::<Vec<_>>(),
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), SessionKeys(x.3.clone(), x.2.clone(), x.4.clone()))).collect::<Vec<_>>(),
         }),
         staking: Some(StakingConfig {
             current_era: 0,
@@ -389,12 +439,20 @@ pub fn crayfish_testnet_genesis(
             },
             gas_price: 1 * MILLI,
         }),
+        eth_relay: Some(eth_relay_genesis()),
+        eth_bridge: Some(eth_bridge_genesis(initial_authorities.iter().map(|x| x.1.clone()).collect())),
         sudo: Some(SudoConfig {
             key: root_key,
         }),
         aura: Some(AuraConfig {
             authorities: initial_authorities.iter().map(|x| x.2.clone()).collect(),
         }),
+        babe: Some(BabeConfig {
+            authorities: initial_authorities.iter().map(|x| (x.4.clone(), 1)).collect(),
+        }),
+        im_online: Some(ImOnlineConfig {
+            keys: initial_authorities.iter().map(|x| (x.1.clone(), x.2.clone())).collect(),
+        }),
         grandpa: Some(GrandpaConfig {
             authorities: initial_authorities.iter().map(|x| (x.3.clone(), 1)).collect(),
         }),
@@ -444,6 +502,19 @@ pub fn crayfish_testnet_config() -> ChainSpec {
         token_properties())
 }
 
+/// crayfish testnet config authored with BABE instead of Aura, see `docs/babe-migration.md`
+pub fn crayfish_babe_testnet_config() -> ChainSpec {
+    ChainSpec::from_genesis(
+        "Darwinia Crayfish Testnet (BABE)",
+        "crayfish_babe_testnet",
+        crayfish_config_genesis,
+        vec![],
+        Some(TelemetryEndpoints::new(vec![(STAGING_TELEMETRY_URL.to_string(), 0)])),
+        Some("DAR"),
+        Some("babe"),
+        token_properties())
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use service_test;
@@ -490,6 +561,44 @@ pub(crate) mod tests {
         ChainSpec::from_genesis("Integration Test", "test", local_testnet_genesis_instant, vec![], None, None, None, None)
     }
 
+    #[test]
+    fn mint_limits_should_be_set_in_every_genesis() {
+        for genesis in vec![
+            staging_testnet_genesis("//Staging"),
+            development_config_genesis(),
+            local_testnet_genesis(),
+            crayfish_config_genesis(),
+        ] {
+            let eth_bridge = genesis.eth_bridge.expect("the Ethereum bridge is configured");
+            assert_eq!(eth_bridge.ring_mint_limit, 1_000_000 * COIN);
+            assert_eq!(eth_bridge.kton_mint_limit, 10_000 * COIN);
+        }
+    }
+
+    #[test]
+    fn session_keys_should_be_derived_like_the_node_does() {
+        let secret = "//Staging";
+        let staging = (1..=4).map(|i| format!("{}//fir//session//{}", secret, i));
+        let testnets = vec!["//Alice", "//Bob"].into_iter().map(String::from);
+
+        for (genesis, seeds) in vec![
+            (staging_testnet_genesis(secret), staging.collect::<Vec<_>>()),
+            (local_testnet_genesis(), testnets.clone().collect()),
+            (crayfish_config_genesis(), testnets.collect()),
+        ] {
+            let grandpa = genesis.grandpa.expect("GRANDPA is configured").authorities;
+            let babe = genesis.babe.expect("BABE is configured").authorities;
+            assert_eq!(grandpa.len(), seeds.len());
+            assert_eq!(babe.len(), seeds.len());
+
+            for (i, seed) in seeds.iter().enumerate() {
+                let authority_key = ed25519::Pair::from_string(seed, None).unwrap();
+                assert_eq!(grandpa[i].0, grandpa_key(&authority_key).public());
+                assert_eq!(babe[i].0, babe_key(&authority_key).public());
+            }
+        }
+    }
+
     #[test]
     #[ignore]
     fn test_connectivity() {
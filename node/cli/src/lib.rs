@@ -52,6 +52,8 @@ pub enum ChainSpec {
 	StagingTestnet,
 	/// Crayfish, darwinia network poc-2
 	CrayfishTestnet,
+	/// Crayfish with blocks authored by BABE instead of Aura.
+	CrayfishBabeTestnet,
 }
 
 /// Custom subcommands.
@@ -123,8 +125,9 @@ impl ChainSpec {
 			ChainSpec::FlamingFir => chain_spec::flaming_fir_config()?,
 			ChainSpec::Development => chain_spec::development_config(),
 			ChainSpec::LocalTestnet => chain_spec::local_testnet_config(),
-			ChainSpec::StagingTestnet => chain_spec::staging_testnet_config(),
+			ChainSpec::StagingTestnet => chain_spec::staging_testnet_config()?,
 			ChainSpec::CrayfishTestnet => chain_spec::crayfish_testnet_config(),
+			ChainSpec::CrayfishBabeTestnet => chain_spec::crayfish_babe_testnet_config(),
 		})
 	}
 
@@ -133,6 +136,7 @@ impl ChainSpec {
 			"dev" => Some(ChainSpec::Development),
 			"local" => Some(ChainSpec::LocalTestnet),
 			"" | "crayfish" => Some(ChainSpec::CrayfishTestnet),
+			"crayfish-babe" => Some(ChainSpec::CrayfishBabeTestnet),
 			"flaming-fir" => Some(ChainSpec::FlamingFir),
 			"staging" => Some(ChainSpec::StagingTestnet),
 			_ => None,
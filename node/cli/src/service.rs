@@ -18,17 +18,23 @@
 
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use babe::{self, start_babe, BabeLink, BabeParams, Config as BabeConfig};
 use client::{self, LongestChain};
-use consensus::{import_queue, start_aura, AuraImportQueue, SlotDuration};
+use consensus::{import_queue, start_aura, SlotDuration};
+use consensus_common::import_queue::BasicQueue;
+use fg_primitives::GrandpaApi;
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
 use node_executor;
 use primitives::{Pair as PairT, ed25519};
 use futures::prelude::*;
-use node_primitives::Block;
-use node_runtime::{GenesisConfig, RuntimeApi};
+use parity_codec::{Decode, Encode};
+use node_primitives::{Block, Hash};
+use node_runtime::{Call, GenesisConfig, GrandpaJustification, OffencesCall, RuntimeApi, UncheckedExtrinsic};
+use sr_primitives::{generic::BlockId, traits::ProvideRuntimeApi, OpaqueExtrinsic};
 use substrate_service::{
 	FactoryFullConfiguration, LightComponents, FullComponents, FullBackend,
 	FullClient, LightClient, LightBackend, FullExecutor, LightExecutor,
@@ -38,19 +44,141 @@ use transaction_pool::{self, txpool::{Pool as TransactionPool}};
 use inherents::InherentDataProviders;
 use network::construct_simple_protocol;
 use substrate_service::construct_service_factory;
-use log::info;
+use log::{debug, info, warn};
 use substrate_service::TelemetryOnConnect;
+use substrate_keystore::Store as Keystore;
+use node_rpc::session::{babe_key, grandpa_key, SessionKeyStore};
 
 construct_simple_protocol! {
 	/// Demo protocol attachment for substrate.
 	pub struct NodeProtocol where Block = Block { }
 }
 
+/// The block production engine of a chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsensusEngine {
+	/// Round robin slots, signed with the ed25519 session key.
+	Aura,
+	/// Slots claimed with a VRF output of the sr25519 session key.
+	Babe,
+}
+
+impl ConsensusEngine {
+	/// The engine named by the `consensusEngine` of a chain spec, Aura if there is none.
+	pub fn of<G>(chain_spec: &substrate_service::ChainSpec<G>) -> Result<Self, ServiceError> {
+		match chain_spec.consensus_engine() {
+			None | Some("aura") => Ok(ConsensusEngine::Aura),
+			Some("babe") => Ok(ConsensusEngine::Babe),
+			Some(other) => Err(format!("Unknown consensus engine {}", other).into()),
+		}
+	}
+}
+
+/// Register the slot inherent data of both Aura and BABE.
+///
+/// The runtime contains both modules and each of them checks the slot of its own inherent
+/// against the timestamp, whichever one authors the blocks.
+fn register_slot_inherent_data_providers(
+	providers: &InherentDataProviders,
+	slot_duration: u64,
+) -> Result<(), ServiceError> {
+	if !providers.has_provider(&srml_aura::INHERENT_IDENTIFIER) {
+		providers
+			.register_provider(srml_aura::InherentDataProvider::new(slot_duration))
+			.map_err(|e| format!("{:?}", e))?;
+	}
+	if !providers.has_provider(&srml_babe::INHERENT_IDENTIFIER) {
+		providers
+			.register_provider(srml_babe::InherentDataProvider::new(slot_duration))
+			.map_err(|e| format!("{:?}", e))?;
+	}
+
+	Ok(())
+}
+
+/// The keystore of a full node, where `author_rotateKeys` generates session keys.
+///
+/// It is opened once in the keystore directory of the service, with its password, and owned by
+/// the session RPC.
+struct NodeKeystore {
+	store: Mutex<Keystore>,
+	password: String,
+}
+
+impl SessionKeyStore for NodeKeystore {
+	fn generate_authority_key(&self) -> Result<ed25519::Pair, String> {
+		let mut store = self.store.lock().map_err(|_| "Keystore lock is poisoned".to_string())?;
+		store.generate::<ed25519::Pair>(&self.password).map_err(|e| format!("{:?}", e))
+	}
+}
+
+thread_local! {
+	/// The keystore of the full service being built, taken by its RPC extensions.
+	///
+	/// RPC extensions are only given the client and the transaction pool: they are built by
+	/// `FullComponents::new`, on the thread building the service, which hands the keystore over
+	/// for the duration of the call.
+	static BUILDING_KEYSTORE: RefCell<Option<Arc<dyn SessionKeyStore>>> = RefCell::new(None);
+}
+
+/// The key GRANDPA votes with: the one derived from `authority_key`, or `authority_key` itself
+/// if only that one is in the authority set at the best block.
+fn grandpa_voter_key(
+	service: &FullComponents<Factory>,
+	authority_key: Arc<ed25519::Pair>,
+) -> Result<Arc<ed25519::Pair>, ServiceError> {
+	let derived = grandpa_key(&authority_key);
+	let client = service.client();
+	let best = BlockId::hash(client.info().chain.best_hash);
+	let authorities = client.runtime_api().grandpa_authorities(&best)?;
+	let in_set = |key: &ed25519::Public| authorities.iter().any(|(id, _)| id == key);
+
+	if !in_set(&derived.public()) && in_set(&authority_key.public()) {
+		info!("GRANDPA key {} is not in the authority set, voting with the authority key", derived.public());
+		Ok(authority_key)
+	} else {
+		Ok(Arc::new(derived))
+	}
+}
+
+/// Submit the GRANDPA justification stored with the finalized block `hash`, if there is one, as
+/// an unsigned `note_grandpa_justification` transaction.
+fn note_grandpa_justification(
+	client: &FullClient<Factory>,
+	pool: &TransactionPool<transaction_pool::ChainApi<FullClient<Factory>, Block>>,
+	hash: Hash,
+) {
+	let justification = match client.justification(&BlockId::hash(hash)) {
+		Ok(Some(justification)) => justification,
+		_ => return,
+	};
+	let justification = match GrandpaJustification::decode(&mut &justification[..]) {
+		Some(justification) => justification,
+		None => {
+			warn!("Cannot decode the GRANDPA justification of {}", hash);
+			return;
+		},
+	};
+
+	let call = Call::Offences(OffencesCall::note_grandpa_justification(justification));
+	// an opaque extrinsic holds the encoding of the extrinsic without its length
+	let xt = UncheckedExtrinsic::new_unsigned(call).encode();
+	let xt = match Vec::<u8>::decode(&mut &xt[..]) {
+		Some(xt) => OpaqueExtrinsic(xt),
+		None => return,
+	};
+	if let Err(e) = pool.submit_one(&BlockId::hash(hash), xt) {
+		debug!("GRANDPA justification of {} not submitted: {:?}", hash, e);
+	}
+}
+
 /// Node specific configuration
 pub struct NodeConfig<F: substrate_service::ServiceFactory> {
 	/// grandpa connection to import block
 	// FIXME #1134 rather than putting this on the config, let's have an actual intermediate setup state
 	pub grandpa_import_setup: Option<(Arc<grandpa::BlockImportForService<F>>, grandpa::LinkHalfForService<F>)>,
+	/// BABE connection of the import queue, needed to author blocks with BABE
+	pub babe_link: Option<BabeLink>,
 	inherent_data_providers: InherentDataProviders,
 }
 
@@ -58,6 +186,7 @@ impl<F> Default for NodeConfig<F> where F: substrate_service::ServiceFactory {
 	fn default() -> NodeConfig<F> {
 		NodeConfig {
 			grandpa_import_setup: None,
+			babe_link: None,
 			inherent_data_providers: InherentDataProviders::new(),
 		}
 	}
@@ -76,8 +205,20 @@ construct_service_factory! {
 		Genesis = GenesisConfig,
 		Configuration = NodeConfig<Self>,
 		FullService = FullComponents<Self>
-			{ |config: FactoryFullConfiguration<Self>|
-				FullComponents::<Factory>::new(config) },
+			{ |config: FactoryFullConfiguration<Self>| {
+				let password: &str = config.password.as_ref();
+				let keystore = NodeKeystore {
+					store: Mutex::new(Keystore::open(config.keystore_path.clone().into())
+						.map_err(|e| format!("Cannot open the keystore: {:?}", e))?),
+					password: password.into(),
+				};
+
+				BUILDING_KEYSTORE.with(|k| *k.borrow_mut() = Some(Arc::new(keystore)));
+				let service = FullComponents::<Factory>::new(config);
+				// the RPC extensions took it, unless building failed before them
+				BUILDING_KEYSTORE.with(|k| k.borrow_mut().take());
+				service
+			}},
 		AuthoritySetup = {
 			|mut service: Self::FullService, local_key: Option<Arc<ed25519::Pair>>| {
 				let (block_import, link_half) = service.config.custom.grandpa_import_setup.take()
@@ -93,33 +234,63 @@ construct_service_factory! {
 					let client = service.client();
 					let select_chain = service.select_chain()
 						.ok_or(ServiceError::SelectChainRequired)?;
-					let aura = start_aura(
-						SlotDuration::get_or_compute(&*client)?,
-						key.clone(),
-						client,
-						select_chain,
-						block_import.clone(),
-						proposer,
-						service.network(),
-						service.config.custom.inherent_data_providers.clone(),
-						service.config.force_authoring,
-					)?;
-					service.spawn_task(Box::new(aura.select(service.on_exit()).then(|_| Ok(()))));
-
-					info!("Running Grandpa session as Authority {}", key.public());
+					match ConsensusEngine::of(&service.config.chain_spec)? {
+						ConsensusEngine::Aura => {
+							let aura = start_aura(
+								SlotDuration::get_or_compute(&*client)?,
+								key.clone(),
+								client,
+								select_chain,
+								block_import.clone(),
+								proposer,
+								service.network(),
+								service.config.custom.inherent_data_providers.clone(),
+								service.config.force_authoring,
+							)?;
+							service.spawn_task(Box::new(aura.select(service.on_exit()).then(|_| Ok(()))));
+						},
+						ConsensusEngine::Babe => {
+							// BABE keys are sr25519, derived from the authority key
+							let babe_key = babe_key(key);
+							info!("Using BABE key {}", babe_key.public());
+
+							let babe_link = service.config.custom.babe_link.take()
+								.expect("BABE link is present for Full Services or setup failed before. qed");
+							let babe = start_babe(BabeParams {
+								config: BabeConfig::get_or_compute(&*client)?,
+								local_key: Arc::new(babe_key),
+								client,
+								select_chain,
+								block_import: block_import.clone(),
+								env: proposer,
+								sync_oracle: service.network(),
+								inherent_data_providers: service.config.custom.inherent_data_providers.clone(),
+								force_authoring: service.config.force_authoring,
+								time_source: babe_link,
+							})?;
+							service.spawn_task(Box::new(babe.select(service.on_exit()).then(|_| Ok(()))));
+						},
+					}
 				}
 
+				// GRANDPA votes with its own key, derived from the authority key, unless the
+				// current set still holds the authority key itself, as registered before the
+				// keys were derived.
 				let local_key = if service.config.disable_grandpa {
 					None
 				} else {
-					local_key
+					local_key.map(|key| grandpa_voter_key(&service, key)).transpose()?
 				};
+				if let Some(ref key) = local_key {
+					info!("Running Grandpa session as Authority {}", key.public());
+				}
 
 				let config = grandpa::Config {
 					local_key,
 					// FIXME #1578 make this available through chainspec
 					gossip_duration: Duration::from_millis(333),
-					justification_period: 4096,
+					// staking waits for justifications, so that eras are enacted soon enough
+					justification_period: 32,
 					name: Some(service.config.name.clone())
 				};
 
@@ -148,14 +319,26 @@ construct_service_factory! {
 					},
 				}
 
+				// staking enacts eras once GRANDPA justified their first block on chain
+				let client = service.client();
+				let pool = service.transaction_pool();
+				let justifications = service.client().finality_notification_stream()
+					.for_each(move |notification| {
+						note_grandpa_justification(&client, &pool, notification.hash);
+						Ok(())
+					});
+				service.spawn_task(Box::new(justifications.select(service.on_exit()).then(|_| Ok(()))));
+
 				Ok(service)
 			}
 		},
 		LightService = LightComponents<Self>
 			{ |config| <LightComponents<Factory>>::new(config) },
-		FullImportQueue = AuraImportQueue<Self::Block>
+		FullImportQueue = BasicQueue<Self::Block>
 			{ |config: &mut FactoryFullConfiguration<Self> , client: Arc<FullClient<Self>>, select_chain: Self::SelectChain| {
 				let slot_duration = SlotDuration::get_or_compute(&*client)?;
+				register_slot_inherent_data_providers(&config.custom.inherent_data_providers, slot_duration.get())?;
+
 				let (block_import, link_half) =
 					grandpa::block_import::<_, _, _, RuntimeApi, FullClient<Self>, _>(
 						client.clone(), client.clone(), select_chain
@@ -165,17 +348,33 @@ construct_service_factory! {
 
 				config.custom.grandpa_import_setup = Some((block_import.clone(), link_half));
 
-				import_queue::<_, _, ed25519::Pair>(
-					slot_duration,
-					block_import,
-					Some(justification_import),
-					None,
-					None,
-					client,
-					config.custom.inherent_data_providers.clone(),
-				).map_err(Into::into)
+				match ConsensusEngine::of(&config.chain_spec)? {
+					ConsensusEngine::Aura => import_queue::<_, _, ed25519::Pair>(
+						slot_duration,
+						block_import,
+						Some(justification_import),
+						None,
+						None,
+						client,
+						config.custom.inherent_data_providers.clone(),
+					).map_err(Into::into),
+					ConsensusEngine::Babe => {
+						let (queue, babe_link) = babe::import_queue(
+							BabeConfig::get_or_compute(&*client)?,
+							block_import,
+							Some(justification_import),
+							None,
+							None,
+							client,
+							config.custom.inherent_data_providers.clone(),
+						)?;
+						config.custom.babe_link = Some(babe_link);
+
+						Ok(queue)
+					},
+				}
 			}},
-		LightImportQueue = AuraImportQueue<Self::Block>
+		LightImportQueue = BasicQueue<Self::Block>
 			{ |config: &FactoryFullConfiguration<Self>, client: Arc<LightClient<Self>>| {
 				#[allow(deprecated)]
 				let fetch_checker = client.backend().blockchain().fetcher()
@@ -189,15 +388,29 @@ construct_service_factory! {
 				let finality_proof_import = block_import.clone();
 				let finality_proof_request_builder = finality_proof_import.create_finality_proof_request_builder();
 
-				import_queue::<_, _, ed25519::Pair>(
-					SlotDuration::get_or_compute(&*client)?,
-					block_import,
-					None,
-					Some(finality_proof_import),
-					Some(finality_proof_request_builder),
-					client,
-					config.custom.inherent_data_providers.clone(),
-				).map_err(Into::into)
+				let slot_duration = SlotDuration::get_or_compute(&*client)?;
+				register_slot_inherent_data_providers(&config.custom.inherent_data_providers, slot_duration.get())?;
+
+				match ConsensusEngine::of(&config.chain_spec)? {
+					ConsensusEngine::Aura => import_queue::<_, _, ed25519::Pair>(
+						slot_duration,
+						block_import,
+						None,
+						Some(finality_proof_import),
+						Some(finality_proof_request_builder),
+						client,
+						config.custom.inherent_data_providers.clone(),
+					).map_err(Into::into),
+					ConsensusEngine::Babe => babe::import_queue(
+						BabeConfig::get_or_compute(&*client)?,
+						block_import,
+						None,
+						Some(finality_proof_import),
+						Some(finality_proof_request_builder),
+						client,
+						config.custom.inherent_data_providers.clone(),
+					).map(|(queue, _)| queue).map_err(Into::into),
+				}
 			}},
 		SelectChain = LongestChain<FullBackend<Self>, Self::Block>
 			{ |config: &FactoryFullConfiguration<Self>, client: Arc<FullClient<Self>>| {
@@ -208,6 +421,17 @@ construct_service_factory! {
 		FinalityProofProvider = { |client: Arc<FullClient<Self>>| {
 			Ok(Some(Arc::new(GrandpaFinalityProofProvider::new(client.clone(), client)) as _))
 		}},
+		RpcExtensions = jsonrpc_core::IoHandler<substrate_rpc::Metadata>
+			{ |client, _pool| {
+				use node_rpc::balances::{Balances, BalancesApi};
+				use node_rpc::session::{Session, SessionApi};
+
+				let mut io = jsonrpc_core::IoHandler::<substrate_rpc::Metadata>::default();
+				io.extend_with(BalancesApi::to_delegate(Balances::new(client.clone())));
+				let keystore = BUILDING_KEYSTORE.with(|k| k.borrow_mut().take());
+				io.extend_with(SessionApi::to_delegate(Session::new(client, keystore)));
+				io
+			}},
 	}
 }
 
@@ -288,14 +288,15 @@ mod tests {
 		});
 	}
 
-	fn to_session_keys(ring: &AuthorityKeyring) -> SessionKeys {
-		SessionKeys(ring.to_owned().into(), ring.to_owned().into())
+	fn to_session_keys(ring: &AuthorityKeyring, babe: &AccountKeyring) -> SessionKeys {
+		SessionKeys(ring.to_owned().into(), ring.to_owned().into(), babe.to_owned().into())
 	}
 
 	fn new_test_ext(code: &[u8], support_changes_trie: bool) -> TestExternalities<Blake2Hasher> {
 		let three = AccountId::from_raw([3u8; 32]);
 		let mut ext = TestExternalities::new_with_code(code, GenesisConfig {
 			aura: Some(Default::default()),
+			babe: Some(Default::default()),
 			system: Some(SystemConfig {
 				changes_trie_config: if support_changes_trie { Some(ChangesTrieConfiguration {
 					digest_interval: 2,
@@ -331,9 +332,9 @@ mod tests {
 			session: Some(SessionConfig {
 				validators: vec![AccountKeyring::One.into(), AccountKeyring::Two.into(), three],
 				keys: vec![
-					(alice(), to_session_keys(&AuthorityKeyring::Alice)),
-					(bob(), to_session_keys(&AuthorityKeyring::Bob)),
-					(charlie(), to_session_keys(&AuthorityKeyring::Charlie)),
+					(alice(), to_session_keys(&AuthorityKeyring::Alice, &AccountKeyring::Alice)),
+					(bob(), to_session_keys(&AuthorityKeyring::Bob, &AccountKeyring::Bob)),
+					(charlie(), to_session_keys(&AuthorityKeyring::Charlie, &AccountKeyring::Charlie)),
 				]
 			}),
 			staking: Some(StakingConfig {
@@ -357,6 +358,9 @@ mod tests {
 			grandpa: Some(GrandpaConfig {
 				authorities: vec![],
 			}),
+			im_online: Some(Default::default()),
+			eth_relay: None,
+			eth_bridge: None,
 		}.build_storage().unwrap().0);
 		ext.changes_trie_storage().insert(0, GENESIS_HASH.into(), Default::default());
 		ext
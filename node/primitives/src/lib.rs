@@ -50,6 +50,9 @@ pub type AuraSignature = primitives::ed25519::Signature;
 /// The Ed25519 pub key of an session that belongs to an Aura authority of the chain.
 pub type AuraId = primitives::ed25519::Public;
 
+/// The Sr25519 pub key of an session that belongs to a BABE authority of the chain.
+pub type BabeId = primitives::sr25519::Public;
+
 /// Index of a transaction in the chain.
 pub type Nonce = u64;
 
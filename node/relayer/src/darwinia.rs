@@ -0,0 +1,209 @@
+//! Darwinia as the target chain, submitting signed extrinsics through the JSON-RPC endpoint of a
+//! node.
+//!
+//! The extrinsics are mortal: one not included within `ERA_PERIOD` blocks never is, and the
+//! relayer submits its call again. Whether a call was applied is read from the storage of the
+//! modules, so that the calls of other relayers count too.
+
+use node_primitives::{AccountId, Hash, Nonce};
+use node_runtime::{Call, UncheckedExtrinsic};
+use parity_codec::{Compact, Decode, Encode};
+use primitive_types::H256;
+use primitives::{blake2_256, sr25519, Bytes, Pair};
+use serde_json::{json, Value};
+use sr_io::keccak_256;
+use sr_primitives::generic::Era;
+use crate::error::{Error, Result};
+use crate::relayer::Target;
+use crate::rpc::RpcClient;
+
+/// The blocks in which a submitted extrinsic can be included.
+const ERA_PERIOD: u64 = 64;
+
+/// A Darwinia node, to which the calls are submitted signed by the account of the relayer.
+pub struct DarwiniaTarget {
+	rpc: RpcClient,
+	signer: sr25519::Pair,
+	/// The nonce of the next extrinsic and the block in which the last submitted one dies,
+	/// unknown until fetched from the node.
+	nonce: Option<(Nonce, u64)>,
+}
+
+impl DarwiniaTarget {
+	/// Submit to the node at `url`, signing with `signer`.
+	pub fn new(url: &str, signer: sr25519::Pair) -> Self {
+		DarwiniaTarget {
+			rpc: RpcClient::new(url),
+			signer,
+			nonce: None,
+		}
+	}
+
+	fn account(&self) -> AccountId {
+		self.signer.public()
+	}
+
+	/// The value of the storage item of `key`, hashed as the key of a map is.
+	fn storage(&self, key: &[u8]) -> Result<Option<Bytes>> {
+		self.rpc.call("state_getStorage", json!([Bytes(blake2_256(key).to_vec())]))
+	}
+
+	/// Whether the `bool` map of `prefix` is true for `key`.
+	fn is_set(&self, prefix: &[u8], key: impl Encode) -> Result<bool> {
+		let mut storage_key = prefix.to_vec();
+		key.encode_to(&mut storage_key);
+		Ok(self.storage(&storage_key)?.map_or(false, |value| value.0 == true.encode()))
+	}
+
+	/// The nonce of the next extrinsic, fetched again once every extrinsic submitted before is
+	/// either included or dead.
+	fn nonce(&mut self, best: u64) -> Result<Nonce> {
+		if let Some((nonce, death)) = self.nonce {
+			if best < death {
+				return Ok(nonce);
+			}
+		}
+
+		let mut key = b"System AccountNonce".to_vec();
+		self.account().encode_to(&mut key);
+		let nonce = match self.storage(&key)? {
+			Some(value) => Decode::decode(&mut &value[..]).ok_or_else(|| Error::Invalid("account nonce".into()))?,
+			None => 0,
+		};
+		Ok(nonce)
+	}
+
+	fn block_hash(&self, number: u64) -> Result<Hash> {
+		let hash: Option<Hash> = self.rpc.call("chain_getBlockHash", json!([number]))?;
+		hash.ok_or_else(|| Error::Invalid(format!("no block {}", number)))
+	}
+
+	/// The extrinsic of `call`, signed with `nonce` and valid in `era`, born in the block of
+	/// `birth_hash`.
+	fn sign(&self, call: Call, nonce: Nonce, era: Era, birth_hash: Hash) -> UncheckedExtrinsic {
+		let raw_payload = (Compact(nonce), call, era, birth_hash);
+		let signature = raw_payload.using_encoded(|payload| if payload.len() > 256 {
+			self.signer.sign(&blake2_256(payload)[..])
+		} else {
+			self.signer.sign(payload)
+		});
+		UncheckedExtrinsic::new_signed(nonce, raw_payload.1, self.account().into(), signature.into(), era)
+	}
+}
+
+impl Target for DarwiniaTarget {
+	fn best_number(&mut self) -> Result<u64> {
+		let header: Option<Value> = self.rpc.call("chain_getHeader", json!([]))?;
+		let number = header.as_ref().and_then(|header| match &header["number"] {
+			Value::String(number) => u64::from_str_radix(number.trim_start_matches("0x"), 16).ok(),
+			number => number.as_u64(),
+		});
+		number.ok_or_else(|| Error::Invalid("best header".into()))
+	}
+
+	fn is_applied(&mut self, call: &Call) -> Result<bool> {
+		match call {
+			Call::EthRelay(eth_relay::Call::relay_header(header, _)) => {
+				let mut key = b"EthRelay Headers".to_vec();
+				H256(keccak_256(header)).encode_to(&mut key);
+				Ok(self.storage(&key)?.is_some())
+			}
+			Call::EthBridge(eth_bridge::Call::redeem(proof)) =>
+				self.is_set(b"EthBridge Redeemed", (proof.block_hash, proof.index)),
+			Call::NftBridge(nft_bridge::Call::receive(proof)) =>
+				self.is_set(b"NftBridge Processed", (proof.block_hash, proof.index)),
+			_ => Ok(false),
+		}
+	}
+
+	fn submit(&mut self, call: Call) -> Result<u64> {
+		let best = self.best_number()?;
+		let era = Era::mortal(ERA_PERIOD, best);
+		let birth_hash = self.block_hash(era.birth(best))?;
+		let nonce = self.nonce(best)?;
+		let xt = self.sign(call, nonce, era, birth_hash);
+
+		match self.rpc.call::<Hash>("author_submitExtrinsic", json!([Bytes(xt.encode())])) {
+			Ok(_) => {
+				let death = era.death(best);
+				self.nonce = Some((nonce + 1, death));
+				Ok(death)
+			}
+			Err(e) => {
+				// the nonce is fetched again, in case another extrinsic of the account was included
+				self.nonce = None;
+				Err(e)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+	use jsonrpc_core::{IoHandler, Params, Value};
+	use sr_primitives::traits::Verify;
+	use crate::mock::serve;
+	use super::*;
+
+	#[test]
+	fn submit_should_sign_extrinsics() {
+		let signer = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let account = signer.public();
+		let submitted = Arc::new(Mutex::new(Vec::<UncheckedExtrinsic>::new()));
+
+		let mut io = IoHandler::new();
+		io.add_method("chain_getHeader", |_| -> jsonrpc_core::Result<Value> {
+			Ok(json!({ "number": "0x64" }))
+		});
+		io.add_method("chain_getBlockHash", |params: Params| -> jsonrpc_core::Result<Value> {
+			let (number,): (u64,) = params.parse()?;
+			Ok(json!(Hash::repeat_byte(number as u8)))
+		});
+		io.add_method("state_getStorage", |_| -> jsonrpc_core::Result<Value> {
+			Ok(json!(Bytes(5u64.encode())))
+		});
+		let s = submitted.clone();
+		io.add_method("author_submitExtrinsic", move |params: Params| -> jsonrpc_core::Result<Value> {
+			let (xt,): (Bytes,) = params.parse()?;
+			s.lock().unwrap().push(Decode::decode(&mut &xt[..]).unwrap());
+			Ok(json!(Hash::repeat_byte(2)))
+		});
+		let (_server, url) = serve(io);
+
+		let mut target = DarwiniaTarget::new(&url, signer);
+		let call = |header: u8| Call::EthRelay(eth_relay::Call::relay_header(vec![header], vec![]));
+		assert_eq!(target.submit(call(1)).unwrap(), 164);
+		assert_eq!(target.submit(call(2)).unwrap(), 164);
+
+		let submitted = submitted.lock().unwrap();
+		assert_eq!(submitted.len(), 2);
+		for (xt, (nonce, header)) in submitted.iter().zip(vec![(5u64, 1), (6, 2)]) {
+			let (address, signature, index, era) = xt.signature.clone().unwrap();
+			assert_eq!(address, account.clone().into());
+			assert_eq!(index, Compact(nonce));
+			assert_eq!(era, Era::mortal(64, 100));
+			assert_eq!(xt.function, call(header));
+
+			// signed against the block the era begins with
+			let payload = (Compact(nonce), call(header), era, Hash::repeat_byte(100));
+			assert!(payload.using_encoded(|payload| signature.verify(payload, &account)));
+		}
+	}
+
+	#[test]
+	fn applied_calls_should_be_read_from_the_storage() {
+		let mut io = IoHandler::new();
+		io.add_method("state_getStorage", |_| -> jsonrpc_core::Result<Value> {
+			Ok(json!(Bytes(5u64.encode())))
+		});
+		let (_server, url) = serve(io);
+
+		let mut target = DarwiniaTarget::new(&url, sr25519::Pair::from_string("//Alice", None).unwrap());
+		let header = Call::EthRelay(eth_relay::Call::relay_header(vec![1], vec![]));
+		assert!(target.is_applied(&header).unwrap());
+		// a value other than `true` is not redeemed
+		let proof = eth_relay::EthReceiptProof { block_hash: Hash::repeat_byte(1), index: 0, proof: vec![] };
+		assert!(!target.is_applied(&Call::EthBridge(eth_bridge::Call::redeem(proof))).unwrap());
+	}
+}
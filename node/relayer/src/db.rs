@@ -0,0 +1,141 @@
+//! The progress of the relayer, persisted so that a restart resumes where it stopped without
+//! submitting anything twice.
+
+use std::sync::Arc;
+use kvdb::{DBTransaction, KeyValueDB};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use node_runtime::Call;
+use parity_codec::{Decode, Encode};
+use primitive_types::H256;
+use crate::error::{Error, Result};
+
+/// The next header to relay and the next block whose locks to redeem.
+const COL_META: Option<u32> = Some(0);
+/// The hashes of the relayed headers, by number.
+const COL_HEADERS: Option<u32> = Some(1);
+/// The submitted calls not known to be applied yet, by hash.
+const COL_CALLS: Option<u32> = Some(2);
+pub const NUM_COLUMNS: u32 = 3;
+
+const NEXT_HEADER: &[u8] = b"next_header";
+const NEXT_LOCKS: &[u8] = b"next_locks";
+const NEXT_SEQUENCE: &[u8] = b"next_sequence";
+
+/// A call submitted to the target chain, not known to be applied yet.
+#[derive(Encode, Decode)]
+pub struct Pending {
+	/// The order of the first submission of the call among the others.
+	pub sequence: u64,
+	/// The block of the target chain from which the last submission can no longer be included.
+	pub death: u64,
+	/// The number of submissions of the call.
+	pub submissions: u32,
+	pub call: Call,
+}
+
+/// The progress of the relayer in a key-value database.
+pub struct Progress {
+	db: Arc<dyn KeyValueDB>,
+}
+
+impl Progress {
+	/// Open the RocksDB database at `path`, creating it if needed.
+	pub fn open(path: &str) -> Result<Self> {
+		let config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
+		Ok(Progress::new(Arc::new(Database::open(&config, path)?)))
+	}
+
+	/// The progress in `db`, which has `NUM_COLUMNS` columns.
+	pub fn new(db: Arc<dyn KeyValueDB>) -> Self {
+		Progress { db }
+	}
+
+	fn number(&self, key: &[u8]) -> Result<Option<u64>> {
+		Ok(self.db.get(COL_META, key)?.map(|v| {
+			let mut bytes = [0; 8];
+			bytes.copy_from_slice(&v);
+			u64::from_be_bytes(bytes)
+		}))
+	}
+
+	/// The number of the next header to relay, if any was relayed.
+	pub fn next_header(&self) -> Result<Option<u64>> {
+		self.number(NEXT_HEADER)
+	}
+
+	/// The number of the next block whose locks to redeem, if any was looked at.
+	pub fn next_locks(&self) -> Result<Option<u64>> {
+		self.number(NEXT_LOCKS)
+	}
+
+	/// The hash of the relayed header `number`.
+	pub fn relayed(&self, number: u64) -> Result<Option<H256>> {
+		Ok(self.db.get(COL_HEADERS, &number.to_be_bytes())?.map(|v| H256::from_slice(&v)))
+	}
+
+	/// Record that header `number` of `hash` was relayed.
+	pub fn set_relayed(&self, number: u64, hash: H256) -> Result<()> {
+		let mut tx = DBTransaction::new();
+		tx.put(COL_HEADERS, &number.to_be_bytes(), hash.as_bytes());
+		tx.put(COL_META, NEXT_HEADER, &(number + 1).to_be_bytes());
+		Ok(self.db.write(tx)?)
+	}
+
+	/// Relay headers again from `number`, after a reorganization.
+	pub fn rewind(&self, number: u64) -> Result<()> {
+		let mut tx = DBTransaction::new();
+		tx.put(COL_META, NEXT_HEADER, &number.to_be_bytes());
+		if self.next_locks()?.map_or(false, |n| n > number) {
+			tx.put(COL_META, NEXT_LOCKS, &number.to_be_bytes());
+		}
+		Ok(self.db.write(tx)?)
+	}
+
+	/// Record that the locks of the blocks before `number` were redeemed.
+	pub fn set_next_locks(&self, number: u64) -> Result<()> {
+		let mut tx = DBTransaction::new();
+		tx.put(COL_META, NEXT_LOCKS, &number.to_be_bytes());
+		Ok(self.db.write(tx)?)
+	}
+
+	/// Whether the call of `hash` is pending.
+	pub fn is_pending(&self, hash: &[u8; 32]) -> Result<bool> {
+		Ok(self.db.get(COL_CALLS, hash)?.is_some())
+	}
+
+	/// The pending calls and their hashes, in the order they were first submitted.
+	pub fn pending(&self) -> Result<Vec<([u8; 32], Pending)>> {
+		let mut pending = Vec::new();
+		for (key, value) in self.db.iter(COL_CALLS) {
+			let mut hash = [0; 32];
+			hash.copy_from_slice(&key);
+			let call = Pending::decode(&mut &value[..]).ok_or_else(|| Error::Invalid("pending call".into()))?;
+			pending.push((hash, call));
+		}
+		pending.sort_by_key(|(_, call)| call.sequence);
+		Ok(pending)
+	}
+
+	/// Record that `call` of `hash` was submitted for the first time, and dies at `death`.
+	pub fn add_pending(&self, hash: &[u8; 32], call: Call, death: u64) -> Result<()> {
+		let sequence = self.number(NEXT_SEQUENCE)?.unwrap_or(0);
+		let mut tx = DBTransaction::new();
+		tx.put(COL_CALLS, hash, &Pending { sequence, death, submissions: 1, call }.encode());
+		tx.put(COL_META, NEXT_SEQUENCE, &(sequence + 1).to_be_bytes());
+		Ok(self.db.write(tx)?)
+	}
+
+	/// Record the pending call of `hash` again, submitted again.
+	pub fn set_pending(&self, hash: &[u8; 32], pending: &Pending) -> Result<()> {
+		let mut tx = DBTransaction::new();
+		tx.put(COL_CALLS, hash, &pending.encode());
+		Ok(self.db.write(tx)?)
+	}
+
+	/// Forget the pending call of `hash`, applied or given up.
+	pub fn remove_pending(&self, hash: &[u8; 32]) -> Result<()> {
+		let mut tx = DBTransaction::new();
+		tx.delete(COL_CALLS, hash);
+		Ok(self.db.write(tx)?)
+	}
+}
@@ -0,0 +1,53 @@
+//! Errors of the relayer.
+
+use std::{fmt, io};
+
+/// Result of the relayer.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error of the relayer.
+#[derive(Debug)]
+pub enum Error {
+	/// The HTTP request to a node failed.
+	Http(reqwest::Error),
+	/// A node answered a JSON-RPC call with an error.
+	Rpc(String),
+	/// A node answered with unexpected JSON.
+	Json(serde_json::Error),
+	/// The local database failed.
+	Db(io::Error),
+	/// A node answered with inconsistent data.
+	Invalid(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Http(e) => write!(f, "HTTP error: {}", e),
+			Error::Rpc(e) => write!(f, "RPC error: {}", e),
+			Error::Json(e) => write!(f, "JSON error: {}", e),
+			Error::Db(e) => write!(f, "database error: {}", e),
+			Error::Invalid(e) => write!(f, "invalid data: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+	fn from(e: reqwest::Error) -> Self {
+		Error::Http(e)
+	}
+}
+
+impl From<serde_json::Error> for Error {
+	fn from(e: serde_json::Error) -> Self {
+		Error::Json(e)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Db(e)
+	}
+}
@@ -0,0 +1,252 @@
+//! Ethash datasets, generated to prove the elements mixed by the seals of the relayed headers
+//! against the dataset roots of the Ethereum relay module.
+//!
+//! Only the light cache of an epoch and the Merkle tree of its dataset above the subtrees of
+//! `SUBTREE_LEAVES` elements are kept: the elements of a subtree are generated again from the
+//! light cache to prove one of them.
+
+use eth_relay::{ethash, DatasetProof, EthHeader};
+use primitive_types::{H256, H512};
+use sr_io::keccak_256;
+
+/// Bytes of the light cache of the first epoch.
+const CACHE_BYTES_INIT: u64 = 1 << 24;
+
+/// Bytes the light cache grows by each epoch.
+const CACHE_BYTES_GROWTH: u64 = 1 << 17;
+
+/// Bytes of a light cache item, and of a dataset node.
+const HASH_BYTES: usize = 64;
+
+/// 32 bit words of a light cache item.
+const HASH_WORDS: usize = HASH_BYTES / 4;
+
+/// Rounds of the generation of the light cache.
+const CACHE_ROUNDS: usize = 3;
+
+/// Light cache items mixed into a dataset node.
+const DATASET_PARENTS: u32 = 256;
+
+/// Dataset elements under the lowest nodes of the tree kept.
+const SUBTREE_LEAVES: u64 = 16;
+
+/// The Ethash dataset of an epoch.
+pub struct Dataset {
+	epoch: u64,
+	full_size: u64,
+	cache: Vec<[u32; HASH_WORDS]>,
+	/// The levels of the Merkle tree, from the roots of the subtrees up to the root.
+	levels: Vec<Vec<H256>>,
+}
+
+impl Dataset {
+	/// Generate the dataset of `epoch`, which takes minutes.
+	pub fn generate(epoch: u64) -> Self {
+		Self::with_sizes(epoch, cache_size(epoch), ethash::dataset_size(epoch))
+	}
+
+	fn with_sizes(epoch: u64, cache_size: u64, full_size: u64) -> Self {
+		let mut dataset = Dataset {
+			epoch,
+			full_size,
+			cache: light_cache(&seed_hash(epoch), cache_size),
+			levels: Vec::new(),
+		};
+
+		let depth = ethash::dataset_depth(full_size);
+		let subtrees = (1u64 << depth) / SUBTREE_LEAVES;
+		let mut level: Vec<H256> = (0..subtrees).map(|subtree| dataset.subtree(subtree).0).collect();
+		while level.len() > 1 {
+			let parents = level.chunks(2).map(|pair| ethash::node_hash(&pair[0], &pair[1])).collect();
+			dataset.levels.push(level);
+			level = parents;
+		}
+		dataset.levels.push(level);
+		dataset
+	}
+
+	/// The epoch of the dataset.
+	pub fn epoch(&self) -> u64 {
+		self.epoch
+	}
+
+	/// The Merkle root of the dataset.
+	pub fn root(&self) -> H256 {
+		self.levels[self.levels.len() - 1][0]
+	}
+
+	/// The proofs of the elements the seal of `header`, in the epoch of the dataset, mixes.
+	pub fn proofs(&self, header: &EthHeader) -> Option<Vec<DatasetProof>> {
+		let (_, nonce) = header.ethash_seal()?;
+		let mut proofs = Vec::with_capacity(ethash::ACCESSES);
+		ethash::hashimoto(&header.bare_hash, &nonce, self.full_size, |_, index| {
+			let proof = self.proof(index);
+			let mut element = [0u8; ethash::MIX_BYTES];
+			element[..HASH_BYTES].copy_from_slice(proof.nodes.0.as_bytes());
+			element[HASH_BYTES..].copy_from_slice(proof.nodes.1.as_bytes());
+			proofs.push(proof);
+			Some(element)
+		})?;
+		Some(proofs)
+	}
+
+	/// The proof of the element at `index`.
+	fn proof(&self, index: u64) -> DatasetProof {
+		let (_, subtree_levels) = self.subtree(index / SUBTREE_LEAVES);
+		let mut position = index % SUBTREE_LEAVES;
+		let mut siblings = Vec::new();
+		for level in &subtree_levels {
+			siblings.push(level[(position ^ 1) as usize]);
+			position /= 2;
+		}
+
+		let mut position = (index / SUBTREE_LEAVES) as usize;
+		for level in &self.levels[..self.levels.len() - 1] {
+			siblings.push(level[position ^ 1]);
+			position /= 2;
+		}
+
+		DatasetProof {
+			nodes: (H512(self.node(2 * index as u32)), H512(self.node(2 * index as u32 + 1))),
+			siblings,
+		}
+	}
+
+	/// The root of the subtree `subtree` and its levels, from the leaves up, below the root.
+	fn subtree(&self, subtree: u64) -> (H256, Vec<Vec<H256>>) {
+		let elements = self.full_size / ethash::MIX_BYTES as u64;
+		let first = subtree * SUBTREE_LEAVES;
+		let mut level: Vec<H256> = (first..first + SUBTREE_LEAVES).map(|index| {
+			if index < elements {
+				let mut element = [0u8; ethash::MIX_BYTES];
+				element[..HASH_BYTES].copy_from_slice(&self.node(2 * index as u32));
+				element[HASH_BYTES..].copy_from_slice(&self.node(2 * index as u32 + 1));
+				ethash::element_hash(&element)
+			} else {
+				H256::zero()
+			}
+		}).collect();
+
+		let mut levels = Vec::new();
+		while level.len() > 1 {
+			let parents = level.chunks(2).map(|pair| ethash::node_hash(&pair[0], &pair[1])).collect();
+			levels.push(level);
+			level = parents;
+		}
+		(level[0], levels)
+	}
+
+	/// The 64 byte dataset node `i`.
+	fn node(&self, i: u32) -> [u8; HASH_BYTES] {
+		let n = self.cache.len();
+		let mut mix = self.cache[i as usize % n];
+		mix[0] ^= i;
+		let mut mix = words(&ethash::keccak_512(&bytes(&mix)));
+		for j in 0..DATASET_PARENTS {
+			let parent = &self.cache[ethash::fnv(i ^ j, mix[j as usize % HASH_WORDS]) as usize % n];
+			for (word, parent_word) in mix.iter_mut().zip(parent.iter()) {
+				*word = ethash::fnv(*word, *parent_word);
+			}
+		}
+		ethash::keccak_512(&bytes(&mix))
+	}
+}
+
+/// The bytes of the light cache of `epoch`.
+fn cache_size(epoch: u64) -> u64 {
+	let mut size = CACHE_BYTES_INIT + CACHE_BYTES_GROWTH * epoch - HASH_BYTES as u64;
+	while !ethash::is_prime(size / HASH_BYTES as u64) {
+		size -= 2 * HASH_BYTES as u64;
+	}
+	size
+}
+
+/// The seed of the light cache of `epoch`.
+fn seed_hash(epoch: u64) -> [u8; 32] {
+	let mut seed = [0u8; 32];
+	for _ in 0..epoch {
+		seed = keccak_256(&seed);
+	}
+	seed
+}
+
+/// The light cache of `size` bytes generated from `seed`.
+fn light_cache(seed: &[u8; 32], size: u64) -> Vec<[u32; HASH_WORDS]> {
+	let n = (size / HASH_BYTES as u64) as usize;
+	let mut cache = Vec::with_capacity(n);
+	let mut item = ethash::keccak_512(seed);
+	for _ in 0..n {
+		cache.push(item);
+		item = ethash::keccak_512(&item);
+	}
+
+	for _ in 0..CACHE_ROUNDS {
+		for i in 0..n {
+			let other = ethash::le_word(&cache[i], 0) as usize % n;
+			let mut mixed = [0u8; HASH_BYTES];
+			for (byte, (a, b)) in mixed.iter_mut().zip(cache[(i + n - 1) % n].iter().zip(cache[other].iter())) {
+				*byte = a ^ b;
+			}
+			cache[i] = ethash::keccak_512(&mixed);
+		}
+	}
+
+	cache.iter().map(|item| words(item)).collect()
+}
+
+fn words(bytes: &[u8; HASH_BYTES]) -> [u32; HASH_WORDS] {
+	let mut words = [0u32; HASH_WORDS];
+	for (i, word) in words.iter_mut().enumerate() {
+		*word = ethash::le_word(bytes, i);
+	}
+	words
+}
+
+fn bytes(words: &[u32; HASH_WORDS]) -> [u8; HASH_BYTES] {
+	let mut bytes = [0u8; HASH_BYTES];
+	for (chunk, word) in bytes.chunks_mut(4).zip(words.iter()) {
+		chunk.copy_from_slice(&word.to_le_bytes());
+	}
+	bytes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A dataset of 37 elements, generated from a light cache of 17 items.
+	fn small_dataset() -> Dataset {
+		Dataset::with_sizes(0, 17 * HASH_BYTES as u64, 37 * ethash::MIX_BYTES as u64)
+	}
+
+	#[test]
+	fn light_caches_should_be_sized() {
+		assert_eq!(cache_size(0), 16_776_896);
+		assert_eq!(cache_size(1), 16_907_456);
+		assert_eq!(seed_hash(0), [0u8; 32]);
+		assert_eq!(seed_hash(2), keccak_256(&keccak_256(&[0u8; 32])));
+	}
+
+	#[test]
+	fn proofs_should_lead_to_the_root() {
+		let dataset = small_dataset();
+		assert_eq!(dataset.levels.len(), 3);
+
+		for index in 0..37 {
+			let proof = dataset.proof(index);
+			assert_eq!(proof.siblings.len(), 6);
+
+			let mut element = proof.nodes.0.as_bytes().to_vec();
+			element.extend_from_slice(proof.nodes.1.as_bytes());
+			let mut hash = ethash::element_hash(&element);
+			for (level, sibling) in proof.siblings.iter().enumerate() {
+				hash = if (index >> level) & 1 == 0 {
+					ethash::node_hash(&hash, sibling)
+				} else {
+					ethash::node_hash(sibling, &hash)
+				};
+			}
+			assert_eq!(hash, dataset.root());
+		}
+	}
+}
@@ -0,0 +1,286 @@
+//! Ethereum as a source chain, watched through the JSON-RPC endpoint of a node.
+//!
+//! Headers are relayed to the Ethereum relay module, RLP encoded from the fields of the blocks,
+//! with the proofs of the Ethash dataset elements their seals mix.
+//! The receipts of a block are all fetched when one of its transactions emitted an event of a
+//! locking contract, and the trie of the receipts is rebuilt to prove that one.
+
+use std::cell::RefCell;
+use node_runtime::Call;
+use primitive_types::{H160, H256, U256};
+use primitives::Bytes;
+use rlp::RlpStream;
+use serde::Deserialize;
+use serde_json::json;
+use sr_io::keccak_256;
+use eth_relay::{ethash::EPOCH_LENGTH, DatasetProof, EthHeader, EthReceiptProof};
+use crate::error::{Error, Result};
+use crate::ethash::Dataset;
+use crate::relayer::{Source, SourceBlock};
+use crate::rpc::RpcClient;
+use crate::trie;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcBlock {
+	hash: H256,
+	parent_hash: H256,
+	sha3_uncles: H256,
+	miner: H160,
+	state_root: H256,
+	transactions_root: H256,
+	receipts_root: H256,
+	logs_bloom: Bytes,
+	difficulty: U256,
+	number: U256,
+	gas_limit: U256,
+	gas_used: U256,
+	timestamp: U256,
+	extra_data: Bytes,
+	mix_hash: H256,
+	nonce: Bytes,
+	/// The hashes of the transactions.
+	transactions: Vec<H256>,
+}
+
+impl RpcBlock {
+	/// The RLP encoded header of the block, with its Ethash seal.
+	fn header(&self) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(15);
+		stream
+			.append(&self.parent_hash)
+			.append(&self.sha3_uncles)
+			.append(&self.miner)
+			.append(&self.state_root)
+			.append(&self.transactions_root)
+			.append(&self.receipts_root)
+			.append(&self.logs_bloom.0)
+			.append(&self.difficulty)
+			.append(&self.number)
+			.append(&self.gas_limit)
+			.append(&self.gas_used)
+			.append(&self.timestamp)
+			.append(&self.extra_data.0)
+			.append(&self.mix_hash)
+			.append(&self.nonce.0);
+		stream.out()
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcReceipt {
+	/// The state root after the transaction, before Byzantium.
+	root: Option<H256>,
+	/// Whether the transaction succeeded, after Byzantium.
+	status: Option<U256>,
+	cumulative_gas_used: U256,
+	logs_bloom: Bytes,
+	logs: Vec<RpcLog>,
+}
+
+#[derive(Deserialize)]
+struct RpcLog {
+	address: H160,
+	topics: Vec<H256>,
+	data: Bytes,
+}
+
+impl RpcReceipt {
+	/// The RLP encoded receipt, as in the trie of the receipts of its block.
+	fn encode(&self) -> Result<Vec<u8>> {
+		let mut stream = RlpStream::new_list(4);
+		match (&self.status, &self.root) {
+			(Some(status), _) => stream.append(status),
+			(None, Some(root)) => stream.append(root),
+			(None, None) => return Err(Error::Invalid("receipt without status nor state root".into())),
+		};
+		stream.append(&self.cumulative_gas_used).append(&self.logs_bloom.0);
+		stream.begin_list(self.logs.len());
+		for log in &self.logs {
+			stream.begin_list(3);
+			stream.append(&log.address);
+			stream.append_list::<H256, _>(&log.topics);
+			stream.append(&log.data.0);
+		}
+		Ok(stream.out())
+	}
+
+	/// Whether the transaction succeeded and `contract` emitted an event of `topic`.
+	fn emitted(&self, contract: Option<H160>, topic: [u8; 32]) -> bool {
+		self.status != Some(U256::zero()) && self.logs.iter().any(|log| {
+			Some(log.address) == contract && log.topics.first() == Some(&H256(topic))
+		})
+	}
+}
+
+/// An Ethereum node, relaying its headers and redeeming the events of its locking contracts.
+pub struct EthereumSource {
+	rpc: RpcClient,
+	/// The contract of the Ethereum bridge module, emitting `Locked` events.
+	locker: Option<H160>,
+	/// The contract of the NFT bridge module, emitting `NftLocked` events.
+	nft_locker: Option<H160>,
+	/// Whether the seals of the headers are proved, for a relay module verifying them.
+	seal_proofs: bool,
+	/// The datasets of the last two epochs, as the locks of a block are redeemed after the
+	/// headers of the next epoch are relayed.
+	datasets: RefCell<Vec<Dataset>>,
+}
+
+impl EthereumSource {
+	/// Watch the node at `url`, redeeming the events of `locker` and `nft_locker`, and proving
+	/// the seals of the headers if `seal_proofs`.
+	pub fn new(url: &str, locker: Option<H160>, nft_locker: Option<H160>, seal_proofs: bool) -> Self {
+		EthereumSource {
+			rpc: RpcClient::new(url),
+			locker,
+			nft_locker,
+			seal_proofs,
+			datasets: RefCell::new(Vec::new()),
+		}
+	}
+
+	/// The proofs of the dataset elements mixed by the seal of the RLP encoded `header`,
+	/// generating the dataset of its epoch first.
+	fn dataset_proofs(&self, header: &[u8]) -> Result<Vec<DatasetProof>> {
+		let header = EthHeader::decode_rlp(header).map_err(|_| Error::Invalid("header encoding".into()))?;
+		let epoch = header.number / EPOCH_LENGTH;
+
+		let mut datasets = self.datasets.borrow_mut();
+		if datasets.iter().all(|dataset| dataset.epoch() != epoch) {
+			log::info!("Generating the Ethash dataset of epoch {}", epoch);
+			let dataset = Dataset::generate(epoch);
+			log::info!("The root of the dataset of epoch {} is {:?}", epoch, dataset.root());
+			datasets.push(dataset);
+			if datasets.len() > 2 {
+				datasets.remove(0);
+			}
+		}
+
+		datasets.iter()
+			.find(|dataset| dataset.epoch() == epoch)
+			.and_then(|dataset| dataset.proofs(&header))
+			.ok_or_else(|| Error::Invalid(format!("seal of block {}", header.number)))
+	}
+
+	fn rpc_block(&self, method: &str, id: serde_json::Value) -> Result<RpcBlock> {
+		let block: Option<RpcBlock> = self.rpc.call(method, json!([id, false]))?;
+		block.ok_or_else(|| Error::Invalid(format!("no block {}", id)))
+	}
+}
+
+impl Source for EthereumSource {
+	fn best_number(&self) -> Result<u64> {
+		let number: U256 = self.rpc.call("eth_blockNumber", json!([]))?;
+		Ok(number.low_u64())
+	}
+
+	fn block(&self, number: u64) -> Result<SourceBlock> {
+		let block = self.rpc_block("eth_getBlockByNumber", json!(format!("{:#x}", number)))?;
+		let header = block.header();
+		if H256(keccak_256(&header)) != block.hash {
+			return Err(Error::Invalid(format!("hash of the header of block {}", number)));
+		}
+
+		let dataset_proofs = if self.seal_proofs { self.dataset_proofs(&header)? } else { Vec::new() };
+		Ok(SourceBlock {
+			number,
+			hash: block.hash,
+			parent_hash: block.parent_hash,
+			relay: Some(Call::EthRelay(eth_relay::Call::relay_header(header, dataset_proofs))),
+		})
+	}
+
+	fn lock_calls(&self, block: &SourceBlock) -> Result<Vec<Call>> {
+		if self.locker.is_none() && self.nft_locker.is_none() {
+			return Ok(Vec::new());
+		}
+
+		let rpc_block = self.rpc_block("eth_getBlockByHash", json!(block.hash))?;
+		let mut receipts = Vec::with_capacity(rpc_block.transactions.len());
+		for hash in &rpc_block.transactions {
+			let receipt: Option<RpcReceipt> = self.rpc.call("eth_getTransactionReceipt", json!([hash]))?;
+			receipts.push(receipt.ok_or_else(|| Error::Invalid(format!("no receipt of {:?}", hash)))?);
+		}
+
+		let redeems: Vec<usize> = (0..receipts.len())
+			.filter(|i| receipts[*i].emitted(self.locker, eth_bridge::LOCKED_TOPIC))
+			.collect();
+		let receives: Vec<usize> = (0..receipts.len())
+			.filter(|i| receipts[*i].emitted(self.nft_locker, nft_bridge::NFT_LOCKED_TOPIC))
+			.collect();
+		if redeems.is_empty() && receives.is_empty() {
+			return Ok(Vec::new());
+		}
+
+		let encoded = receipts.iter().map(RpcReceipt::encode).collect::<Result<Vec<_>>>()?;
+		let entries = trie::ordered_entries(&encoded);
+		if trie::root(&entries) != rpc_block.receipts_root {
+			return Err(Error::Invalid(format!("receipts of block {}", block.number)));
+		}
+
+		let proof = |index: usize| EthReceiptProof {
+			block_hash: block.hash,
+			index: index as u64,
+			proof: trie::proof(&entries, &entries[index].0),
+		};
+		let redeems = redeems.into_iter().map(|i| Call::EthBridge(eth_bridge::Call::redeem(proof(i))));
+		let receives = receives.into_iter().map(|i| Call::NftBridge(nft_bridge::Call::receive(proof(i))));
+		Ok(redeems.chain(receives).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+	use crate::mock::*;
+	use super::*;
+
+	#[test]
+	fn headers_should_encode() {
+		let chain = Arc::new(Mutex::new(mainnet_chain()));
+		let (_server, url) = serve_ethereum(chain.clone());
+		let source = EthereumSource::new(&url, None, None, false);
+
+		assert_eq!(source.best_number().unwrap(), 3);
+		let block = source.block(1).unwrap();
+		assert_eq!(block.hash, H256(MAINNET_BLOCK_1_HASH));
+		assert_eq!(block.relay, Some(Call::EthRelay(eth_relay::Call::relay_header(mainnet_block_1_rlp(), vec![]))));
+
+		// a header whose fields do not match its hash
+		chain.lock().unwrap().blocks[1]["timestamp"] = json!("0x55ba4225");
+		assert!(source.block(1).is_err());
+	}
+
+	#[test]
+	fn receipts_should_be_proved() {
+		let chain = Arc::new(Mutex::new(mainnet_chain()));
+		let (_server, url) = serve_ethereum(chain.clone());
+
+		let source = EthereumSource::new(&url, None, None, false);
+		let block = source.block(2).unwrap();
+		assert_eq!(source.lock_calls(&block).unwrap(), vec![]);
+
+		let source = EthereumSource::new(&url, Some(H160(LOCKER)), None, false);
+		let calls = source.lock_calls(&block).unwrap();
+		assert_eq!(calls.len(), 2);
+		assert_eq!(calls[0], Call::EthBridge(eth_bridge::Call::redeem(EthReceiptProof {
+			block_hash: H256(BLOCK_2_HASH),
+			index: 1,
+			proof: ring_receipt_proof(),
+		})));
+		match &calls[1] {
+			Call::EthBridge(eth_bridge::Call::redeem(proof)) => {
+				let receipts_root = H256(hex_literal::hex!("2ac1b073a8e417ad4efda1efcf85f3209035050e64a6bf864f777cb77838ff98"));
+				let receipt = eth_relay::verify_proof(&receipts_root, &[2], &proof.proof).unwrap();
+				assert_eq!(eth_relay::Receipt::decode_rlp(&receipt).unwrap().gas_used, U256::from(0x222e0));
+			}
+			call => panic!("unexpected call {:?}", call),
+		}
+
+		// a receipt which is not the one of the block
+		chain.lock().unwrap().receipts.get_mut(&transaction_hash(0)).unwrap()["cumulativeGasUsed"] = json!("0x5209");
+		assert!(source.lock_calls(&block).is_err());
+	}
+}
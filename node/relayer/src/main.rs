@@ -0,0 +1,121 @@
+//! Darwinia relayer.
+//!
+//! Follows an Ethereum node and submits its headers to the Ethereum relay module of a Darwinia
+//! node, then the proofs of the receipts locking tokens in the contracts of the bridges once their
+//! blocks are confirmed. The progress is kept in a local database, so that the relayer resumes
+//! where it stopped, and follows the reorganizations of Ethereum by relaying the new branch.
+
+mod darwinia;
+mod db;
+mod error;
+mod ethash;
+mod ethereum;
+mod relayer;
+mod rpc;
+mod trie;
+
+#[cfg(test)]
+mod mock;
+
+use std::{thread, time::Duration};
+use log::{error, info};
+use primitive_types::H160;
+use primitives::{sr25519, Pair};
+use structopt::StructOpt;
+use crate::darwinia::DarwiniaTarget;
+use crate::db::Progress;
+use crate::ethereum::EthereumSource;
+use crate::relayer::Relayer;
+
+/// Command line options of the relayer.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "darwinia-relayer")]
+struct Options {
+	/// The JSON-RPC endpoint of the Ethereum node.
+	#[structopt(long = "eth-rpc", default_value = "http://localhost:8545")]
+	eth_rpc: String,
+
+	/// The JSON-RPC endpoint of the Darwinia node.
+	#[structopt(long = "darwinia-rpc", default_value = "http://localhost:9933")]
+	darwinia_rpc: String,
+
+	/// The secret URI of the account signing the extrinsics, e.g. `//Alice`.
+	#[structopt(long = "signer")]
+	signer: String,
+
+	/// The directory of the database of the progress.
+	#[structopt(long = "base-path", default_value = "relayer-db")]
+	base_path: String,
+
+	/// The first Ethereum block to relay, following the genesis header of the relay module.
+	#[structopt(long = "start", default_value = "1")]
+	start: u64,
+
+	/// The number of Ethereum headers on top of a block before its locks are redeemed.
+	#[structopt(long = "confirmations", default_value = "12")]
+	confirmations: u64,
+
+	/// The contract of the Ethereum bridge module, emitting `Locked` events.
+	#[structopt(long = "locker", parse(try_from_str = "parse_address"))]
+	locker: Option<H160>,
+
+	/// The contract of the NFT bridge module, emitting `NftLocked` events.
+	#[structopt(long = "nft-locker", parse(try_from_str = "parse_address"))]
+	nft_locker: Option<H160>,
+
+	/// Relay the headers without the proofs of their seals, to a relay module not verifying them.
+	///
+	/// Proving the seals generates the Ethash dataset of each epoch, which takes minutes, and
+	/// keeps the last two in a few hundred MB of memory.
+	#[structopt(long = "no-seal-proofs")]
+	no_seal_proofs: bool,
+
+	/// The seconds to wait for new Ethereum blocks, or after an error.
+	#[structopt(long = "interval", default_value = "15")]
+	interval: u64,
+}
+
+fn parse_address(s: &str) -> Result<H160, String> {
+	s.trim_start_matches("0x").parse().map_err(|_| format!("invalid address {}", s))
+}
+
+fn main() {
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+	let options = Options::from_args();
+
+	let signer = match sr25519::Pair::from_string(&options.signer, None) {
+		Ok(signer) => signer,
+		Err(e) => {
+			error!("Invalid signer: {:?}", e);
+			std::process::exit(1);
+		}
+	};
+	let progress = match Progress::open(&options.base_path) {
+		Ok(progress) => progress,
+		Err(e) => {
+			error!("Cannot open the database: {}", e);
+			std::process::exit(1);
+		}
+	};
+
+	info!("Relaying {} to {}", options.eth_rpc, options.darwinia_rpc);
+	let mut relayer = Relayer::new(
+		EthereumSource::new(&options.eth_rpc, options.locker, options.nft_locker, !options.no_seal_proofs),
+		DarwiniaTarget::new(&options.darwinia_rpc, signer),
+		progress,
+		options.start,
+		options.confirmations,
+	);
+
+	let interval = Duration::from_secs(options.interval);
+	loop {
+		match relayer.step() {
+			Ok(true) => {}
+			Ok(false) => thread::sleep(interval),
+			Err(e) => {
+				error!("{}", e);
+				thread::sleep(interval);
+			}
+		}
+	}
+}
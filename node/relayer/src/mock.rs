@@ -0,0 +1,238 @@
+//! Mock JSON-RPC servers and Ethereum fixtures for tests.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use hex_literal::hex;
+use jsonrpc_core::{IoHandler, Params};
+use jsonrpc_http_server::{Server, ServerBuilder};
+use primitive_types::H256;
+use serde_json::{json, Value};
+
+pub const LOCKER: [u8; 20] = hex!("b52fbe2b925ab79a821b261c82c5ba0814aaa5e0");
+
+pub const MAINNET_BLOCK_1_HASH: [u8; 32] = hex!("88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6");
+pub const BLOCK_2_HASH: [u8; 32] = hex!("e6025fe3585f9e7ed6129084b04e9f187c471f4ae9b3492dc379ba988ffeb03d");
+
+const EMPTY_UNCLES: &str = "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347";
+const EMPTY_ROOT: &str = "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+const STATE_ROOT: &str = "0xd67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3";
+
+/// Serve `io` over HTTP on a free local port, returning the server and its URL.
+pub fn serve(io: IoHandler) -> (Server, String) {
+	let server = ServerBuilder::new(io)
+		.start_http(&"127.0.0.1:0".parse().expect("valid address; qed"))
+		.expect("a free port is available");
+	let url = format!("http://{}", server.address());
+	(server, url)
+}
+
+/// The blocks and receipts served by a mock Ethereum node.
+#[derive(Default)]
+pub struct MockEthereum {
+	/// The canonical blocks, by number.
+	pub blocks: Vec<Value>,
+	/// Receipts, by transaction hash.
+	pub receipts: BTreeMap<H256, Value>,
+}
+
+/// Serve `chain` like the JSON-RPC endpoint of an Ethereum node.
+pub fn serve_ethereum(chain: Arc<Mutex<MockEthereum>>) -> (Server, String) {
+	let mut io = IoHandler::new();
+
+	let c = chain.clone();
+	io.add_method("eth_blockNumber", move |_| -> jsonrpc_core::Result<Value> {
+		Ok(json!(format!("{:#x}", c.lock().unwrap().blocks.len() - 1)))
+	});
+
+	let c = chain.clone();
+	io.add_method("eth_getBlockByNumber", move |params: Params| -> jsonrpc_core::Result<Value> {
+		let (number, _): (String, bool) = params.parse()?;
+		let number = usize::from_str_radix(number.trim_start_matches("0x"), 16)
+			.map_err(|_| jsonrpc_core::Error::invalid_params("invalid block number"))?;
+		Ok(c.lock().unwrap().blocks.get(number).cloned().unwrap_or(Value::Null))
+	});
+
+	let c = chain.clone();
+	io.add_method("eth_getBlockByHash", move |params: Params| -> jsonrpc_core::Result<Value> {
+		let (hash, _): (Value, bool) = params.parse()?;
+		Ok(c.lock().unwrap().blocks.iter().find(|b| b["hash"] == hash).cloned().unwrap_or(Value::Null))
+	});
+
+	let c = chain;
+	io.add_method("eth_getTransactionReceipt", move |params: Params| -> jsonrpc_core::Result<Value> {
+		let (hash,): (H256,) = params.parse()?;
+		Ok(c.lock().unwrap().receipts.get(&hash).cloned().unwrap_or(Value::Null))
+	});
+
+	serve(io)
+}
+
+/// A made up block of quantities `number`, `difficulty` and `timestamp`, without seal.
+fn block(hash: &str, parent_hash: &str, number: u64, difficulty: u64, timestamp: u64, extra_data: &str) -> Value {
+	json!({
+		"hash": hash,
+		"parentHash": parent_hash,
+		"sha3Uncles": EMPTY_UNCLES,
+		"miner": format!("0x{}", "00".repeat(20)),
+		"stateRoot": STATE_ROOT,
+		"transactionsRoot": EMPTY_ROOT,
+		"receiptsRoot": EMPTY_ROOT,
+		"logsBloom": format!("0x{}", "00".repeat(256)),
+		"difficulty": format!("{:#x}", difficulty),
+		"number": format!("{:#x}", number),
+		"gasLimit": "0x1388",
+		"gasUsed": "0x0",
+		"timestamp": format!("{:#x}", timestamp),
+		"extraData": extra_data,
+		"mixHash": format!("0x{}", "00".repeat(32)),
+		"nonce": format!("0x{}", "00".repeat(8)),
+		"transactions": [],
+	})
+}
+
+pub fn mainnet_block_1() -> Value {
+	json!({
+		"hash": "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+		"parentHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+		"sha3Uncles": EMPTY_UNCLES,
+		"miner": "0x05a56e2d52c817161883f50c441c3228cfe54d9f",
+		"stateRoot": STATE_ROOT,
+		"transactionsRoot": EMPTY_ROOT,
+		"receiptsRoot": EMPTY_ROOT,
+		"logsBloom": format!("0x{}", "00".repeat(256)),
+		"difficulty": "0x3ff800000",
+		"number": "0x1",
+		"gasLimit": "0x1388",
+		"gasUsed": "0x0",
+		"timestamp": "0x55ba4224",
+		"extraData": "0x476574682f76312e302e302f6c696e75782f676f312e342e32",
+		"mixHash": "0x969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59",
+		"nonce": "0x539bd4979fef1ec4",
+		"transactions": [],
+	})
+}
+
+/// Block 2 of the fixtures of the Ethereum bridge module: a transfer, a transaction locking
+/// 10 RING and one locking 5 KTON. Its transactions root is left empty.
+pub fn block_2() -> Value {
+	let mut b = block(
+		"0xe6025fe3585f9e7ed6129084b04e9f187c471f4ae9b3492dc379ba988ffeb03d",
+		"0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+		2,
+		0x3ff001000,
+		0x55ba4241,
+		"0x",
+	);
+	b["receiptsRoot"] = json!("0x2ac1b073a8e417ad4efda1efcf85f3209035050e64a6bf864f777cb77838ff98");
+	b["transactions"] = json!((0..3).map(transaction_hash).collect::<Vec<_>>());
+	b
+}
+
+pub fn block_3() -> Value {
+	block(
+		"0xa8c5fc21344b6336df55d5e2de8aa62e228499220f4bcdf55cfc382ad3e2658b",
+		"0xe6025fe3585f9e7ed6129084b04e9f187c471f4ae9b3492dc379ba988ffeb03d",
+		3,
+		0x3fe802004,
+		0x55ba4260,
+		"0x",
+	)
+}
+
+/// Blocks 1 to 3 of a fork of the mainnet genesis.
+pub fn fork_blocks() -> Vec<Value> {
+	vec![
+		block(
+			"0x47226125333f364a5d2c5625469ce2ec2dc41b02ab56d2a6b042fc67515d5068",
+			"0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+			1,
+			0x3ff001000,
+			0x55ba4226,
+			"0x666f726b",
+		),
+		block(
+			"0x3ca2cebc341e6ab8ebbba6ca9aa92904095f5322da26b03417b21b356b353dab",
+			"0x47226125333f364a5d2c5625469ce2ec2dc41b02ab56d2a6b042fc67515d5068",
+			2,
+			0x3ff001000,
+			0x55ba4244,
+			"0x666f726b",
+		),
+		block(
+			"0xa250bf51512bfa31fe4cd6ff68027308045c016b2f169fbdbf8aca87a38343d5",
+			"0x3ca2cebc341e6ab8ebbba6ca9aa92904095f5322da26b03417b21b356b353dab",
+			3,
+			0x3ff001000,
+			0x55ba4262,
+			"0x666f726b",
+		),
+	]
+}
+
+/// The hash of the transaction at `index` of block 2.
+pub fn transaction_hash(index: u8) -> H256 {
+	H256([0x20 + index; 32])
+}
+
+/// The receipts of block 2, by transaction hash.
+pub fn block_2_receipts() -> BTreeMap<H256, Value> {
+	let locked = |token: &str, value: &str| json!({
+		"address": format!("0x{}", hex::encode(LOCKER)),
+		"topics": [
+			"0x1e7b27577112ed83d53de87b38aee59ab80d8a9ba4acd90aad6cfee917534c79",
+			format!("0x000000000000000000000000{}", token),
+			"0x0000000000000000000000001111111111111111111111111111111111111111",
+			"0x2a00000000000000000000000000000000000000000000000000000000000000",
+		],
+		"data": value,
+	});
+	let receipt = |index: u8, gas: &str, logs: Vec<Value>| (transaction_hash(index), json!({
+		"transactionHash": transaction_hash(index),
+		"transactionIndex": format!("{:#x}", index),
+		"status": "0x1",
+		"cumulativeGasUsed": gas,
+		"logsBloom": format!("0x{}", "00".repeat(256)),
+		"logs": logs,
+	}));
+
+	vec![
+		receipt(0, "0x5208", vec![]),
+		receipt(1, "0x13880", vec![locked(
+			"9469d013805bffb7d3debe5e7839237e535ec483",
+			"0x0000000000000000000000000000000000000000000000008ac7230489e80000",
+		)]),
+		receipt(2, "0x222e0", vec![locked(
+			"9f284e1337a815fe77d2ff4ae46544645b20c5ff",
+			"0x0000000000000000000000000000000000000000000000004563918244f40000",
+		)]),
+	].into_iter().collect()
+}
+
+/// The proof of the receipt locking RING in block 2, as in the fixtures of the Ethereum bridge
+/// module.
+pub fn ring_receipt_proof() -> Vec<Vec<u8>> {
+	vec![
+		hex!("f851a0e71b919b65aecd4368e4e64194b0aa959078e9281a3e0b1137af059327b4643f80808080808080a0e58215be848c1293dd381210359d84485553000a82b67410406d183b42adbbdd8080808080808080").to_vec(),
+		hex!("f85180a0ed321b28582bf9b7bb2bf7229e383ca67efb796c31f44d437917e15e28eb96d1a06fbcbb5716e2aeb71e5b82119f57c2987e6900dd502a9a52f619984d908d8c308080808080808080808080808080").to_vec(),
+		hex!("f901cf20b901cbf901c80183013880b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f8bef8bc94b52fbe2b925ab79a821b261c82c5ba0814aaa5e0f884a01e7b27577112ed83d53de87b38aee59ab80d8a9ba4acd90aad6cfee917534c79a00000000000000000000000009469d013805bffb7d3debe5e7839237e535ec483a00000000000000000000000001111111111111111111111111111111111111111a02a00000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000008ac7230489e80000").to_vec(),
+	]
+}
+
+/// The RLP encoding of mainnet block 1.
+pub fn mainnet_block_1_rlp() -> Vec<u8> {
+	hex!("f90211a0d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d493479405a56e2d52c817161883f50c441c3228cfe54d9fa0d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008503ff80000001821388808455ba422499476574682f76312e302e302f6c696e75782f676f312e342e32a0969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f5988539bd4979fef1ec4").to_vec()
+}
+
+/// A mock Ethereum node serving mainnet block 1 on top of block 0, and blocks 2 and 3.
+pub fn mainnet_chain() -> MockEthereum {
+	// the headers relayed start from block 1, only the hash of the genesis is known
+	let genesis = json!({
+		"hash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+		"number": "0x0",
+	});
+
+	MockEthereum {
+		blocks: vec![genesis, mainnet_block_1(), block_2(), block_3()],
+		receipts: block_2_receipts(),
+	}
+}
@@ -0,0 +1,316 @@
+//! The relay loop: headers of the source chain are relayed in order, then the locks of the
+//! blocks are redeemed once confirmed by enough headers on top of them.
+//!
+//! A submitted call stays pending until it is applied on the target chain. One whose submission
+//! died without being applied is submitted again, up to `MAX_SUBMISSIONS` times.
+
+use node_runtime::Call;
+use parity_codec::Encode;
+use primitive_types::H256;
+use sr_io::blake2_256;
+use crate::db::Progress;
+use crate::error::Result;
+
+/// The submissions of a call before it is given up, as failing on the target chain.
+const MAX_SUBMISSIONS: u32 = 3;
+
+/// A block of the source chain.
+pub struct SourceBlock {
+	pub number: u64,
+	pub hash: H256,
+	pub parent_hash: H256,
+	/// The call relaying the header of the block, if the target chain needs it.
+	pub relay: Option<Call>,
+}
+
+/// A chain whose headers and locks are relayed.
+pub trait Source {
+	/// The number of the best block.
+	fn best_number(&self) -> Result<u64>;
+
+	/// The canonical block `number`.
+	fn block(&self, number: u64) -> Result<SourceBlock>;
+
+	/// The calls redeeming the locks in `block`.
+	fn lock_calls(&self, block: &SourceBlock) -> Result<Vec<Call>>;
+}
+
+/// A chain to which the calls are submitted.
+pub trait Target {
+	/// The number of the best block.
+	fn best_number(&mut self) -> Result<u64>;
+
+	/// Whether `call` took effect, by whomever it was submitted: its header relayed, or its
+	/// receipt redeemed.
+	fn is_applied(&mut self, call: &Call) -> Result<bool>;
+
+	/// Submit `call`, signed by the relayer, returning the block from which it can no longer be
+	/// included.
+	fn submit(&mut self, call: Call) -> Result<u64>;
+}
+
+/// Relays the headers and locks of `source` to `target`.
+pub struct Relayer<S, T> {
+	source: S,
+	target: T,
+	progress: Progress,
+	/// The first block to relay, following the genesis header of the target relay module.
+	start: u64,
+	/// The number of headers on top of a block before its locks are redeemed.
+	confirmations: u64,
+}
+
+impl<S: Source, T: Target> Relayer<S, T> {
+	pub fn new(source: S, target: T, progress: Progress, start: u64, confirmations: u64) -> Self {
+		Relayer { source, target, progress, start, confirmations }
+	}
+
+	/// Relay a header or the locks of a block, returning whether there was anything to do.
+	pub fn step(&mut self) -> Result<bool> {
+		self.resubmit_dead()?;
+
+		let next_header = self.progress.next_header()?.unwrap_or(self.start);
+		let next_locks = self.progress.next_locks()?.unwrap_or(self.start);
+
+		if next_locks + self.confirmations < next_header {
+			let block = self.source.block(next_locks)?;
+			if self.progress.relayed(next_locks)? != Some(block.hash) {
+				log::info!("Block #{} was reorganized, relaying again from it", next_locks);
+				self.progress.rewind(next_locks)?;
+				return Ok(true);
+			}
+
+			for call in self.source.lock_calls(&block)? {
+				self.submit_once(call)?;
+			}
+			self.progress.set_next_locks(next_locks + 1)?;
+			return Ok(true);
+		}
+
+		if next_header > self.source.best_number()? {
+			return Ok(false);
+		}
+
+		let block = self.source.block(next_header)?;
+		if next_header > self.start {
+			let parent = self.progress.relayed(next_header - 1)?;
+			if parent != Some(block.parent_hash) {
+				log::info!("Block #{} was reorganized, relaying again from it", next_header - 1);
+				self.progress.rewind(next_header - 1)?;
+				return Ok(true);
+			}
+		}
+
+		if let Some(call) = block.relay {
+			log::info!("Relaying header #{} ({:?})", block.number, block.hash);
+			self.submit_once(call)?;
+		}
+		self.progress.set_relayed(block.number, block.hash)?;
+		Ok(true)
+	}
+
+	/// Submit `call` unless it is pending or was applied, before a restart or a reorganization.
+	fn submit_once(&mut self, call: Call) -> Result<()> {
+		let hash = blake2_256(&call.encode());
+		if self.progress.is_pending(&hash)? || self.target.is_applied(&call)? {
+			return Ok(());
+		}
+
+		let death = self.target.submit(call.clone())?;
+		self.progress.add_pending(&hash, call, death)
+	}
+
+	/// Once their last submission can no longer be included, forget the pending calls which were
+	/// applied, and submit the others again, in order.
+	fn resubmit_dead(&mut self) -> Result<()> {
+		let pending = self.progress.pending()?;
+		if pending.is_empty() {
+			return Ok(());
+		}
+
+		let best = self.target.best_number()?;
+		for (hash, mut pending) in pending {
+			if best + 1 < pending.death {
+				continue;
+			}
+
+			if self.target.is_applied(&pending.call)? {
+				self.progress.remove_pending(&hash)?;
+			} else if pending.submissions >= MAX_SUBMISSIONS {
+				log::warn!("Giving up call {:?}, not applied after {} submissions", H256(hash), pending.submissions);
+				self.progress.remove_pending(&hash)?;
+			} else {
+				log::info!("Submitting again call {:?}, not applied", H256(hash));
+				pending.death = self.target.submit(pending.call.clone())?;
+				pending.submissions += 1;
+				self.progress.set_pending(&hash, &pending)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+	use kvdb::KeyValueDB;
+	use primitive_types::H160;
+	use crate::db::NUM_COLUMNS;
+	use crate::ethereum::EthereumSource;
+	use crate::mock::*;
+	use super::*;
+
+	/// The calls submitted to the target, those applied and its best block.
+	#[derive(Default)]
+	struct TestChain {
+		submitted: Vec<Call>,
+		applied: Vec<Call>,
+		best: u64,
+	}
+
+	struct TestTarget(Arc<Mutex<TestChain>>);
+
+	impl Target for TestTarget {
+		fn best_number(&mut self) -> Result<u64> {
+			Ok(self.0.lock().unwrap().best)
+		}
+
+		fn is_applied(&mut self, call: &Call) -> Result<bool> {
+			Ok(self.0.lock().unwrap().applied.contains(call))
+		}
+
+		/// The submissions die after 10 blocks.
+		fn submit(&mut self, call: Call) -> Result<u64> {
+			let mut chain = self.0.lock().unwrap();
+			chain.submitted.push(call);
+			Ok(chain.best + 10)
+		}
+	}
+
+	fn run(relayer: &mut Relayer<EthereumSource, TestTarget>) {
+		while relayer.step().unwrap() {}
+	}
+
+	fn relayed_hashes(calls: &[Call]) -> Vec<H256> {
+		calls.iter().filter_map(|call| match call {
+			Call::EthRelay(eth_relay::Call::relay_header(header, _)) => Some(H256(sr_io::keccak_256(header))),
+			_ => None,
+		}).collect()
+	}
+
+	#[test]
+	fn relay_should_work() {
+		let chain = Arc::new(Mutex::new(mainnet_chain()));
+		let (_server, url) = serve_ethereum(chain.clone());
+		let db: Arc<dyn KeyValueDB> = Arc::new(kvdb_memorydb::create(NUM_COLUMNS));
+		let target = Arc::new(Mutex::new(TestChain::default()));
+
+		let relayer = |target: &Arc<Mutex<TestChain>>| Relayer::new(
+			EthereumSource::new(&url, Some(H160(LOCKER)), None, false),
+			TestTarget(target.clone()),
+			Progress::new(db.clone()),
+			1,
+			1,
+		);
+		run(&mut relayer(&target));
+
+		let calls = target.lock().unwrap().submitted.clone();
+		assert_eq!(calls.len(), 5);
+		assert_eq!(calls[0], Call::EthRelay(eth_relay::Call::relay_header(mainnet_block_1_rlp(), vec![])));
+		let blocks = chain.lock().unwrap().blocks.clone();
+		let hashes: Vec<H256> = blocks[1..].iter()
+			.map(|b| serde_json::from_value(b["hash"].clone()).unwrap())
+			.collect();
+		assert_eq!(relayed_hashes(&calls), hashes);
+		// the locks of block 2 are redeemed once block 3 confirms it
+		assert_eq!(calls[3], Call::EthBridge(eth_bridge::Call::redeem(eth_relay::EthReceiptProof {
+			block_hash: H256(BLOCK_2_HASH),
+			index: 1,
+			proof: ring_receipt_proof(),
+		})));
+		match &calls[4] {
+			Call::EthBridge(eth_bridge::Call::redeem(proof)) => assert_eq!(proof.index, 2),
+			call => panic!("unexpected call {:?}", call),
+		}
+
+		// nothing is submitted again after a restart
+		let restarted = Arc::new(Mutex::new(TestChain::default()));
+		run(&mut relayer(&restarted));
+		assert!(restarted.lock().unwrap().submitted.is_empty());
+	}
+
+	#[test]
+	fn dead_calls_should_be_submitted_again() {
+		let chain = Arc::new(Mutex::new(mainnet_chain()));
+		let (_server, url) = serve_ethereum(chain.clone());
+		let target = Arc::new(Mutex::new(TestChain::default()));
+		let mut relayer = Relayer::new(
+			EthereumSource::new(&url, Some(H160(LOCKER)), None, false),
+			TestTarget(target.clone()),
+			Progress::new(Arc::new(kvdb_memorydb::create(NUM_COLUMNS))),
+			1,
+			1,
+		);
+		run(&mut relayer);
+
+		// every call but the last redemption of block 2 was applied
+		let calls = std::mem::replace(&mut target.lock().unwrap().submitted, Vec::new());
+		assert_eq!(calls.len(), 5);
+		target.lock().unwrap().applied = calls[..4].to_vec();
+
+		// still alive until block 9
+		target.lock().unwrap().best = 8;
+		run(&mut relayer);
+		assert!(target.lock().unwrap().submitted.is_empty());
+
+		target.lock().unwrap().best = 9;
+		run(&mut relayer);
+		assert_eq!(target.lock().unwrap().submitted, vec![calls[4].clone()]);
+
+		// given up after three submissions
+		for best in &[19, 29, 39] {
+			target.lock().unwrap().best = *best;
+			run(&mut relayer);
+		}
+		assert_eq!(target.lock().unwrap().submitted, vec![calls[4].clone(), calls[4].clone()]);
+		assert!(relayer.progress.pending().unwrap().is_empty());
+
+		// applied calls are not submitted again after a reorganization
+		relayer.progress.rewind(1).unwrap();
+		target.lock().unwrap().submitted.clear();
+		run(&mut relayer);
+		assert_eq!(target.lock().unwrap().submitted, vec![calls[4].clone()]);
+	}
+
+	#[test]
+	fn relay_should_follow_reorganizations() {
+		let chain = Arc::new(Mutex::new(mainnet_chain()));
+		chain.lock().unwrap().blocks.truncate(3);
+		let (_server, url) = serve_ethereum(chain.clone());
+		let target = Arc::new(Mutex::new(TestChain::default()));
+		let mut relayer = Relayer::new(
+			EthereumSource::new(&url, None, None, false),
+			TestTarget(target.clone()),
+			Progress::new(Arc::new(kvdb_memorydb::create(NUM_COLUMNS))),
+			1,
+			1,
+		);
+		run(&mut relayer);
+		assert_eq!(
+			relayed_hashes(&target.lock().unwrap().submitted),
+			vec![H256(MAINNET_BLOCK_1_HASH), H256(BLOCK_2_HASH)]
+		);
+
+		// a longer fork of the genesis replaces blocks 1 and 2
+		let fork = fork_blocks();
+		chain.lock().unwrap().blocks.truncate(1);
+		chain.lock().unwrap().blocks.extend(fork.clone());
+		target.lock().unwrap().submitted.clear();
+		run(&mut relayer);
+
+		let fork_hashes: Vec<H256> = fork.iter()
+			.map(|b| serde_json::from_value(b["hash"].clone()).unwrap())
+			.collect();
+		assert_eq!(relayed_hashes(&target.lock().unwrap().submitted), fork_hashes);
+	}
+}
@@ -0,0 +1,62 @@
+//! A blocking JSON-RPC client over HTTP, for Ethereum and Substrate nodes alike.
+
+use std::cell::Cell;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use crate::error::{Error, Result};
+
+#[derive(Serialize)]
+struct Request<'a> {
+	jsonrpc: &'static str,
+	id: u64,
+	method: &'a str,
+	params: Value,
+}
+
+#[derive(Deserialize)]
+struct Response {
+	result: Option<Value>,
+	error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+	code: i64,
+	message: String,
+}
+
+/// A client of the JSON-RPC endpoint of a node.
+pub struct RpcClient {
+	url: String,
+	client: reqwest::Client,
+	next_id: Cell<u64>,
+}
+
+impl RpcClient {
+	/// A client of the endpoint at `url`.
+	pub fn new(url: &str) -> Self {
+		RpcClient {
+			url: url.to_owned(),
+			client: reqwest::Client::new(),
+			next_id: Cell::new(0),
+		}
+	}
+
+	/// Call `method` with `params`, a JSON array.
+	pub fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+		let id = self.next_id.get();
+		self.next_id.set(id + 1);
+
+		let response: Response = self.client.post(&self.url)
+			.json(&Request { jsonrpc: "2.0", id, method, params })
+			.send()?
+			.error_for_status()?
+			.json()?;
+
+		if let Some(e) = response.error {
+			return Err(Error::Rpc(format!("{}: {} ({})", method, e.message, e.code)));
+		}
+		Ok(serde_json::from_value(response.result.unwrap_or(Value::Null))?)
+	}
+}
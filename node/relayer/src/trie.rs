@@ -0,0 +1,159 @@
+//! Merkle-Patricia tries, as Ethereum commits to the receipts of a block, and proofs of their
+//! values, as the Ethereum relay module verifies them.
+
+use primitive_types::H256;
+use rlp::RlpStream;
+use sr_io::keccak_256;
+
+/// The keys of `values` in the trie of a block: their RLP encoded index.
+pub fn ordered_entries(values: &[Vec<u8>]) -> Vec<(Vec<u8>, Vec<u8>)> {
+	values.iter().enumerate().map(|(i, value)| {
+		let mut key = RlpStream::new();
+		key.append(&(i as u64));
+		(key.out(), value.clone())
+	}).collect()
+}
+
+/// The root of the trie of `entries`.
+pub fn root(entries: &[(Vec<u8>, Vec<u8>)]) -> H256 {
+	if entries.is_empty() {
+		// the empty string
+		return H256(keccak_256(&[0x80]));
+	}
+
+	let mut proof = Vec::new();
+	H256(keccak_256(&build(&nibbled(entries), None, &mut proof)))
+}
+
+/// The RLP encoded nodes on the path to the value of `key` in the trie of `entries`, from the
+/// root. Nodes shorter than 32 bytes are embedded in their parent.
+pub fn proof(entries: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> Vec<Vec<u8>> {
+	let target = to_nibbles(key);
+	let mut proof = Vec::new();
+	let root = build(&nibbled(entries), Some(&target), &mut proof);
+	if root.len() < 32 {
+		proof.push(root);
+	}
+	proof.reverse();
+	proof
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+fn nibbled(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<(Vec<u8>, &[u8])> {
+	entries.iter().map(|(key, value)| (to_nibbles(key), &value[..])).collect()
+}
+
+/// The hex-prefix encoding of the path of a leaf or extension node.
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+	let flag = if is_leaf { 0x20 } else { 0 };
+	let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+	let rest = if nibbles.len() % 2 == 1 {
+		encoded.push(flag | 0x10 | nibbles[0]);
+		&nibbles[1..]
+	} else {
+		encoded.push(flag);
+		nibbles
+	};
+	encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+	encoded
+}
+
+/// Append a reference to `child` to its parent: the node itself if it is shorter than 32
+/// bytes, its hash otherwise.
+fn append_child(stream: &mut RlpStream, child: &[u8]) {
+	if child.len() < 32 {
+		stream.append_raw(child, 1);
+	} else {
+		stream.append(&H256(keccak_256(child)));
+	}
+}
+
+/// The RLP encoded node of `entries`, keyed by the rest of their path. The nodes of at least 32
+/// bytes on the way to `target` are pushed to `proof`, children first.
+fn build(entries: &[(Vec<u8>, &[u8])], target: Option<&[u8]>, proof: &mut Vec<Vec<u8>>) -> Vec<u8> {
+	let mut stream;
+	if let [(path, value)] = entries {
+		stream = RlpStream::new_list(2);
+		stream.append(&hex_prefix(path, true));
+		stream.append(&value.to_vec());
+	} else {
+		let first = &entries[0].0;
+		let shared = (0..first.len())
+			.take_while(|i| entries.iter().all(|(path, _)| path.len() > *i && path[*i] == first[*i]))
+			.count();
+
+		if shared > 0 {
+			let rest: Vec<_> = entries.iter().map(|(path, value)| (path[shared..].to_vec(), *value)).collect();
+			let target = target.filter(|t| t.starts_with(&first[..shared])).map(|t| &t[shared..]);
+			let child = build(&rest, target, proof);
+
+			stream = RlpStream::new_list(2);
+			stream.append(&hex_prefix(&first[..shared], false));
+			append_child(&mut stream, &child);
+		} else {
+			stream = RlpStream::new_list(17);
+			for nibble in 0..16u8 {
+				let rest: Vec<_> = entries.iter()
+					.filter(|(path, _)| path.first() == Some(&nibble))
+					.map(|(path, value)| (path[1..].to_vec(), *value))
+					.collect();
+				if rest.is_empty() {
+					stream.append_empty_data();
+				} else {
+					let target = target.filter(|t| t.first() == Some(&nibble)).map(|t| &t[1..]);
+					append_child(&mut stream, &build(&rest, target, proof));
+				}
+			}
+			match entries.iter().find(|(path, _)| path.is_empty()) {
+				Some((_, value)) => stream.append(&value.to_vec()),
+				None => stream.append_empty_data(),
+			};
+		}
+	}
+
+	let node = stream.out();
+	if target.is_some() && node.len() >= 32 {
+		proof.push(node.clone());
+	}
+	node
+}
+
+#[cfg(test)]
+mod tests {
+	use hex_literal::hex;
+	use super::*;
+
+	fn dogs() -> Vec<(Vec<u8>, Vec<u8>)> {
+		vec![
+			(b"doe".to_vec(), b"reindeer".to_vec()),
+			(b"dog".to_vec(), b"puppy".to_vec()),
+			(b"dogglesworth".to_vec(), b"cat".to_vec()),
+		]
+	}
+
+	#[test]
+	fn roots_should_match_the_ethereum_tests() {
+		// the `dogs` trie of the Ethereum trie tests, and the empty trie
+		assert_eq!(root(&dogs()), H256(hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")));
+		assert_eq!(root(&[]), H256(hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")));
+	}
+
+	#[test]
+	fn proofs_should_verify() {
+		let entries = dogs();
+		let root = root(&entries);
+		for (key, value) in &entries {
+			assert_eq!(eth_relay::verify_proof(&root, key, &proof(&entries, key)), Ok(value.clone()));
+		}
+
+		let values: Vec<Vec<u8>> = (0..200u32).map(|i| i.to_be_bytes().to_vec()).collect();
+		let entries = ordered_entries(&values);
+		let root = super::root(&entries);
+		for (key, value) in entries.iter().step_by(7) {
+			assert_eq!(eth_relay::verify_proof(&root, key, &proof(&entries, key)), Ok(value.clone()));
+		}
+	}
+}
@@ -0,0 +1,80 @@
+//! Runtime APIs backing the Darwinia specific RPCs.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{Codec, Decode, Encode};
+use rstd::prelude::*;
+
+pub use dsupport::breakdown::{BalancesBreakdown, CurrencyBreakdown, LockBreakdown};
+
+/// The role of a key in the `SessionKeys` of the runtime.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum SessionKeyType {
+	/// The ed25519 key GRANDPA votes with.
+	Grandpa,
+	/// The ed25519 key Aura blocks are sealed with, and heartbeats are signed with.
+	Aura,
+	/// The sr25519 key BABE slots are claimed with.
+	Babe,
+}
+
+client::decl_runtime_apis! {
+	/// Query what an account holds of RING and KTON and how much of it can be spent.
+	pub trait BalancesBreakdownApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Free, reserved, locked, vesting and transferable balances of `who`.
+		fn balances_breakdown(who: AccountId) -> BalancesBreakdown<Balance, BlockNumber>;
+	}
+
+	/// Read the on-chain randomness beacon.
+	pub trait RandomnessApi<Hash> where
+		Hash: Codec,
+	{
+		/// Random value for `subject` at the queried block.
+		fn random(subject: Vec<u8>) -> Hash;
+
+		/// The revealed randomness of request `id`, if it is fulfilled and not forgotten.
+		fn fulfilled(id: u64) -> Option<Hash>;
+	}
+
+	/// Prove the messages of RING and KTON burnt to be unlocked on Ethereum.
+	pub trait EthOutboundApi<Proof> where
+		Proof: Codec,
+	{
+		/// The message of `nonce` and its proof against the root of the range of `mmr_size`
+		/// nodes, if the message was in it.
+		fn redeem_proof(nonce: u64, mmr_size: u64) -> Option<Proof>;
+	}
+
+	/// Prove the messages of NFTs burnt to be unlocked or minted on another chain.
+	pub trait NftBridgeApi<Proof> where
+		Proof: Codec,
+	{
+		/// The message of `nonce` and its proof against the root of the range of `mmr_size`
+		/// nodes, if the message was in it.
+		fn message_proof(nonce: u64, mmr_size: u64) -> Option<Proof>;
+	}
+
+	/// Prove the messages sent from Darwinia to be delivered on another chain.
+	pub trait MessagesApi<Proof> where
+		Proof: Codec,
+	{
+		/// The message of `nonce` and its proof against the root of the range of `mmr_size`
+		/// nodes, if the message was in it.
+		fn message_proof(nonce: u64, mmr_size: u64) -> Option<Proof>;
+	}
+
+	/// Build the `SessionKeys` of the runtime out of keys generated by the node.
+	pub trait SessionKeysApi {
+		/// The roles of the keys in `SessionKeys`, in order.
+		fn session_key_types() -> Vec<SessionKeyType>;
+
+		/// Encoded `SessionKeys` made of the raw public `keys`, given in the order of
+		/// `session_key_types`, or `None` if they do not fit.
+		fn encode_session_keys(keys: Vec<Vec<u8>>) -> Option<Vec<u8>>;
+	}
+}
@@ -0,0 +1,58 @@
+//! RPC interface for the RING and KTON balances of accounts.
+
+use std::sync::Arc;
+
+use client::blockchain::HeaderBackend;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::{AccountId, Balance, Block, BlockNumber};
+use node_rpc_runtime_api::{BalancesBreakdown, BalancesBreakdownApi};
+use sr_primitives::{generic::BlockId, traits::{Block as BlockT, ProvideRuntimeApi}};
+
+const RUNTIME_ERROR: i64 = 1;
+
+/// Balances RPC methods.
+#[rpc]
+pub trait BalancesApi<BlockHash> {
+	/// Free, reserved, locked, vesting and transferable RING and KTON of `who`,
+	/// at the given block or at the best block.
+	#[rpc(name = "balances_breakdown")]
+	fn balances_breakdown(
+		&self,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<BalancesBreakdown<Balance, BlockNumber>>;
+}
+
+/// An implementation of balances specific RPC methods.
+pub struct Balances<C> {
+	client: Arc<C>,
+}
+
+impl<C> Balances<C> {
+	/// Create new `Balances` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Balances { client }
+	}
+}
+
+impl<C> BalancesApi<<Block as BlockT>::Hash> for Balances<C>
+where
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: BalancesBreakdownApi<Block, AccountId, Balance, BlockNumber>,
+{
+	fn balances_breakdown(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<BalancesBreakdown<Balance, BlockNumber>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.balances_breakdown(&at, who).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query the balances breakdown.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -0,0 +1,6 @@
+#![warn(missing_docs)]
+
+//! Darwinia specific RPC methods, served next to the Substrate ones by the node.
+
+pub mod balances;
+pub mod session;
@@ -0,0 +1,108 @@
+//! RPC interface for rotating the session keys of a validator node.
+
+use std::sync::Arc;
+
+use client::blockchain::HeaderBackend;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_primitives::Block;
+use node_rpc_runtime_api::{SessionKeyType, SessionKeysApi};
+use primitives::{crypto::{DeriveJunction, Pair}, ed25519, sr25519, Bytes};
+use sr_primitives::{generic::BlockId, traits::ProvideRuntimeApi};
+
+const RUNTIME_ERROR: i64 = 1;
+const KEYSTORE_ERROR: i64 = 2;
+
+/// The junction the GRANDPA key is derived from the authority key with.
+pub const GRANDPA_JUNCTION: &str = "grandpa";
+
+/// The GRANDPA key of a node with the given authority key, `<authority key>//grandpa`.
+pub fn grandpa_key(authority_key: &ed25519::Pair) -> ed25519::Pair {
+	authority_key
+		.derive(std::iter::once(DeriveJunction::hard(GRANDPA_JUNCTION)))
+		.expect("hard derivation of ed25519 keys cannot fail; qed")
+}
+
+/// The junction the BABE key is derived from the authority key with.
+pub const BABE_JUNCTION: &str = "babe";
+
+/// The sr25519 BABE key of a node with the given authority key, the sr25519 key of the seed of
+/// `<authority key>//babe`, so that a node holding its authority key always holds its BABE key.
+pub fn babe_key(authority_key: &ed25519::Pair) -> sr25519::Pair {
+	let derived = authority_key
+		.derive(std::iter::once(DeriveJunction::hard(BABE_JUNCTION)))
+		.expect("hard derivation of ed25519 keys cannot fail; qed");
+	sr25519::Pair::from_seed_slice(&derived.seed()[..])
+		.expect("ed25519 and sr25519 seeds are both 32 bytes long; qed")
+}
+
+/// Where new session keys of the node are generated.
+pub trait SessionKeyStore: Send + Sync {
+	/// Generate and store a new ed25519 authority key, used by Aura and ImOnline, from which the
+	/// GRANDPA and BABE keys are derived.
+	fn generate_authority_key(&self) -> std::result::Result<ed25519::Pair, String>;
+}
+
+/// Session RPC methods.
+#[rpc]
+pub trait SessionApi {
+	/// Generate new session keys into the keystore of the node and return them encoded
+	/// as the `SessionKeys` of the runtime, ready to be passed to `session.set_keys`.
+	#[rpc(name = "author_rotateKeys")]
+	fn rotate_keys(&self) -> Result<Bytes>;
+}
+
+/// An implementation of session specific RPC methods.
+pub struct Session<C> {
+	client: Arc<C>,
+	keystore: Option<Arc<dyn SessionKeyStore>>,
+}
+
+impl<C> Session<C> {
+	/// Create new `Session` with the given reference to the client and the keystore of
+	/// the node, if it has one.
+	pub fn new(client: Arc<C>, keystore: Option<Arc<dyn SessionKeyStore>>) -> Self {
+		Session { client, keystore }
+	}
+}
+
+fn keystore_error(message: String) -> Error {
+	Error {
+		code: ErrorCode::ServerError(KEYSTORE_ERROR),
+		message: "Unable to generate session keys.".into(),
+		data: Some(message.into()),
+	}
+}
+
+impl<C> SessionApi for Session<C>
+where
+	C: ProvideRuntimeApi + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: SessionKeysApi<Block>,
+{
+	fn rotate_keys(&self) -> Result<Bytes> {
+		let keystore = self.keystore.as_ref()
+			.ok_or_else(|| keystore_error("The node has no keystore.".into()))?;
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(self.client.info().best_hash);
+		let runtime_error = |e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to build the session keys.".into(),
+			data: Some(format!("{:?}", e).into()),
+		};
+
+		let types = api.session_key_types(&at).map_err(runtime_error)?;
+		let authority_key = keystore.generate_authority_key().map_err(keystore_error)?;
+
+		// the node derives its GRANDPA and BABE keys from the authority key it runs with
+		let keys = types.iter().map(|key_type| match key_type {
+			SessionKeyType::Grandpa => grandpa_key(&authority_key).public().0.to_vec(),
+			SessionKeyType::Aura => authority_key.public().0.to_vec(),
+			SessionKeyType::Babe => babe_key(&authority_key).public().0.to_vec(),
+		}).collect();
+
+		api.encode_session_keys(&at, keys)
+			.map_err(runtime_error)?
+			.map(Into::into)
+			.ok_or_else(|| keystore_error("The runtime rejected the generated keys.".into()))
+	}
+}
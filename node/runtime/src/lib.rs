@@ -21,12 +21,13 @@
 #![recursion_limit="256"]
 
 use rstd::prelude::*;
+use parity_codec::{Decode, Encode};
 use support::{
 	construct_runtime, parameter_types, traits::{SplitTwoWays, Currency, OnUnbalanced}
 };
 use substrate_primitives::u32_trait::{_1, _2, _3, _4};
 pub use node_primitives::{
-	AccountId, AccountIndex, AuraId, Balance, BlockNumber, Hash, Nonce,
+	AccountId, AccountIndex, AuraId, AuraSignature, BabeId, Balance, BlockNumber, Hash, Nonce,
 	Moment, Signature,
 };
 use grandpa::fg_primitives::{self, ScheduledChange};
@@ -45,12 +46,14 @@ use version::RuntimeVersion;
 use version::NativeVersion;
 use substrate_primitives::OpaqueMetadata;
 use grandpa::{AuthorityId as GrandpaId, AuthorityWeight as GrandpaWeight};
+use node_rpc_runtime_api::{BalancesBreakdown, CurrencyBreakdown, SessionKeyType};
 use finality_tracker::{DEFAULT_REPORT_LATENCY, DEFAULT_WINDOW_SIZE};
 
 #[cfg(any(feature = "std", test))]
 pub use runtime_primitives::BuildStorage;
 pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
+pub use offences::{Call as OffencesCall, GrandpaJustification};
 pub use contracts::Gas;
 pub use runtime_primitives::{Permill, Perbill, impl_opaque_keys};
 pub use support::StorageValue;
@@ -63,8 +66,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node"),
 	impl_name: create_runtime_str!("darwinia-node"),
 	authoring_version: 2,
-	spec_version: 78,
-	impl_version: 78,
+	spec_version: 99,
+	impl_version: 99,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -140,9 +143,26 @@ impl system::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const AuraSlotDuration: Moment = SECS_PER_BLOCK;
+}
+
 impl aura::Trait for Runtime {
-	type HandleReport = aura::StakingSlasher<Runtime>;
+	type SlotDuration = AuraSlotDuration;
+	type HandleEquivocation = aura::OffenceReporter<Runtime, Offences>;
 	type AuthorityId = AuraId;
+	type Signature = AuraSignature;
+}
+
+parameter_types! {
+	// one epoch per session
+	pub const EpochDuration: u64 = Period::get();
+	pub const ExpectedBlockTime: Moment = SECS_PER_BLOCK;
+}
+
+impl babe::Trait for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
 }
 
 impl indices::Trait for Runtime {
@@ -168,17 +188,30 @@ impl balances::Trait for Runtime {
 }
 
 
+parameter_types! {
+	pub const MaxLocks: u32 = 32;
+	pub const MaxLockSweeps: u32 = 64;
+	pub const MaxVestingSchedules: u32 = 16;
+}
+
 impl kton::Trait for Runtime {
 	type Balance = Balance;
 	type Event = Event;
 	type OnMinted = ();
 	type OnRemoval = ();
+	type OnAccountBalanceChanged = ();
+	type MaxVestingSchedules = MaxVestingSchedules;
+	type MaxLocks = MaxLocks;
+	type MaxLockSweeps = MaxLockSweeps;
 }
 
 
+/// RING and KTON behind a single `MultiCurrency` interface.
+pub type Currencies = dsupport::traits::RingKtonCurrency<Balances, Kton>;
+
 impl timestamp::Trait for Runtime {
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	type OnTimestampSet = (Aura, Babe);
 }
 
 parameter_types! {
@@ -189,14 +222,16 @@ parameter_types! {
 	pub const TransactionByteFee: Balance = 1 * MICRO;
 }
 
-type SessionHandlers = (Grandpa, Aura);
+// Both Aura and BABE follow the session validators, the chain spec decides which of them
+// the nodes run. Heartbeats are signed with the Aura key, so ImOnline shares its slot.
+type SessionHandlers = (Grandpa, im_online::SharedKey<Runtime, Aura>, Babe);
 parameter_types! {
 	pub const UncleGenerations: u64 = 0;
 }
 
 
 impl_opaque_keys! {
-	pub struct SessionKeys(grandpa::AuthorityId, AuraId);
+	pub struct SessionKeys(grandpa::AuthorityId, AuraId, BabeId);
 }
 
 impl authorship::Trait for Runtime {
@@ -232,6 +267,16 @@ parameter_types! {
 	pub const BondingDuration: staking::EraIndex = 4032;
 	// 365 days * 24 hours * 60 miutes / 5 minutes
 	pub const ErasPerEpoch: staking::ErasNums = 63720;
+	pub const EnactmentDelay: BlockNumber = 1 * MINUTES;
+	pub const MaxEnactmentDelay: BlockNumber = 1 * HOURS;
+}
+
+/// The highest block finalized by a GRANDPA justification noted on chain.
+pub struct GrandpaFinalizedNumber;
+impl staking::FinalizedNumber<BlockNumber> for GrandpaFinalizedNumber {
+	fn finalized_number() -> Option<BlockNumber> {
+		Some(Offences::grandpa_finalized())
+	}
 }
 
 // customed
@@ -245,14 +290,19 @@ parameter_types! {
 impl staking::Trait for Runtime {
 	type Ring = Balances;
 	type Kton = Kton;
+	type Currencies = Currencies;
 	type CurrencyToVote = CurrencyToVoteHandler;
 	type Event = Event;
 	type RingReward = ();
 	type RingSlash = ();
 	type KtonReward = ();
 	type KtonSlash = ();
+	type SlashPolicy = staking::ExponentialSlash;
 	type SessionsPerEra = SessionsPerEra;
 	type BondingDuration = BondingDuration;
+	type Finality = GrandpaFinalizedNumber;
+	type EnactmentDelay = EnactmentDelay;
+	type MaxEnactmentDelay = MaxEnactmentDelay;
 	// customed
 	type Cap = CAP;
 	type ErasPerEpoch = ErasPerEpoch;
@@ -311,6 +361,140 @@ impl grandpa::Trait for Runtime {
 	type Event = Event;
 }
 
+impl offences::Trait for Runtime {
+	type Event = Event;
+	type OnOffenceHandler = Staking;
+}
+
+impl im_online::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type UncheckedExtrinsic = UncheckedExtrinsic;
+	type AuthorityId = AuraId;
+	type Signature = AuraSignature;
+	type HandleOffline = im_online::StakingSlasher<Runtime>;
+}
+
+/// The BABE epoch randomness, mixed from the VRF outputs of the previous epoch, and the
+/// randomness of the last commit-reveal round. Under Aura the BABE randomness never changes,
+/// and the commit-reveal randomness changes once a round.
+pub struct ConsensusEntropy;
+impl randomness::BlockEntropy for ConsensusEntropy {
+	fn block_entropy() -> Vec<u8> {
+		(Babe::randomness(), CommitReveal::last_randomness()).encode()
+	}
+}
+
+parameter_types! {
+	// a round of commit-reveal ends between any request and its reveal
+	pub const MinRandomnessDelay: BlockNumber = CommitPeriod::get() + RevealPeriod::get();
+	pub const MaxRandomnessDelay: BlockNumber = 1 * DAYS;
+	pub const MaxRandomnessRequestsPerBlock: u32 = 64;
+}
+
+impl randomness::Trait for Runtime {
+	type Event = Event;
+	type BlockEntropy = ConsensusEntropy;
+	type MinDelay = MinRandomnessDelay;
+	type MaxDelay = MaxRandomnessDelay;
+	type MaxRequestsPerBlock = MaxRandomnessRequestsPerBlock;
+	type OnRandomness = randomness::ContractCallback<Runtime>;
+}
+
+parameter_types! {
+	pub const CommitDeposit: Balance = 10 * COIN;
+	pub const CommitPeriod: BlockNumber = 10 * MINUTES;
+	pub const RevealPeriod: BlockNumber = 5 * MINUTES;
+	pub const MaxParticipants: u32 = 256;
+}
+
+impl commit_reveal::Trait for Runtime {
+	type Event = Event;
+	type Ring = Balances;
+	type RingSlash = ();
+	type CommitDeposit = CommitDeposit;
+	type CommitPeriod = CommitPeriod;
+	type RevealPeriod = RevealPeriod;
+	type MaxParticipants = MaxParticipants;
+}
+
+parameter_types! {
+	pub const EthVerificationLevel: eth_relay::VerificationLevel = eth_relay::VerificationLevel::Seal;
+	pub const EthashParams: eth_relay::EthashParams = eth_relay::ethash::MAINNET;
+	pub const EthConfirmations: u64 = 12;
+	/// About a week of Ethereum blocks, for the locks in them to be redeemed.
+	pub const EthHeadersToKeep: u64 = 45_000;
+}
+
+impl eth_relay::Trait for Runtime {
+	type Event = Event;
+	type VerificationLevel = EthVerificationLevel;
+	type EthashParams = EthashParams;
+	type Confirmations = EthConfirmations;
+	type HeadersToKeep = EthHeadersToKeep;
+}
+
+parameter_types! {
+	pub const BridgeProposalLifetime: BlockNumber = 1 * DAYS;
+	pub const BridgeMintPeriod: BlockNumber = 1 * DAYS;
+}
+
+impl eth_bridge::Trait for Runtime {
+	type Event = Event;
+	type Ring = Balances;
+	type Kton = Kton;
+	type EthRelay = EthRelay;
+	type ProposalLifetime = BridgeProposalLifetime;
+	type MintPeriod = BridgeMintPeriod;
+	type Cap = CAP;
+}
+
+impl eth_outbound::Trait for Runtime {
+	type Event = Event;
+	type Currency = Currencies;
+}
+
+parameter_types! {
+	pub const NftMaxMetadataLength: u32 = 1024;
+	pub const NftClassDeposit: Balance = 100 * COIN;
+	/// The RFC-0005 chain id of the tokens produced on Darwinia, above those of the chains tokens
+	/// arrive from.
+	pub const NftNativeChainId: u8 = 0x80;
+}
+
+impl nft::Trait for Runtime {
+	type Event = Event;
+	type MaxMetadataLength = NftMaxMetadataLength;
+	type Ring = Balances;
+	type ClassDeposit = NftClassDeposit;
+	type NativeChainId = NftNativeChainId;
+}
+
+parameter_types! {
+	pub const MaxMessagePayloadLength: u32 = 1024;
+	pub const CallbackGas: Gas = 100_000;
+	pub const NftArrivalFee: Balance = 10 * MILLI;
+	pub const MessageFee: Balance = 10 * MILLI;
+}
+
+impl nft_bridge::Trait for Runtime {
+	type Event = Event;
+	type EthRelay = EthRelay;
+	type OnArrival = messages::ContractCallback<Runtime, CallbackGas>;
+	type ArrivalFee = NftArrivalFee;
+	type FeePayment = DealWithFees;
+}
+
+impl messages::Trait for Runtime {
+	type Event = Event;
+	type EthRelay = EthRelay;
+	type OnInbound = messages::ContractCallback<Runtime, CallbackGas>;
+	type MaxPayloadLength = MaxMessagePayloadLength;
+	type Ring = Balances;
+	type MessageFee = MessageFee;
+	type FeePayment = DealWithFees;
+}
+
 parameter_types! {
 	pub const WindowSize: BlockNumber = DEFAULT_WINDOW_SIZE.into();
 	pub const ReportLatency: BlockNumber = DEFAULT_REPORT_LATENCY.into();
@@ -328,7 +512,8 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
 		System: system::{Module, Call, Storage, Config, Event},
-		Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
+		Aura: aura::{Module, Call, Config<T>, Inherent(Timestamp), ValidateUnsigned},
+		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
 		Timestamp: timestamp::{Module, Call, Storage, Config<T>, Inherent},
 		Authorship: authorship::{Module, Call, Storage},
 		Indices: indices,
@@ -339,6 +524,16 @@ construct_runtime!(
 		Contracts: contracts,
 		FinalityTracker: finality_tracker::{Module, Call, Inherent},
 		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		Offences: offences::{Module, Call, Storage, Event<T>, ValidateUnsigned},
+		ImOnline: im_online::{Module, Call, Storage, Event<T>, Config<T>, ValidateUnsigned},
+		Randomness: randomness::{Module, Call, Storage, Event<T>},
+		CommitReveal: commit_reveal::{Module, Call, Storage, Event<T>},
+		EthRelay: eth_relay::{Module, Call, Storage, Config, Event},
+		EthBridge: eth_bridge::{Module, Call, Storage, Config<T>, Event<T>},
+		EthOutbound: eth_outbound::{Module, Call, Storage, Event<T>},
+		Nft: nft::{Module, Call, Storage, Event<T>},
+		NftBridge: nft_bridge::{Module, Call, Storage, Event<T>},
+		Messages: messages::{Module, Call, Storage, Event<T>},
 		Sudo: sudo,
 	}
 );
@@ -433,6 +628,64 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl node_rpc_runtime_api::BalancesBreakdownApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn balances_breakdown(who: AccountId) -> BalancesBreakdown<Balance, BlockNumber> {
+			BalancesBreakdown {
+				ring: CurrencyBreakdown::new(
+					Balances::free_balance(&who),
+					Balances::reserved_balance(&who),
+					Balances::locks(&who).into_iter().map(|l| (l.id, l.amount, l.until, l.reasons)).collect(),
+					Balances::vesting_balance(&who),
+					System::block_number(),
+				),
+				kton: Kton::breakdown(&who),
+			}
+		}
+	}
+
+	impl node_rpc_runtime_api::EthOutboundApi<Block, eth_outbound::RedeemProof<AccountId, Balance>> for Runtime {
+		fn redeem_proof(nonce: u64, mmr_size: u64) -> Option<eth_outbound::RedeemProof<AccountId, Balance>> {
+			EthOutbound::redeem_proof(nonce, mmr_size)
+		}
+	}
+
+	impl node_rpc_runtime_api::NftBridgeApi<Block, nft_bridge::NftMessageProof<AccountId>> for Runtime {
+		fn message_proof(nonce: u64, mmr_size: u64) -> Option<nft_bridge::NftMessageProof<AccountId>> {
+			NftBridge::message_proof(nonce, mmr_size)
+		}
+	}
+
+	impl node_rpc_runtime_api::MessagesApi<Block, messages::OutboundMessageProof<AccountId>> for Runtime {
+		fn message_proof(nonce: u64, mmr_size: u64) -> Option<messages::OutboundMessageProof<AccountId>> {
+			Messages::message_proof(nonce, mmr_size)
+		}
+	}
+
+	impl node_rpc_runtime_api::SessionKeysApi<Block> for Runtime {
+		fn session_key_types() -> Vec<SessionKeyType> {
+			vec![SessionKeyType::Grandpa, SessionKeyType::Aura, SessionKeyType::Babe]
+		}
+
+		fn encode_session_keys(keys: Vec<Vec<u8>>) -> Option<Vec<u8>> {
+			// all the keys are 32 byte public keys, encoded as they are
+			if keys.len() != 4 || keys.iter().any(|key| key.len() != 32) {
+				return None;
+			}
+			let raw = keys.concat();
+			SessionKeys::decode(&mut &raw[..]).map(|keys| keys.encode())
+		}
+	}
+
+	impl node_rpc_runtime_api::RandomnessApi<Block, Hash> for Runtime {
+		fn random(subject: Vec<u8>) -> Hash {
+			Randomness::random(&subject)
+		}
+
+		fn fulfilled(id: u64) -> Option<Hash> {
+			Randomness::fulfilled(id).map(|(_, random)| random)
+		}
+	}
+
 	impl consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> u64 {
 			Aura::slot_duration()
@@ -441,4 +694,25 @@ impl_runtime_apis! {
 			Aura::authorities()
 		}
 	}
+
+	impl babe_primitives::BabeApi<Block> for Runtime {
+		fn startup_data() -> babe_primitives::BabeConfiguration {
+			babe_primitives::BabeConfiguration {
+				median_required_blocks: 1000,
+				slot_duration: Babe::slot_duration(),
+				// a slot has a primary author with probability 1/4
+				c: (1, 4),
+			}
+		}
+
+		fn epoch() -> babe_primitives::Epoch {
+			babe_primitives::Epoch {
+				start_slot: Babe::epoch_start_slot(),
+				authorities: Babe::authorities(),
+				epoch_index: Babe::epoch_index(),
+				randomness: Babe::randomness(),
+				duration: EpochDuration::get(),
+			}
+		}
+	}
 }
@@ -21,19 +21,27 @@
 //!
 //! ## Overview
 //!
-//! The Aura module extends Aura consensus by managing offline reporting.
+//! The Aura module keeps the rotation of Aura authorities, which the client reads through
+//! `AuraApi::authorities`. Authorities disabled by staking are left out of the rotation until
+//! the authority set changes, so they are not expected to author in any slot.
 //!
 //! ## Interface
 //!
 //! ### Public Functions
 //!
-//! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration.
+//! - `slot_duration` - The configured slot duration, at least twice the minimum period of the Timestamp module.
+//! - `check_equivocation_proof` - Check that an authority sealed two different headers for one slot.
+//!
+//! ### Dispatchable Functions
+//!
+//! - `report_equivocation` - Unsigned call reporting an [`EquivocationProof`](./struct.EquivocationProof.html),
+//!  the offender is slashed through [`HandleEquivocation`](./trait.HandleEquivocation.html).
 //!
 //! ## Related Modules
 //!
-//! - [Staking](../srml_staking/index.html): The Staking module is called in Aura to enforce slashing
-//!  if validators miss a certain number of slots (see the [`StakingSlasher`](./struct.StakingSlasher.html)
-//!  struct and associated method).
+//! - [Staking](../srml_staking/index.html): The Staking module is called in Aura to slash
+//!  equivocating validators (see the [`StakingSlasher`](./struct.StakingSlasher.html) struct),
+//!  and disables validators through the Session module.
 //! - [Timestamp](../srml_timestamp/index.html): The Timestamp module is used in Aura to track
 //! consensus rounds (via `slots`).
 //! - [Consensus](../srml_consensus/index.html): The Consensus module does not relate directly to Aura,
@@ -51,9 +59,14 @@
 pub use timestamp;
 
 use rstd::{result, prelude::*};
-use parity_codec::Encode;
-use srml_support::{decl_storage, decl_module, Parameter, storage::StorageValue};
-use primitives::{traits::{SaturatedConversion, Saturating, Zero, One, Member}, generic::DigestItem};
+use parity_codec::{Encode, Decode};
+use srml_support::{decl_storage, decl_module, ensure, Parameter, storage::StorageValue, traits::Get};
+use primitives::{
+	traits::{SaturatedConversion, Saturating, Zero, Member, Header as HeaderT, ValidateUnsigned, Verify},
+	generic::DigestItem, ApplyError,
+	transaction_validity::{TransactionValidity, TransactionPriority, TransactionLongevity},
+};
+use system::ensure_none;
 use timestamp::OnTimestampSet;
 #[cfg(feature = "std")]
 use timestamp::TimestampInherentData;
@@ -61,11 +74,11 @@ use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent
 #[cfg(feature = "std")]
 use inherents::{InherentDataProviders, ProvideInherentData};
 use substrate_consensus_aura_primitives::{AURA_ENGINE_ID, ConsensusLog};
-#[cfg(feature = "std")]
-use parity_codec::Decode;
+use dsupport::traits::{OffenceKind, ReportOffence};
 
 mod mock;
-//mod tests;
+#[cfg(test)]
+mod tests;
 
 /// The Aura inherent identifier.
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"auraslot";
@@ -139,21 +152,41 @@ impl ProvideInherentData for InherentDataProvider {
 	}
 }
 
-/// Something that can handle Aura consensus reports.
-pub trait HandleReport {
-	fn handle_report(report: AuraReport);
+/// Something that can handle a proven equivocation of an Aura authority.
+pub trait HandleEquivocation {
+	/// `authority_index` is the index of the offender among the session validators.
+	fn handle_equivocation(authority_index: usize);
 }
 
-impl HandleReport for () {
-	fn handle_report(_report: AuraReport) { }
+impl HandleEquivocation for () {
+	fn handle_equivocation(_authority_index: usize) { }
+}
+
+/// Two different headers sealed by the same authority for the same slot.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EquivocationProof<Header, AuthorityId> {
+	/// The authority which sealed both headers.
+	pub offender: AuthorityId,
+	/// The slot both headers claim.
+	pub slot: u64,
+	pub first_header: Header,
+	pub second_header: Header,
 }
 
 pub trait Trait: timestamp::Trait {
-	/// The logic for handling reports.
-	type HandleReport: HandleReport;
+	/// The duration of a slot. It is at least twice the minimum period of the Timestamp module,
+	/// so that each author can always propose within the majority of its slot.
+	type SlotDuration: Get<Self::Moment>;
+
+	/// The logic for handling proven equivocations.
+	type HandleEquivocation: HandleEquivocation;
 
 	/// The identifier type for an authority.
 	type AuthorityId: Member + Parameter + Default;
+
+	/// The signature of an authority found in the seal of a header.
+	type Signature: Verify<Signer=Self::AuthorityId> + Decode;
 }
 
 decl_storage! {
@@ -161,25 +194,85 @@ decl_storage! {
 		/// The last timestamp.
 		LastTimestamp get(last) build(|_| 0.into()): T::Moment;
 
-		/// The current authorities
+		/// The rotation of authorities: the authorities of the session which are not disabled.
 		pub Authorities get(authorities) config(): Vec<T::AuthorityId>;
+
+		/// The authorities of the session, in the order of the session validators.
+		pub SessionAuthorities get(session_authorities)
+			build(|config: &GenesisConfig<T>| config.authorities.clone()): Vec<T::AuthorityId>;
+
+		/// The first slot of the current rotation, which starts with every session and whenever
+		/// an authority is disabled. Equivocations in earlier slots, which may have been
+		/// authored by another rotation, are not reported any more.
+		pub FirstSlot get(first_slot): u64;
+
+		/// Slots of the current rotation for which an equivocation has been reported already.
+		pub ReportedSlots get(reported_slots): Vec<u64>;
+
+		/// Sorted indices among the session authorities of the authorities disabled until the
+		/// authority set changes, which are left out of the rotation.
+		pub Disabled get(disabled): Vec<u32>;
 	}
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		const SlotDuration: T::Moment = T::SlotDuration::get();
+
+		/// Report an authority for sealing two different headers in the same slot.
+		fn report_equivocation(origin, proof: EquivocationProof<T::Header, T::AuthorityId>) {
+			ensure_none(origin)?;
+			ensure!(!Self::reported_slots().contains(&proof.slot), "equivocation already reported for this slot");
+
+			let index = Self::check_equivocation_proof(&proof)?;
+			ReportedSlots::mutate(|slots| slots.push(proof.slot));
+
+			T::HandleEquivocation::handle_equivocation(Self::session_index(index));
+		}
+	}
 }
 
 impl<T: Trait> Module<T> {
 	fn change_authorities(new: Vec<T::AuthorityId>) {
-		<Authorities<T>>::put(&new);
+		<SessionAuthorities<T>>::put(&new);
+		Disabled::kill();
+		Self::change_rotation(new);
+	}
+
+	fn change_rotation(rotation: Vec<T::AuthorityId>) {
+		<Authorities<T>>::put(&rotation);
 
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(
 			AURA_ENGINE_ID,
-			ConsensusLog::AuthoritiesChange(new).encode()
+			ConsensusLog::AuthoritiesChange(rotation).encode()
 		);
 		<system::Module<T>>::deposit_log(log.into());
 	}
+
+	/// The index among the session authorities of the authority at `index` of the rotation.
+	fn session_index(index: usize) -> usize {
+		let mut session_index = index;
+		for disabled in Self::disabled() {
+			if disabled as usize > session_index {
+				break;
+			}
+			session_index += 1;
+		}
+		session_index
+	}
+
+	/// Start reporting the equivocations of a new rotation, which begins after the slot of the
+	/// last block.
+	fn start_rotation() {
+		let last = Self::last();
+		let first_slot = if last.is_zero() {
+			0
+		} else {
+			(last / Self::slot_duration()).saturated_into::<u64>() + 1
+		};
+		FirstSlot::put(first_slot);
+		ReportedSlots::kill();
+	}
 }
 
 impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
@@ -187,104 +280,174 @@ impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
 	fn on_new_session<'a, I: 'a>(changed: bool, validators: I)
 		where I: Iterator<Item=(&'a T::AccountId, T::AuthorityId)>
 	{
+		Self::start_rotation();
+
 		// instant changes
 		if changed {
 			let next_authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
-			let last_authorities = <Module<T>>::authorities();
+			let last_authorities = <Module<T>>::session_authorities();
 			if next_authorities != last_authorities {
 				Self::change_authorities(next_authorities);
 			}
 		}
 	}
-	fn on_disabled(_i: usize) {
-		// ignore?
-	}
-}
-
-/// A report of skipped authorities in Aura.
-#[derive(Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "std", derive(Debug))]
-pub struct AuraReport {
-	// The first skipped slot.
-	start_slot: usize,
-	// The number of times authorities were skipped.
-	skipped: usize,
-}
+	fn on_disabled(i: usize) {
+		let authorities = Self::session_authorities();
+		if i >= authorities.len() {
+			return;
+		}
 
-impl AuraReport {
-	/// Call the closure with (`validator_indices`, `punishment_count`) for each
-	/// validator to punish.
-	pub fn punish<F>(&self, validator_count: usize, mut punish_with: F)
-		where F: FnMut(usize, usize)
-	{
-		// If all validators have been skipped, then it implies some sort of
-		// systematic problem common to all rather than a minority of validators
-		// not fulfilling their specific duties. In this case, it doesn't make
-		// sense to punish anyone, so we guard against it.
-		if self.skipped < validator_count {
-			for index in 0..self.skipped {
-				punish_with((self.start_slot + index) % validator_count, 1);
+		let index = i as u32;
+		let mut disabled = Self::disabled();
+		if let Err(position) = disabled.binary_search(&index) {
+			// someone has to author the blocks
+			if disabled.len() + 1 >= authorities.len() {
+				runtime_io::print("the last Aura authority of the rotation is not disabled");
+				return;
 			}
-		} else {
-			runtime_io::print("ALL validators SKIP");
+
+			disabled.insert(position, index);
+			let rotation = authorities.into_iter()
+				.enumerate()
+				.filter(|(i, _)| disabled.binary_search(&(*i as u32)).is_err())
+				.map(|(_, authority)| authority)
+				.collect();
+			Disabled::put(disabled);
+
+			// the slots of the rotation belong to other authorities from the next slot on
+			Self::start_rotation();
+			Self::change_rotation(rotation);
 		}
 	}
 }
 
 impl<T: Trait> Module<T> {
-	/// Determine the Aura slot-duration based on the Timestamp module configuration.
+	/// The configured slot duration, at least twice the minimum period of the Timestamp module.
 	pub fn slot_duration() -> T::Moment {
 		// we double the minimum block-period so each author can always propose within
 		// the majority of its slot.
-		<timestamp::Module<T>>::minimum_period().saturating_mul(2.into())
+		let minimum = <timestamp::Module<T>>::minimum_period().saturating_mul(2.into());
+		rstd::cmp::max(T::SlotDuration::get(), minimum)
 	}
+}
 
-	fn on_timestamp_set<H: HandleReport>(now: T::Moment, slot_duration: T::Moment) {
-		let last = Self::last();
-		<Self as Store>::LastTimestamp::put(now.clone());
+impl<T: Trait> Module<T> {
+	/// Check `proof` against the current rotation, returning the index of the offender in it.
+	///
+	/// Only slots of the current rotation are checked, the authorities of earlier slots may
+	/// differ from the current ones.
+	pub fn check_equivocation_proof(
+		proof: &EquivocationProof<T::Header, T::AuthorityId>,
+	) -> result::Result<usize, &'static str> {
+		let authorities = Self::authorities();
+		ensure!(!authorities.is_empty(), "no authorities");
+
+		let current_slot = Self::last() / Self::slot_duration();
+		ensure!(
+			proof.slot <= current_slot.saturated_into::<u64>(),
+			"equivocation reported for a future slot"
+		);
+		ensure!(proof.slot >= Self::first_slot(), "equivocation reported for a previous session");
 
-		if last.is_zero() {
-			return;
-		}
+		let index = (proof.slot % authorities.len() as u64) as usize;
+		ensure!(authorities[index] == proof.offender, "offender is not the author of the slot");
 
-		assert!(!slot_duration.is_zero(), "Aura slot duration cannot be zero.");
+		let (first_slot, first_hash, first_signature) = Self::sealed_slot(&proof.first_header)
+			.ok_or("first header is not sealed by Aura")?;
+		let (second_slot, second_hash, second_signature) = Self::sealed_slot(&proof.second_header)
+			.ok_or("second header is not sealed by Aura")?;
 
-		let last_slot = last / slot_duration.clone();
-		let first_skipped = last_slot.clone() + One::one();
-		let cur_slot = now / slot_duration;
+		ensure!(first_slot == proof.slot && second_slot == proof.slot, "headers are not from the reported slot");
+		ensure!(first_hash != second_hash, "headers are identical");
+		ensure!(
+			first_signature.verify(&first_hash.encode()[..], &proof.offender),
+			"bad seal on first header"
+		);
+		ensure!(
+			second_signature.verify(&second_hash.encode()[..], &proof.offender),
+			"bad seal on second header"
+		);
 
-		assert!(last_slot < cur_slot, "Only one block may be authored per slot.");
-		if cur_slot == first_skipped { return }
+		Ok(index)
+	}
 
-		let skipped_slots = cur_slot - last_slot - One::one();
+	/// The slot claimed by `header`, its pre-seal hash and the signature in its seal.
+	fn sealed_slot(header: &T::Header) -> Option<(u64, T::Hash, T::Signature)> {
+		let mut header = header.clone();
+		let signature = match header.digest_mut().pop() {
+			Some(DigestItem::Seal(id, seal)) if id == AURA_ENGINE_ID =>
+				T::Signature::decode(&mut &seal[..])?,
+			_ => return None,
+		};
+		let slot = header.digest().logs().iter().filter_map(|log| match log {
+			DigestItem::PreRuntime(id, data) if *id == AURA_ENGINE_ID => u64::decode(&mut &data[..]),
+			_ => None,
+		}).next()?;
 
-		H::handle_report(AuraReport {
-			start_slot: first_skipped.saturated_into::<usize>(),
-			skipped: skipped_slots.saturated_into::<usize>(),
-		})
+		Some((slot, header.hash(), signature))
+	}
+}
+
+impl<T: Trait> ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		match call {
+			Call::report_equivocation(proof) => {
+				if Self::reported_slots().contains(&proof.slot) {
+					return TransactionValidity::Invalid(ApplyError::Stale as i8);
+				}
+				if Self::check_equivocation_proof(proof).is_err() {
+					return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
+				}
+
+				TransactionValidity::Valid {
+					priority: TransactionPriority::max_value(),
+					requires: vec![],
+					provides: vec![(AURA_ENGINE_ID, proof.slot).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+				}
+			}
+			_ => TransactionValidity::Invalid(0),
+		}
 	}
 }
 
 impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
 	fn on_timestamp_set(moment: T::Moment) {
-		Self::on_timestamp_set::<T::HandleReport>(moment, Self::slot_duration())
+		let last = Self::last();
+		<Self as Store>::LastTimestamp::put(moment.clone());
+
+		if last.is_zero() {
+			return;
+		}
+
+		let slot_duration = Self::slot_duration();
+		assert!(!slot_duration.is_zero(), "Aura slot duration cannot be zero.");
+		assert!(last / slot_duration.clone() < moment / slot_duration, "Only one block may be authored per slot.");
 	}
 }
 
-/// A type for performing slashing based on Aura reports.
+/// A type for slashing equivocating validators.
 pub struct StakingSlasher<T>(::rstd::marker::PhantomData<T>);
 
-impl<T: staking::Trait + Trait> HandleReport for StakingSlasher<T> {
-	fn handle_report(report: AuraReport) {
-		let validators = session::Module::<T>::validators();
-		runtime_io::print("OFFLINE REPORT");
-		report.punish(
-			validators.len(),
-			|idx, slash_count| {
-				let v = validators[idx].clone();
-				staking::Module::<T>::on_offline_validator(v, slash_count);
-			}
-		);
+impl<T: staking::Trait + Trait> HandleEquivocation for StakingSlasher<T> {
+	fn handle_equivocation(authority_index: usize) {
+		if let Some(v) = session::Module::<T>::validators().get(authority_index) {
+			staking::Module::<T>::on_equivocation(v.clone());
+		}
+	}
+}
+
+/// A type for passing Aura equivocations on to an offence collector `R`.
+pub struct OffenceReporter<T, R>(::rstd::marker::PhantomData<(T, R)>);
+
+impl<T: session::Trait + Trait, R: ReportOffence<T::AccountId>> HandleEquivocation for OffenceReporter<T, R> {
+	fn handle_equivocation(authority_index: usize) {
+		if let Some(v) = session::Module::<T>::validators().get(authority_index) {
+			R::report_offence(OffenceKind::AuraEquivocation, v.clone());
+		}
 	}
 }
 
@@ -18,11 +18,13 @@
 
 #![cfg(test)]
 
-use primitives::{traits::IdentityLookup, testing::{Header, UintAuthorityId}};
-use srml_support::impl_outer_origin;
+use std::cell::RefCell;
+use parity_codec::{Encode, Decode};
+use primitives::{traits::{IdentityLookup, Lazy, Verify}, testing::{Header, UintAuthorityId}};
+use srml_support::{impl_outer_origin, traits::Get};
 use runtime_io;
 use substrate_primitives::{H256, Blake2Hasher};
-use crate::{Trait, Module, GenesisConfig};
+use crate::{Trait, Module, GenesisConfig, HandleEquivocation};
 
 impl_outer_origin!{
 	pub enum Origin for Test {}
@@ -49,9 +51,52 @@ impl timestamp::Trait for Test {
 	type OnTimestampSet = Aura;
 }
 
+/// Seal of a test header: the authority and the message it signed.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct TestSignature(pub u64, pub Vec<u8>);
+
+impl Verify for TestSignature {
+	type Signer = UintAuthorityId;
+
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &UintAuthorityId) -> bool {
+		self.0 == signer.0 && &self.1[..] == msg.get()
+	}
+}
+
+thread_local! {
+	static EQUIVOCATIONS: RefCell<Vec<usize>> = RefCell::new(vec![]);
+	static SLOT_DURATION: RefCell<u64> = RefCell::new(0);
+}
+
+/// The slot duration set by the tests with `set_slot_duration`.
+pub struct TestSlotDuration;
+impl Get<u64> for TestSlotDuration {
+	fn get() -> u64 {
+		SLOT_DURATION.with(|d| *d.borrow())
+	}
+}
+
+pub fn set_slot_duration(duration: u64) {
+	SLOT_DURATION.with(|d| *d.borrow_mut() = duration);
+}
+
+/// Records the authority indices passed to `handle_equivocation`.
+pub struct EquivocationRecorder;
+impl HandleEquivocation for EquivocationRecorder {
+	fn handle_equivocation(authority_index: usize) {
+		EQUIVOCATIONS.with(|v| v.borrow_mut().push(authority_index));
+	}
+}
+
+pub fn equivocations() -> Vec<usize> {
+	EQUIVOCATIONS.with(|v| v.borrow().clone())
+}
+
 impl Trait for Test {
-	type HandleReport = ();
+	type SlotDuration = TestSlotDuration;
+	type HandleEquivocation = EquivocationRecorder;
 	type AuthorityId = UintAuthorityId;
+	type Signature = TestSignature;
 }
 
 pub fn new_test_ext(authorities: Vec<u64>) -> runtime_io::TestExternalities<Blake2Hasher> {
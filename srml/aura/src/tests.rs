@@ -0,0 +1,184 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use parity_codec::Encode;
+use primitives::{generic::DigestItem, testing::{Digest, Header, UintAuthorityId}, traits::{Header as HeaderT, ValidateUnsigned}};
+use session::OneSessionHandler;
+use primitives::transaction_validity::TransactionValidity;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok, StorageValue};
+use substrate_consensus_aura_primitives::{AURA_ENGINE_ID, ConsensusLog};
+use substrate_primitives::H256;
+use crate::{Call, EquivocationProof, LastTimestamp};
+use crate::mock::{equivocations, new_test_ext, set_slot_duration, Aura, System, Test, TestSignature};
+
+/// A header of block `number` claiming `slot`, sealed by `author`.
+fn sealed_header(number: u64, slot: u64, author: u64) -> Header {
+	let mut header = Header::new(
+		number,
+		Default::default(),
+		Default::default(),
+		H256::default(),
+		Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] },
+	);
+	let signature = TestSignature(author, header.hash().encode());
+	header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature.encode()));
+	header
+}
+
+fn proof(offender: u64, slot: u64, first: Header, second: Header) -> EquivocationProof<Header, UintAuthorityId> {
+	EquivocationProof { offender: UintAuthorityId(offender), slot, first_header: first, second_header: second }
+}
+
+#[test]
+fn equivocation_should_be_reported() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		// slot duration is 2, so the current slot is 10
+		<LastTimestamp<Test>>::put(20);
+
+		// slot 5 belongs to authority 1
+		let report = proof(1, 5, sealed_header(1, 5, 1), sealed_header(2, 5, 1));
+		assert_ok!(Aura::check_equivocation_proof(&report), 1);
+		match Aura::validate_unsigned(&Call::report_equivocation(report.clone())) {
+			TransactionValidity::Valid { .. } => {},
+			_ => panic!("a valid proof should be accepted by the pool"),
+		}
+
+		assert_ok!(Aura::report_equivocation(system::RawOrigin::None.into(), report.clone()));
+		assert_eq!(Aura::reported_slots(), vec![5]);
+		assert_eq!(equivocations(), vec![1]);
+
+		assert_noop!(
+			Aura::report_equivocation(system::RawOrigin::None.into(), report),
+			"equivocation already reported for this slot"
+		);
+	});
+}
+
+#[test]
+fn invalid_equivocation_proofs_should_be_rejected() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		// slot duration is 2, so the current slot is 10
+		<LastTimestamp<Test>>::put(20);
+
+		assert_eq!(
+			Aura::check_equivocation_proof(&proof(2, 5, sealed_header(1, 5, 2), sealed_header(2, 5, 2))),
+			Err("offender is not the author of the slot")
+		);
+		assert_eq!(
+			Aura::check_equivocation_proof(&proof(1, 5, sealed_header(1, 5, 1), sealed_header(1, 5, 1))),
+			Err("headers are identical")
+		);
+		assert_eq!(
+			Aura::check_equivocation_proof(&proof(1, 5, sealed_header(1, 5, 1), sealed_header(2, 9, 1))),
+			Err("headers are not from the reported slot")
+		);
+		assert_eq!(
+			Aura::check_equivocation_proof(&proof(1, 5, sealed_header(1, 5, 1), sealed_header(2, 5, 3))),
+			Err("bad seal on second header")
+		);
+		assert_eq!(
+			Aura::check_equivocation_proof(&proof(1, 13, sealed_header(1, 13, 1), sealed_header(2, 13, 1))),
+			Err("equivocation reported for a future slot")
+		);
+		assert!(equivocations().is_empty());
+	});
+}
+
+#[test]
+fn equivocations_of_previous_sessions_should_be_rejected() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		<LastTimestamp<Test>>::put(20);
+		let report = proof(1, 5, sealed_header(1, 5, 1), sealed_header(2, 5, 1));
+		assert_ok!(Aura::report_equivocation(system::RawOrigin::None.into(), report.clone()));
+
+		// the session starts after slot 10
+		let validators = vec![(&0, UintAuthorityId(0)), (&1, UintAuthorityId(1))];
+		Aura::on_new_session(false, validators.into_iter());
+		assert_eq!(Aura::first_slot(), 11);
+		assert!(Aura::reported_slots().is_empty());
+		assert_noop!(
+			Aura::report_equivocation(system::RawOrigin::None.into(), report),
+			"equivocation reported for a previous session"
+		);
+		match Aura::validate_unsigned(&Call::report_equivocation(
+			proof(2, 10, sealed_header(1, 10, 2), sealed_header(2, 10, 2))
+		)) {
+			TransactionValidity::Invalid(_) => {},
+			_ => panic!("a proof of a previous session should be rejected by the pool"),
+		}
+
+		<LastTimestamp<Test>>::put(30);
+		assert_ok!(Aura::check_equivocation_proof(
+			&proof(3, 15, sealed_header(1, 15, 3), sealed_header(2, 15, 3))
+		), 3);
+	});
+}
+
+#[test]
+fn disabled_authorities_should_leave_the_rotation() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		<LastTimestamp<Test>>::put(20);
+		Aura::on_disabled(1);
+		Aura::on_disabled(1);
+		Aura::on_disabled(4);
+		assert_eq!(Aura::disabled(), vec![1]);
+		assert_eq!(Aura::authorities(), vec![UintAuthorityId(0), UintAuthorityId(2), UintAuthorityId(3)]);
+
+		let log: DigestItem<H256> = DigestItem::Consensus(
+			AURA_ENGINE_ID,
+			ConsensusLog::AuthoritiesChange(Aura::authorities()).encode(),
+		);
+		assert_eq!(System::digest().logs, vec![log]);
+		// the rotation starts after slot 10
+		assert_eq!(Aura::first_slot(), 11);
+
+		// slot 14 belongs to authority 2 of the rotation, which is authority 3 of the session
+		<LastTimestamp<Test>>::put(30);
+		let report = proof(3, 14, sealed_header(1, 14, 3), sealed_header(2, 14, 3));
+		assert_ok!(Aura::check_equivocation_proof(&report), 2);
+		assert_ok!(Aura::report_equivocation(system::RawOrigin::None.into(), report));
+		assert_eq!(equivocations(), vec![3]);
+
+		// the last authority of the rotation is kept
+		Aura::on_disabled(0);
+		Aura::on_disabled(2);
+		Aura::on_disabled(3);
+		assert_eq!(Aura::disabled(), vec![0, 1, 2]);
+		assert_eq!(Aura::authorities(), vec![UintAuthorityId(3)]);
+
+		let validators = vec![(&0, UintAuthorityId(0)), (&1, UintAuthorityId(1))];
+		Aura::on_new_session(true, validators.into_iter());
+		assert!(Aura::disabled().is_empty());
+		assert_eq!(Aura::authorities(), vec![UintAuthorityId(0), UintAuthorityId(1)]);
+		assert_eq!(Aura::session_authorities(), Aura::authorities());
+	});
+}
+
+#[test]
+fn slot_duration_should_be_at_least_twice_the_minimum_period() {
+	with_externalities(&mut new_test_ext(vec![0, 1, 2, 3]), || {
+		// the minimum period is 1
+		assert_eq!(Aura::slot_duration(), 2);
+
+		set_slot_duration(6);
+		assert_eq!(Aura::slot_duration(), 6);
+	});
+}
@@ -0,0 +1,206 @@
+//! # Commit-Reveal Module
+//!
+//! Randomness which no single party, block authors included, can choose.
+//!
+//! Randomness is produced in rounds of `CommitPeriod + RevealPeriod` blocks. During the
+//! commit period, participants `commit` to a secret by posting
+//! `Hashing::hash_of(&(account, secret))`, reserving `CommitDeposit` RING. During the reveal
+//! period they `reveal` the secret and get the deposit back. At the end of the round the
+//! revealed secrets are XORed into the randomness of the round, published in
+//! `RoundRandomness` and with a `RoundRandomness` event, and the deposits of participants
+//! who did not reveal are slashed.
+//!
+//! The account is part of the commitment so that nobody can copy the commitment of another
+//! participant and reveal the same secret, cancelling it out of the XOR. The last participant
+//! to reveal can still choose between two outcomes by withholding their secret, which costs
+//! them the deposit: it has to be worth more than anything that depends on a single round.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use primitives::traits::{Hash, One};
+use rstd::prelude::*;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::traits::{Currency, Get, OnUnbalanced, ReservableCurrency};
+use system::ensure_signed;
+use dsupport::traits::Randomness;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Index of a randomness round.
+pub type RoundIndex = u32;
+
+type RingBalanceOf<T> = <<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::Balance;
+type RingNegativeImbalanceOf<T> =
+<<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The currency of the commit deposits.
+    type Ring: ReservableCurrency<Self::AccountId>;
+
+    /// Handler for the deposits of participants who did not reveal.
+    type RingSlash: OnUnbalanced<RingNegativeImbalanceOf<Self>>;
+
+    /// The deposit reserved for a commitment, returned on reveal.
+    type CommitDeposit: Get<RingBalanceOf<Self>>;
+
+    /// Blocks of a round in which commitments are accepted.
+    type CommitPeriod: Get<Self::BlockNumber>;
+
+    /// Blocks of a round, after the commit period, in which secrets are revealed.
+    type RevealPeriod: Get<Self::BlockNumber>;
+
+    /// The most participants of a round.
+    type MaxParticipants: Get<u32>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as CommitReveal {
+        /// The round in progress.
+        pub CurrentRound get(current_round): RoundIndex;
+
+        /// The first block of the round in progress.
+        pub RoundStart get(round_start): T::BlockNumber;
+
+        /// Participants of the round in progress, in the order they committed.
+        pub Participants get(participants): Vec<T::AccountId>;
+
+        /// The commitment of a participant of the round in progress.
+        pub Commitments get(commitments): map T::AccountId => Option<T::Hash>;
+
+        /// Participants of the round in progress who revealed their secret.
+        pub Revealed get(revealed): map T::AccountId => bool;
+
+        /// XOR of the secrets revealed so far in the round in progress.
+        pub Accumulator get(accumulator): T::Hash;
+
+        /// The randomness of finished rounds in which at least one secret was revealed.
+        pub RoundRandomness get(round_randomness): map RoundIndex => Option<T::Hash>;
+
+        /// The randomness of the last round in which at least one secret was revealed.
+        pub LastRandomness get(last_randomness): T::Hash;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::Hash,
+        Balance = RingBalanceOf<T>
+    {
+        /// A participant committed to a secret in the round.
+        Committed(RoundIndex, AccountId),
+        /// A participant revealed its secret in the round.
+        Revealed(RoundIndex, AccountId),
+        /// A participant did not reveal its secret in the round and lost its deposit.
+        Slashed(RoundIndex, AccountId, Balance),
+        /// The round finished with the given randomness.
+        RoundRandomness(RoundIndex, Hash),
+        /// The round finished without any secret revealed.
+        RoundFailed(RoundIndex),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const CommitDeposit: RingBalanceOf<T> = T::CommitDeposit::get();
+        const CommitPeriod: T::BlockNumber = T::CommitPeriod::get();
+        const RevealPeriod: T::BlockNumber = T::RevealPeriod::get();
+        const MaxParticipants: u32 = T::MaxParticipants::get();
+
+        fn deposit_event<T>() = default;
+
+        /// Commit to a secret for the round in progress, reserving `CommitDeposit`.
+        ///
+        /// `commitment` is `Hashing::hash_of(&(sender, secret))`.
+        fn commit(origin, commitment: T::Hash) {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_commit_period(<system::Module<T>>::block_number()), "not in the commit period");
+            ensure!(!<Commitments<T>>::exists(&who), "already committed in this round");
+
+            let mut participants = Self::participants();
+            ensure!((participants.len() as u32) < T::MaxParticipants::get(), "too many participants in this round");
+
+            T::Ring::reserve(&who, T::CommitDeposit::get())?;
+
+            participants.push(who.clone());
+            <Participants<T>>::put(participants);
+            <Commitments<T>>::insert(&who, commitment);
+
+            Self::deposit_event(RawEvent::Committed(Self::current_round(), who));
+        }
+
+        /// Reveal the secret committed to in the round in progress, getting the deposit back.
+        fn reveal(origin, secret: T::Hash) {
+            let who = ensure_signed(origin)?;
+            ensure!(!Self::is_commit_period(<system::Module<T>>::block_number()), "not in the reveal period");
+
+            let commitment = Self::commitments(&who).ok_or("not committed in this round")?;
+            ensure!(!Self::revealed(&who), "already revealed in this round");
+            ensure!(T::Hashing::hash_of(&(&who, secret)) == commitment, "secret does not match the commitment");
+
+            T::Ring::unreserve(&who, T::CommitDeposit::get());
+            <Revealed<T>>::insert(&who, true);
+            <Accumulator<T>>::mutate(|acc| *acc = *acc ^ secret);
+
+            Self::deposit_event(RawEvent::Revealed(Self::current_round(), who));
+        }
+
+        fn on_finalize(now: T::BlockNumber) {
+            if now >= Self::round_start() + T::CommitPeriod::get() + T::RevealPeriod::get() - One::one() {
+                Self::finish_round(now);
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Whether `now` is in the commit period of the round in progress.
+    pub fn is_commit_period(now: T::BlockNumber) -> bool {
+        now < Self::round_start() + T::CommitPeriod::get()
+    }
+
+    fn finish_round(now: T::BlockNumber) {
+        let round = Self::current_round();
+        let mut revealed = 0;
+
+        for who in <Participants<T>>::take() {
+            <Commitments<T>>::remove(&who);
+            if <Revealed<T>>::take(&who) {
+                revealed += 1;
+            } else {
+                let (imbalance, _) = T::Ring::slash_reserved(&who, T::CommitDeposit::get());
+                T::RingSlash::on_unbalanced(imbalance);
+                Self::deposit_event(RawEvent::Slashed(round, who, T::CommitDeposit::get()));
+            }
+        }
+
+        let randomness = <Accumulator<T>>::take();
+        if revealed > 0 {
+            <RoundRandomness<T>>::insert(round, randomness);
+            <LastRandomness<T>>::put(randomness);
+            Self::deposit_event(RawEvent::RoundRandomness(round, randomness));
+        } else {
+            Self::deposit_event(RawEvent::RoundFailed(round));
+        }
+
+        CurrentRound::put(round + 1);
+        <RoundStart<T>>::put(now + One::one());
+    }
+}
+
+impl<T: Trait> Randomness<T::Hash> for Module<T> {
+    /// Random value for `subject`, derived from the randomness of the last finished round.
+    ///
+    /// It only changes once per round, so everything depending on it must be settled
+    /// before the reveal period of the round producing it.
+    fn random(subject: &[u8]) -> T::Hash {
+        T::Hashing::hash_of(&(subject, Self::last_randomness()))
+    }
+}
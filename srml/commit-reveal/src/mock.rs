@@ -0,0 +1,86 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use crate::{Module, Trait};
+
+pub type AccountId = u64;
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 0;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+}
+
+parameter_types! {
+    pub const CommitDeposit: u64 = 10;
+    pub const CommitPeriod: u64 = 3;
+    pub const RevealPeriod: u64 = 2;
+    pub const MaxParticipants: u32 = 3;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type Ring = Balances;
+    type RingSlash = ();
+    type CommitDeposit = CommitDeposit;
+    type CommitPeriod = CommitPeriod;
+    type RevealPeriod = RevealPeriod;
+    type MaxParticipants = MaxParticipants;
+}
+
+/// Accounts 1 to 4 with 100 RING each, account 5 with 5.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(balances::GenesisConfig::<Test> {
+        balances: vec![(1, 100), (2, 100), (3, 100), (4, 100), (5, 5)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+    t.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type CommitReveal = Module<Test>;
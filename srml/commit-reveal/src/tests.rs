@@ -0,0 +1,120 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use primitives::traits::{Hash, OnFinalize};
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use substrate_primitives::H256;
+use dsupport::traits::Randomness;
+use crate::mock::{new_test_ext, AccountId, Balances, CommitReveal, Origin, System, Test};
+
+fn secret(n: u8) -> H256 {
+    H256::repeat_byte(n)
+}
+
+fn commitment(who: AccountId, n: u8) -> H256 {
+    <Test as system::Trait>::Hashing::hash_of(&(who, secret(n)))
+}
+
+/// Finalize the current block and move to the next one.
+fn next_block() {
+    CommitReveal::on_finalize(System::block_number());
+    System::set_block_number(System::block_number() + 1);
+}
+
+#[test]
+fn revealed_secrets_should_be_xored_into_the_round_randomness() {
+    with_externalities(&mut new_test_ext(), || {
+        // blocks 0 to 2 are the commit period, 3 and 4 the reveal period
+        assert_ok!(CommitReveal::commit(Origin::signed(1), commitment(1, 1)));
+        assert_ok!(CommitReveal::commit(Origin::signed(2), commitment(2, 2)));
+        assert_eq!(Balances::reserved_balance(&1), 10);
+        next_block();
+        next_block();
+        next_block();
+
+        assert_ok!(CommitReveal::reveal(Origin::signed(1), secret(1)));
+        assert_ok!(CommitReveal::reveal(Origin::signed(2), secret(2)));
+        assert_eq!(Balances::reserved_balance(&1), 0);
+        next_block();
+        assert!(CommitReveal::round_randomness(0).is_none());
+        next_block();
+
+        assert_eq!(CommitReveal::round_randomness(0), Some(secret(1) ^ secret(2)));
+        assert_eq!(CommitReveal::current_round(), 1);
+        assert_eq!(CommitReveal::round_start(), 5);
+        assert!(CommitReveal::participants().is_empty());
+        assert_ne!(
+            <CommitReveal as Randomness<H256>>::random(b"loot"),
+            <CommitReveal as Randomness<H256>>::random(b"breeding")
+        );
+    });
+}
+
+#[test]
+fn unrevealed_commitments_should_be_slashed() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(CommitReveal::commit(Origin::signed(1), commitment(1, 1)));
+        assert_ok!(CommitReveal::commit(Origin::signed(2), commitment(2, 2)));
+        for _ in 0..3 {
+            next_block();
+        }
+        assert_ok!(CommitReveal::reveal(Origin::signed(1), secret(1)));
+        next_block();
+        next_block();
+
+        assert_eq!(Balances::free_balance(&1), 100);
+        assert_eq!(Balances::free_balance(&2), 90);
+        assert_eq!(Balances::reserved_balance(&2), 0);
+        assert_eq!(CommitReveal::round_randomness(0), Some(secret(1)));
+        assert!(CommitReveal::commitments(&2).is_none());
+    });
+}
+
+#[test]
+fn round_without_reveals_should_publish_nothing() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(CommitReveal::commit(Origin::signed(1), commitment(1, 1)));
+        for _ in 0..5 {
+            next_block();
+        }
+
+        assert_eq!(CommitReveal::current_round(), 1);
+        assert!(CommitReveal::round_randomness(0).is_none());
+        assert_eq!(Balances::free_balance(&1), 90);
+    });
+}
+
+#[test]
+fn invalid_commits_and_reveals_should_be_rejected() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(CommitReveal::commit(Origin::signed(1), commitment(1, 1)));
+        assert_noop!(
+            CommitReveal::commit(Origin::signed(1), commitment(1, 2)),
+            "already committed in this round"
+        );
+        // account 5 cannot afford the deposit
+        assert!(CommitReveal::commit(Origin::signed(5), commitment(5, 5)).is_err());
+        assert_ok!(CommitReveal::commit(Origin::signed(2), commitment(2, 2)));
+        assert_ok!(CommitReveal::commit(Origin::signed(3), commitment(3, 3)));
+        assert_noop!(
+            CommitReveal::commit(Origin::signed(4), commitment(4, 4)),
+            "too many participants in this round"
+        );
+        assert_noop!(CommitReveal::reveal(Origin::signed(1), secret(1)), "not in the reveal period");
+
+        for _ in 0..3 {
+            next_block();
+        }
+        assert_noop!(CommitReveal::commit(Origin::signed(4), commitment(4, 4)), "not in the commit period");
+        assert_noop!(CommitReveal::reveal(Origin::signed(4), secret(4)), "not committed in this round");
+        // account 2 revealing the secret of account 1 does not match its commitment
+        assert_noop!(
+            CommitReveal::reveal(Origin::signed(2), secret(1)),
+            "secret does not match the commitment"
+        );
+        assert_ok!(CommitReveal::reveal(Origin::signed(1), secret(1)));
+        assert_noop!(CommitReveal::reveal(Origin::signed(1), secret(1)), "already revealed in this round");
+    });
+}
@@ -0,0 +1,118 @@
+//! The chains RING and KTON are locked on, behind `ChainAdapter`.
+
+use eth_relay::{EthReceiptProof, LogEntry, Receipt, TransactionOutcome, VerifyEthReceipts};
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256, U256};
+use rstd::{marker::PhantomData, prelude::*};
+use srml_support::ensure;
+
+use crate::{Module, Trait, DECIMALS_DIVISOR, LOCKED_TOPIC};
+
+/// A chain RING and KTON are locked on.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Chain {
+    Ethereum,
+    Tron,
+}
+
+/// A token locked on another chain.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Token {
+    Ring,
+    Kton,
+}
+
+/// `value` of `token`, in 9 decimals, locked for `who`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Lock<AccountId> {
+    pub token: Token,
+    pub who: AccountId,
+    pub value: u128,
+}
+
+/// The contracts of a chain: the one emitting `Locked` events and the tokens.
+pub struct Contracts {
+    pub locker: H160,
+    pub ring: H160,
+    pub kton: H160,
+}
+
+/// A chain from which locks of RING and KTON are redeemed.
+pub trait ChainAdapter<T: Trait> {
+    /// The chain of the locks.
+    const CHAIN: Chain;
+
+    /// The proof of a receipt of a locking transaction.
+    type Proof;
+
+    /// The block and the index in it of the receipt proved by `proof`.
+    fn receipt_id(proof: &Self::Proof) -> (H256, u64);
+
+    /// The locks in the receipt proved by `proof`.
+    fn verify_locks(proof: &Self::Proof) -> Result<Vec<Lock<T::AccountId>>, &'static str>;
+}
+
+/// Ethereum, whose receipts are verified by `T::EthRelay`.
+pub struct EthereumAdapter<T>(PhantomData<T>);
+
+impl<T: Trait> ChainAdapter<T> for EthereumAdapter<T> {
+    const CHAIN: Chain = Chain::Ethereum;
+
+    type Proof = EthReceiptProof;
+
+    fn receipt_id(proof: &EthReceiptProof) -> (H256, u64) {
+        (proof.block_hash, proof.index)
+    }
+
+    fn verify_locks(proof: &EthReceiptProof) -> Result<Vec<Lock<T::AccountId>>, &'static str> {
+        let receipt = T::EthRelay::verify_receipt(proof)?;
+        parse_locks(&receipt, &Contracts {
+            locker: <Module<T>>::locker_contract(),
+            ring: <Module<T>>::ring_contract(),
+            kton: <Module<T>>::kton_contract(),
+        })
+    }
+}
+
+/// The locks of the `Locked` events `contracts.locker` emitted in a successful `receipt`.
+pub fn parse_locks<AccountId: Decode>(
+    receipt: &Receipt,
+    contracts: &Contracts,
+) -> Result<Vec<Lock<AccountId>>, &'static str> {
+    ensure!(receipt.outcome == TransactionOutcome::Status(1), "transaction failed");
+
+    let mut locks = Vec::new();
+    for log in receipt.logs.iter().filter(|log| is_locked_event(log, &contracts.locker)) {
+        locks.push(parse_locked_event(log, contracts)?);
+    }
+    ensure!(!locks.is_empty(), "no Locked event in the receipt");
+
+    Ok(locks)
+}
+
+fn is_locked_event(log: &LogEntry, locker: &H160) -> bool {
+    log.address == *locker && log.topics.first() == Some(&H256(LOCKED_TOPIC))
+}
+
+fn parse_locked_event<AccountId: Decode>(log: &LogEntry, contracts: &Contracts) -> Result<Lock<AccountId>, &'static str> {
+    ensure!(log.topics.len() == 4 && log.data.len() == 32, "invalid Locked event");
+
+    let token = H160::from_slice(&log.topics[1].as_bytes()[12..]);
+    let token = if token == contracts.ring {
+        Token::Ring
+    } else if token == contracts.kton {
+        Token::Kton
+    } else {
+        return Err("unsupported token");
+    };
+
+    let who = AccountId::decode(&mut log.topics[3].as_bytes()).ok_or("invalid receiver")?;
+
+    let value = U256::from_big_endian(&log.data) / U256::from(DECIMALS_DIVISOR);
+    ensure!(value <= U256::from(u128::max_value()), "value overflow");
+
+    Ok(Lock { token, who, value: value.low_u128() })
+}
@@ -0,0 +1,371 @@
+//! # Ethereum Bridge Module
+//!
+//! RING and KTON locked on Ethereum or Tron, redeemed on Darwinia.
+//!
+//! Locking RING or KTON in the `LockerContract` on Ethereum emits
+//! `Locked(address indexed token, address indexed from, bytes32 indexed receiver, uint256 value)`.
+//! Anyone can `redeem` it by submitting the receipt of the locking transaction, verified by
+//! `T::EthRelay` against the relayed Ethereum headers. `value` is minted to `receiver` through
+//! `T::Ring` or `T::Kton`, depending on `token`.
+//!
+//! Tron is only a `Chain` of attestations: there is no relay of Tron blocks, so Tron locks are
+//! minted through the attestations of the authorities below, and `tron` converts its
+//! addresses. Receipts of either chain share the minting and `Redeemed`: block hashes of
+//! Ethereum and block ids of Tron do not collide, and every receipt is redeemed once.
+//!
+//! Root sets the contracts, once they are deployed.
+//!
+//! Until the relays can be trusted, root can also set bridge `Authorities`. Each of them
+//! `attest`s the locks it saw in a receipt, and they are minted once `Threshold` of them
+//! attested exactly the same locks. Attestations gathering fewer signatures expire after
+//! `ProposalLifetime` blocks. One reaching the threshold while minting is paused or limited
+//! keeps its signatures until then, and anyone can `mint_attested` it before it expires.
+//! Attested receipts count as redeemed as well, so that no lock is minted by both.
+//!
+//! However locks are redeemed, at most `RingMintLimit` RING and `KtonMintLimit` KTON are
+//! minted every `MintPeriod` blocks, so that a compromised relayer or authority cannot mint
+//! unbounded tokens, and never more RING than `Cap`, which the rewards of staking are computed
+//! against. Root can pause minting altogether. A value which would leave its receiver below the
+//! existential deposit is not minted.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use eth_relay::{EthReceiptProof, VerifyEthReceipts};
+use hex_literal::hex;
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256};
+use primitives::traits::{CheckedAdd, Hash, SaturatedConversion, Saturating, Zero};
+use rstd::prelude::*;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::Result;
+use srml_support::traits::{Currency, Get};
+use system::ensure_signed;
+
+mod adapter;
+pub mod tron;
+
+pub use adapter::{Chain, ChainAdapter, Contracts, EthereumAdapter, Lock, Token};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// `keccak256("Locked(address,address,bytes32,uint256)")`, the first topic of a `Locked` event.
+pub const LOCKED_TOPIC: [u8; 32] = hex!("1e7b27577112ed83d53de87b38aee59ab80d8a9ba4acd90aad6cfee917534c79");
+
+/// RING and KTON have 18 decimals on Ethereum and 9 on Darwinia.
+const DECIMALS_DIVISOR: u64 = 1_000_000_000;
+
+type RingBalanceOf<T> = <<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::Balance;
+type KtonBalanceOf<T> = <<T as Trait>::Kton as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// The locks in the transaction at `index` of `block` of `chain`, as a bridge authority saw them.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Attestation<AccountId> {
+    pub chain: Chain,
+    pub block: H256,
+    pub index: u64,
+    pub locks: Vec<Lock<AccountId>>,
+}
+
+/// The authorities who attested the same locks, until `expiry`.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AttestationProposal<AccountId, BlockNumber> {
+    pub signers: Vec<AccountId>,
+    pub expiry: BlockNumber,
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The currency minted for locked RING.
+    type Ring: Currency<Self::AccountId>;
+
+    /// The currency minted for locked KTON.
+    type Kton: Currency<Self::AccountId>;
+
+    /// The verifier of the receipts of locking transactions on Ethereum.
+    type EthRelay: VerifyEthReceipts;
+
+    /// Blocks after which attested locks which did not gather enough signatures are dropped.
+    type ProposalLifetime: Get<Self::BlockNumber>;
+
+    /// Blocks of a period of the mint limits.
+    type MintPeriod: Get<Self::BlockNumber>;
+
+    /// The most RING ever issued, as staking computes the rewards.
+    type Cap: Get<RingBalanceOf<Self>>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as EthBridge {
+        /// The contract emitting `Locked` events.
+        pub LockerContract get(locker_contract) config(): H160;
+
+        /// The RING token contract.
+        pub RingContract get(ring_contract) config(): H160;
+
+        /// The KTON token contract.
+        pub KtonContract get(kton_contract) config(): H160;
+
+        /// Receipts already redeemed, by block hash and transaction index.
+        pub Redeemed get(redeemed): map (H256, u64) => bool;
+
+        /// The bridge authorities attesting locks.
+        pub Authorities get(authorities) config(): Vec<T::AccountId>;
+
+        /// The number of authorities attesting the same locks to mint them.
+        pub Threshold get(threshold) config(): u32;
+
+        /// Attested locks which did not reach the threshold yet, by hash of the attestation.
+        pub Proposals get(proposal): map T::Hash => Option<AttestationProposal<T::AccountId, T::BlockNumber>>;
+
+        /// The hashes of the attestations whose proposal expires at a block.
+        pub ProposalExpiry get(proposal_expiry): map T::BlockNumber => Vec<T::Hash>;
+
+        /// Whether root paused minting.
+        pub Paused get(paused): bool;
+
+        /// The most RING minted in a period.
+        pub RingMintLimit get(ring_mint_limit) config(): RingBalanceOf<T>;
+
+        /// The most KTON minted in a period.
+        pub KtonMintLimit get(kton_mint_limit) config(): KtonBalanceOf<T>;
+
+        /// The period of `RingMinted` and `KtonMinted`, the block number over `MintPeriod`.
+        pub MintPeriodIndex get(mint_period_index): T::BlockNumber;
+
+        /// RING minted in the period.
+        pub RingMinted get(ring_minted): RingBalanceOf<T>;
+
+        /// KTON minted in the period.
+        pub KtonMinted get(kton_minted): KtonBalanceOf<T>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::Hash,
+        RingBalance = RingBalanceOf<T>,
+        KtonBalance = KtonBalanceOf<T>
+    {
+        /// RING locked in the transaction at the index of the block of the chain was redeemed.
+        RedeemRing(Chain, AccountId, RingBalance, H256, u64),
+        /// KTON locked in the transaction at the index of the block of the chain was redeemed.
+        RedeemKton(Chain, AccountId, KtonBalance, H256, u64),
+        /// A bridge authority attested the locks of the attestation, which has the signatures.
+        Attested(AccountId, Hash, u32),
+        /// The attestation expired before being minted.
+        ProposalExpired(Hash),
+        /// The attestation reached the threshold but minting is paused or limited: it is
+        /// minted later with `mint_attested`.
+        MintDeferred(Hash),
+        /// Root set the bridge authorities and the threshold.
+        AuthoritiesSet(Vec<AccountId>, u32),
+        /// Root paused or resumed minting.
+        PausedSet(bool),
+        /// Root set the most RING and KTON minted in a period.
+        MintLimitsSet(RingBalance, KtonBalance),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const ProposalLifetime: T::BlockNumber = T::ProposalLifetime::get();
+        const MintPeriod: T::BlockNumber = T::MintPeriod::get();
+        const Cap: RingBalanceOf<T> = T::Cap::get();
+
+        fn deposit_event<T>() = default;
+
+        /// Redeem the `Locked` events in a receipt of Ethereum.
+        fn redeem(origin, proof: EthReceiptProof) {
+            let _ = ensure_signed(origin)?;
+            Self::redeem_locks::<EthereumAdapter<T>>(&proof)?;
+        }
+
+        /// Attest, as a bridge authority, the locks in a receipt. They are minted once
+        /// `Threshold` authorities attested the same.
+        fn attest(origin, attestation: Attestation<T::AccountId>) {
+            let who = ensure_signed(origin)?;
+            let authorities = Self::authorities();
+            ensure!(authorities.contains(&who), "not a bridge authority");
+            ensure!(!attestation.locks.is_empty(), "no lock attested");
+            ensure!(!Self::redeemed((attestation.block, attestation.index)), "receipt already redeemed");
+
+            let hash = T::Hashing::hash_of(&attestation);
+            let (mut proposal, is_new) = match Self::proposal(hash) {
+                Some(proposal) => (proposal, false),
+                None => {
+                    let expiry = <system::Module<T>>::block_number() + T::ProposalLifetime::get();
+                    (AttestationProposal { signers: Vec::new(), expiry }, true)
+                }
+            };
+            ensure!(!proposal.signers.contains(&who), "already attested");
+            proposal.signers.push(who.clone());
+
+            let signatures = Self::signatures(&proposal, &authorities);
+
+            if is_new {
+                <ProposalExpiry<T>>::mutate(proposal.expiry, |hashes| hashes.push(hash));
+            }
+            <Proposals<T>>::insert(hash, proposal);
+            Self::deposit_event(RawEvent::Attested(who, hash, signatures));
+
+            // the signature is kept while minting is paused or limited
+            if signatures >= Self::threshold() && Self::mint_attestation(hash, attestation).is_err() {
+                Self::deposit_event(RawEvent::MintDeferred(hash));
+            }
+        }
+
+        /// Mint the locks of an attestation which reached the threshold while minting was paused
+        /// or limited.
+        fn mint_attested(origin, attestation: Attestation<T::AccountId>) {
+            let _ = ensure_signed(origin)?;
+            ensure!(!Self::redeemed((attestation.block, attestation.index)), "receipt already redeemed");
+
+            let hash = T::Hashing::hash_of(&attestation);
+            let proposal = Self::proposal(hash).ok_or("unknown attestation")?;
+            let signatures = Self::signatures(&proposal, &Self::authorities());
+            ensure!(signatures > 0 && signatures >= Self::threshold(), "not enough signatures");
+
+            Self::mint_attestation(hash, attestation)?;
+        }
+
+        /// Set the bridge authorities, `threshold` of which attest locks to mint them. Without
+        /// authorities, locks are only redeemed with proofs.
+        fn set_authorities(authorities: Vec<T::AccountId>, threshold: u32) {
+            ensure!(
+                authorities.is_empty() || (threshold > 0 && threshold as usize <= authorities.len()),
+                "invalid threshold"
+            );
+            let mut sorted = authorities.clone();
+            sorted.sort();
+            sorted.dedup();
+            ensure!(sorted.len() == authorities.len(), "duplicate authority");
+
+            <Authorities<T>>::put(&authorities);
+            Threshold::put(threshold);
+            Self::deposit_event(RawEvent::AuthoritiesSet(authorities, threshold));
+        }
+
+        /// Pause or resume minting.
+        fn set_paused(paused: bool) {
+            Paused::put(paused);
+            Self::deposit_event(RawEvent::PausedSet(paused));
+        }
+
+        /// Set the most RING and KTON minted in a period.
+        fn set_mint_limits(ring: RingBalanceOf<T>, kton: KtonBalanceOf<T>) {
+            <RingMintLimit<T>>::put(ring);
+            <KtonMintLimit<T>>::put(kton);
+            Self::deposit_event(RawEvent::MintLimitsSet(ring, kton));
+        }
+
+        /// Set the locking contract and the token contracts on Ethereum.
+        fn set_contracts(locker: H160, ring: H160, kton: H160) {
+            LockerContract::put(locker);
+            RingContract::put(ring);
+            KtonContract::put(kton);
+        }
+
+        fn on_initialize(now: T::BlockNumber) {
+            for hash in <ProposalExpiry<T>>::take(now) {
+                if <Proposals<T>>::exists(hash) {
+                    <Proposals<T>>::remove(hash);
+                    Self::deposit_event(RawEvent::ProposalExpired(hash));
+                }
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Mint the locks in the receipt proved by `proof`, unless it was already redeemed.
+    pub fn redeem_locks<A: ChainAdapter<T>>(proof: &A::Proof) -> Result {
+        let (block, index) = A::receipt_id(proof);
+        ensure!(!Self::redeemed((block, index)), "receipt already redeemed");
+
+        let locks = A::verify_locks(proof)?;
+        Self::mint(A::CHAIN, block, index, locks)
+    }
+
+    /// The signatures of `proposal` by current `authorities`: those removed since they attested
+    /// do not count.
+    fn signatures(proposal: &AttestationProposal<T::AccountId, T::BlockNumber>, authorities: &[T::AccountId]) -> u32 {
+        proposal.signers.iter().filter(|s| authorities.contains(s)).count() as u32
+    }
+
+    /// Mint the locks of `attestation` of `hash`, and drop its proposal.
+    fn mint_attestation(hash: T::Hash, attestation: Attestation<T::AccountId>) -> Result {
+        let Attestation { chain, block, index, locks } = attestation;
+        Self::mint(chain, block, index, locks)?;
+        <Proposals<T>>::remove(hash);
+        Ok(())
+    }
+
+    /// Mint `locks`, redeeming the receipt at `index` of `block` of `chain`, within the limits.
+    fn mint(chain: Chain, block: H256, index: u64, locks: Vec<Lock<T::AccountId>>) -> Result {
+        ensure!(!Self::paused(), "minting paused");
+
+        let period = <system::Module<T>>::block_number() / T::MintPeriod::get();
+        let (mut ring_minted, mut kton_minted) = if Self::mint_period_index() == period {
+            (Self::ring_minted(), Self::kton_minted())
+        } else {
+            (Zero::zero(), Zero::zero())
+        };
+        let mut ring_issued = RingBalanceOf::<T>::zero();
+        for lock in &locks {
+            match lock.token {
+                Token::Ring => {
+                    let value = lock.value.saturated_into();
+                    ensure!(
+                        T::Ring::free_balance(&lock.who).saturating_add(value) >= T::Ring::minimum_balance(),
+                        "value below the existential deposit"
+                    );
+                    ring_minted = ring_minted.checked_add(&value).ok_or("RING mint limit exceeded")?;
+                    ring_issued = ring_issued.checked_add(&value).ok_or("RING cap exceeded")?;
+                }
+                Token::Kton => {
+                    let value = lock.value.saturated_into();
+                    ensure!(
+                        T::Kton::free_balance(&lock.who).saturating_add(value) >= T::Kton::minimum_balance(),
+                        "value below the existential deposit"
+                    );
+                    kton_minted = kton_minted.checked_add(&value).ok_or("KTON mint limit exceeded")?;
+                }
+            }
+        }
+        ensure!(ring_minted <= Self::ring_mint_limit(), "RING mint limit exceeded");
+        ensure!(kton_minted <= Self::kton_mint_limit(), "KTON mint limit exceeded");
+        let issuance = T::Ring::total_issuance().checked_add(&ring_issued).ok_or("RING cap exceeded")?;
+        ensure!(issuance <= T::Cap::get(), "RING cap exceeded");
+
+        <MintPeriodIndex<T>>::put(period);
+        <RingMinted<T>>::put(ring_minted);
+        <KtonMinted<T>>::put(kton_minted);
+        Redeemed::insert((block, index), true);
+
+        for Lock { token, who, value } in locks {
+            match token {
+                Token::Ring => {
+                    let value = value.saturated_into();
+                    let _ = T::Ring::deposit_creating(&who, value);
+                    Self::deposit_event(RawEvent::RedeemRing(chain, who, value, block, index));
+                }
+                Token::Kton => {
+                    let value = value.saturated_into();
+                    let _ = T::Kton::deposit_creating(&who, value);
+                    Self::deposit_event(RawEvent::RedeemKton(chain, who, value, block, index));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use hex_literal::hex;
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use eth_relay::{ethash, EthReceiptProof, EthashParams, LogEntry, VerificationLevel};
+use crate::{GenesisConfig, Module, Trait, LOCKED_TOPIC};
+
+pub type AccountId = u64;
+
+/// The account the `Locked` events of the fixtures lock to.
+pub const RECEIVER: AccountId = 42;
+
+pub const LOCKER: [u8; 20] = hex!("b52fbe2b925ab79a821b261c82c5ba0814aaa5e0");
+pub const RING: [u8; 20] = hex!("9469d013805bffb7d3debe5e7839237e535ec483");
+pub const KTON: [u8; 20] = hex!("9f284e1337a815fe77d2ff4ae46544645b20c5ff");
+
+/// A `Locked` event of `locker`, locking `value` in 18 decimals of `token` for `RECEIVER`.
+pub fn locked_log(locker: [u8; 20], token: [u8; 20], value: u64) -> LogEntry {
+    let mut token_topic = [0u8; 32];
+    token_topic[12..].copy_from_slice(&token);
+    let mut receiver_topic = [0u8; 32];
+    receiver_topic[..8].copy_from_slice(&RECEIVER.to_le_bytes());
+    let mut data = [0u8; 32];
+    data[24..].copy_from_slice(&value.to_be_bytes());
+
+    LogEntry {
+        address: locker.into(),
+        topics: vec![LOCKED_TOPIC.into(), token_topic.into(), Default::default(), receiver_topic.into()],
+        data: data.to_vec(),
+    }
+}
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 5;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+}
+
+impl timestamp::Trait for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+}
+
+parameter_types! {
+    pub const MaxVestingSchedules: u32 = 8;
+    pub const MaxLocks: u32 = 16;
+    pub const MaxLockSweeps: u32 = 8;
+}
+
+impl kton::Trait for Test {
+    type Balance = u64;
+    type Event = ();
+    type OnMinted = ();
+    type OnRemoval = ();
+    type OnAccountBalanceChanged = ();
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type MaxLocks = MaxLocks;
+    type MaxLockSweeps = MaxLockSweeps;
+}
+
+parameter_types! {
+    pub const Parent: VerificationLevel = VerificationLevel::Parent;
+    pub const Mainnet: EthashParams = ethash::MAINNET;
+    pub const Confirmations: u64 = 1;
+    pub const HeadersToKeep: u64 = 100;
+}
+
+impl eth_relay::Trait for Test {
+    type Event = ();
+    type VerificationLevel = Parent;
+    type EthashParams = Mainnet;
+    type Confirmations = Confirmations;
+    type HeadersToKeep = HeadersToKeep;
+}
+
+parameter_types! {
+    pub const ProposalLifetime: u64 = 3;
+    pub const MintPeriod: u64 = 10;
+    pub const Cap: u64 = 100_000_000_000;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type Ring = Balances;
+    type Kton = Kton;
+    type EthRelay = EthRelay;
+    type ProposalLifetime = ProposalLifetime;
+    type MintPeriod = MintPeriod;
+    type Cap = Cap;
+}
+
+/// Headers relayed from the Ethereum mainnet genesis, only checking their parent, 2 of the bridge authorities 10, 11 and 12 attesting locks, and at most 20 RING
+/// and 10 KTON minted every 10 blocks.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(eth_relay::GenesisConfig {
+        genesis_header: mainnet_block_0(),
+        dataset_roots: vec![],
+    }.build_storage::<Test>().unwrap().0);
+    t.extend(GenesisConfig::<Test> {
+        locker_contract: LOCKER.into(),
+        ring_contract: RING.into(),
+        kton_contract: KTON.into(),
+        authorities: vec![10, 11, 12],
+        threshold: 2,
+        ring_mint_limit: 20_000_000_000,
+        kton_mint_limit: 10_000_000_000,
+    }.build_storage::<Test>().unwrap().0);
+    t.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Balances = balances::Module<Test>;
+pub type Kton = kton::Module<Test>;
+pub type EthRelay = eth_relay::Module<Test>;
+pub type EthBridge = Module<Test>;
+
+// Ethereum mainnet blocks 0 and 1, followed by block 2, whose receipts root is the root of
+// the receipts below, and block 3. The fork block is a child of block 0, with more difficulty
+// than blocks 1 to 3 together.
+
+pub const MAINNET_BLOCK_0_HASH: [u8; 32] = hex!("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3");
+pub const MAINNET_BLOCK_1_HASH: [u8; 32] = hex!("88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6");
+pub const BLOCK_2_HASH: [u8; 32] = hex!("e6025fe3585f9e7ed6129084b04e9f187c471f4ae9b3492dc379ba988ffeb03d");
+
+pub fn mainnet_block_0() -> Vec<u8> {
+    hex!("f90214a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000850400000000808213888080a011bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82faa00000000000000000000000000000000000000000000000000000000000000000880000000000000042").to_vec()
+}
+
+pub fn mainnet_block_1() -> Vec<u8> {
+    hex!("f90211a0d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d493479405a56e2d52c817161883f50c441c3228cfe54d9fa0d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008503ff80000001821388808455ba422499476574682f76312e302e302f6c696e75782f676f312e342e32a0969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f5988539bd4979fef1ec4").to_vec()
+}
+
+pub fn block_2() -> Vec<u8> {
+    hex!("f901f8a088e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a02ac1b073a8e417ad4efda1efcf85f3209035050e64a6bf864f777cb77838ff98b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008503ff00100002821388808455ba424180a00000000000000000000000000000000000000000000000000000000000000000880000000000000000").to_vec()
+}
+
+pub fn block_3() -> Vec<u8> {
+    hex!("f901f8a0e6025fe3585f9e7ed6129084b04e9f187c471f4ae9b3492dc379ba988ffeb03da01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b90100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008503fe802ffe03821388808455ba426080a00000000000000000000000000000000000000000000000000000000000000000880000000000000000").to_vec()
+}
+
+pub fn fork_block_1() -> Vec<u8> {
+    hex!("f901fca0d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347940000000000000000000000000000000000000000a0d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000085e8d4a5100001821388808455ba422684666f726ba00000000000000000000000000000000000000000000000000000000000000000880000000000000000").to_vec()
+}
+
+// The receipts of block 2: a transfer, a transaction locking 10 RING and one locking 5 KTON,
+// each to `RECEIVER`.
+
+pub fn transfer_receipt_proof() -> EthReceiptProof {
+    EthReceiptProof {
+        block_hash: BLOCK_2_HASH.into(),
+        index: 0,
+        proof: vec![
+            hex!("f851a0e71b919b65aecd4368e4e64194b0aa959078e9281a3e0b1137af059327b4643f80808080808080a0e58215be848c1293dd381210359d84485553000a82b67410406d183b42adbbdd8080808080808080").to_vec(),
+            hex!("f9010f30b9010bf9010801825208b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000c0").to_vec(),
+        ],
+    }
+}
+
+pub fn ring_receipt_proof() -> EthReceiptProof {
+    EthReceiptProof {
+        block_hash: BLOCK_2_HASH.into(),
+        index: 1,
+        proof: vec![
+            hex!("f851a0e71b919b65aecd4368e4e64194b0aa959078e9281a3e0b1137af059327b4643f80808080808080a0e58215be848c1293dd381210359d84485553000a82b67410406d183b42adbbdd8080808080808080").to_vec(),
+            hex!("f85180a0ed321b28582bf9b7bb2bf7229e383ca67efb796c31f44d437917e15e28eb96d1a06fbcbb5716e2aeb71e5b82119f57c2987e6900dd502a9a52f619984d908d8c308080808080808080808080808080").to_vec(),
+            hex!("f901cf20b901cbf901c80183013880b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f8bef8bc94b52fbe2b925ab79a821b261c82c5ba0814aaa5e0f884a01e7b27577112ed83d53de87b38aee59ab80d8a9ba4acd90aad6cfee917534c79a00000000000000000000000009469d013805bffb7d3debe5e7839237e535ec483a00000000000000000000000001111111111111111111111111111111111111111a02a00000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000008ac7230489e80000").to_vec(),
+        ],
+    }
+}
+
+pub fn kton_receipt_proof() -> EthReceiptProof {
+    EthReceiptProof {
+        block_hash: BLOCK_2_HASH.into(),
+        index: 2,
+        proof: vec![
+            hex!("f851a0e71b919b65aecd4368e4e64194b0aa959078e9281a3e0b1137af059327b4643f80808080808080a0e58215be848c1293dd381210359d84485553000a82b67410406d183b42adbbdd8080808080808080").to_vec(),
+            hex!("f85180a0ed321b28582bf9b7bb2bf7229e383ca67efb796c31f44d437917e15e28eb96d1a06fbcbb5716e2aeb71e5b82119f57c2987e6900dd502a9a52f619984d908d8c308080808080808080808080808080").to_vec(),
+            hex!("f901cf20b901cbf901c801830222e0b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f8bef8bc94b52fbe2b925ab79a821b261c82c5ba0814aaa5e0f884a01e7b27577112ed83d53de87b38aee59ab80d8a9ba4acd90aad6cfee917534c79a00000000000000000000000009f284e1337a815fe77d2ff4ae46544645b20c5ffa00000000000000000000000001111111111111111111111111111111111111111a02a00000000000000000000000000000000000000000000000000000000000000a00000000000000000000000000000000000000000000000004563918244f40000").to_vec(),
+        ],
+    }
+}
@@ -0,0 +1,257 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use eth_relay::{Receipt, TransactionOutcome};
+use primitives::traits::OnInitialize;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use srml_support::traits::Currency;
+use adapter::parse_locks;
+use mock::*;
+use super::*;
+
+fn relay(headers: Vec<Vec<u8>>) {
+    for header in headers {
+        assert_ok!(EthRelay::relay_header(Origin::signed(1), header, vec![]));
+    }
+}
+
+#[test]
+fn redeem_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        relay(vec![mainnet_block_1(), block_2()]);
+        assert_noop!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()), "header not confirmed");
+
+        relay(vec![block_3()]);
+        assert_ok!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10_000_000_000);
+        assert_noop!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()), "receipt already redeemed");
+
+        assert_ok!(EthBridge::redeem(Origin::signed(2), kton_receipt_proof()));
+        assert_eq!(Kton::free_balance(&RECEIVER), 5_000_000_000);
+
+        assert_noop!(
+            EthBridge::redeem(Origin::signed(2), transfer_receipt_proof()),
+            "no Locked event in the receipt"
+        );
+    });
+}
+
+#[test]
+fn redeem_should_check_the_proof() {
+    with_externalities(&mut new_test_ext(), || {
+        relay(vec![mainnet_block_1(), block_2(), block_3()]);
+
+        // the proof of the KTON receipt does not contain the RING one
+        let mut proof = kton_receipt_proof();
+        proof.index = 1;
+        assert_noop!(EthBridge::redeem(Origin::signed(2), proof), "node missing from the proof");
+
+        let mut proof = ring_receipt_proof();
+        proof.block_hash = H256(MAINNET_BLOCK_1_HASH);
+        assert_noop!(EthBridge::redeem(Origin::signed(2), proof), "root node missing from the proof");
+
+        // locked through another contract
+        LockerContract::put(H160::zero());
+        assert_noop!(
+            EthBridge::redeem(Origin::signed(2), ring_receipt_proof()),
+            "no Locked event in the receipt"
+        );
+    });
+}
+
+#[test]
+fn redeem_should_need_a_canonical_block() {
+    with_externalities(&mut new_test_ext(), || {
+        relay(vec![mainnet_block_1(), block_2(), block_3(), fork_block_1()]);
+        assert_noop!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()), "header not canonical");
+    });
+}
+
+#[test]
+fn parse_locks_should_check_the_contracts() {
+    let contracts = Contracts { locker: LOCKER.into(), ring: RING.into(), kton: KTON.into() };
+    let receipt = |logs| Receipt {
+        outcome: TransactionOutcome::Status(1),
+        gas_used: Default::default(),
+        log_bloom: vec![0; 256],
+        logs,
+    };
+
+    let locks = parse_locks::<AccountId>(&receipt(vec![
+        locked_log(LOCKER, RING, 10_000_000_000_000_000_000),
+        locked_log(LOCKER, KTON, 5_000_000_000_000_000_000),
+    ]), &contracts).unwrap();
+    assert_eq!(locks, vec![
+        Lock { token: Token::Ring, who: RECEIVER, value: 10_000_000_000 },
+        Lock { token: Token::Kton, who: RECEIVER, value: 5_000_000_000 },
+    ]);
+
+    assert_eq!(
+        parse_locks::<AccountId>(&receipt(vec![locked_log(KTON, RING, 1_000_000_000)]), &contracts),
+        Err("no Locked event in the receipt")
+    );
+    assert_eq!(
+        parse_locks::<AccountId>(&receipt(vec![locked_log(LOCKER, LOCKER, 1_000_000_000)]), &contracts),
+        Err("unsupported token")
+    );
+}
+
+fn attestation(index: u64, value: u128) -> Attestation<AccountId> {
+    Attestation {
+        chain: Chain::Ethereum,
+        block: H256(BLOCK_2_HASH),
+        index,
+        locks: vec![Lock { token: Token::Ring, who: RECEIVER, value }],
+    }
+}
+
+#[test]
+fn attestations_should_mint_at_the_threshold() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(EthBridge::attest(Origin::signed(2), attestation(1, 10)), "not a bridge authority");
+
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 10)));
+        assert_noop!(EthBridge::attest(Origin::signed(10), attestation(1, 10)), "already attested");
+        // other locks in the same receipt are another proposal
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 1_000)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 0);
+
+        assert_ok!(EthBridge::attest(Origin::signed(12), attestation(1, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10);
+        assert!(EthBridge::redeemed((H256(BLOCK_2_HASH), 1)));
+        assert_noop!(EthBridge::attest(Origin::signed(10), attestation(1, 1_000)), "receipt already redeemed");
+
+        // the receipt attested cannot be redeemed with a proof as well
+        relay(vec![mainnet_block_1(), block_2(), block_3()]);
+        assert_noop!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()), "receipt already redeemed");
+    });
+}
+
+#[test]
+fn authorities_should_be_set_by_root() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(EthBridge::set_authorities(vec![10, 11], 3), "invalid threshold");
+        assert_noop!(EthBridge::set_authorities(vec![10, 11], 0), "invalid threshold");
+        assert_noop!(EthBridge::set_authorities(vec![10, 10], 1), "duplicate authority");
+
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 10)));
+        assert_ok!(EthBridge::set_authorities(vec![11, 12, 13], 2));
+        assert_noop!(EthBridge::attest(Origin::signed(10), attestation(2, 10)), "not a bridge authority");
+
+        // the signature of 10 no longer counts
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 0);
+        assert_ok!(EthBridge::attest(Origin::signed(12), attestation(1, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10);
+
+        // no authorities, no attestations
+        assert_ok!(EthBridge::set_authorities(vec![], 0));
+        assert_noop!(EthBridge::attest(Origin::signed(11), attestation(2, 10)), "not a bridge authority");
+    });
+}
+
+#[test]
+fn stale_proposals_should_expire() {
+    with_externalities(&mut new_test_ext(), || {
+        System::set_block_number(1);
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 10)));
+        let hash = <Test as system::Trait>::Hashing::hash_of(&attestation(1, 10));
+        assert_eq!(EthBridge::proposal(hash), Some(AttestationProposal { signers: vec![10], expiry: 4 }));
+        assert_eq!(EthBridge::proposal_expiry(4), vec![hash]);
+
+        System::set_block_number(4);
+        EthBridge::on_initialize(4);
+        assert_eq!(EthBridge::proposal(hash), None);
+        assert!(EthBridge::proposal_expiry(4).is_empty());
+
+        // attesting again starts over
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 0);
+        assert_eq!(EthBridge::proposal(hash), Some(AttestationProposal { signers: vec![11], expiry: 7 }));
+    });
+}
+
+#[test]
+fn mint_should_be_limited_per_period() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(EthBridge::set_mint_limits(15, 5));
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 10)));
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 10)));
+        assert_eq!(EthBridge::ring_minted(), 10);
+
+        // the attestation stays pending until the next period, with its signatures
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(2, 10)));
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)), "not enough signatures");
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(2, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10);
+        let hash = <Test as system::Trait>::Hashing::hash_of(&attestation(2, 10));
+        assert_eq!(EthBridge::proposal(hash).map(|p| p.signers), Some(vec![10, 11]));
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)), "RING mint limit exceeded");
+
+        System::set_block_number(10);
+        assert_ok!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 20);
+        assert_eq!(EthBridge::ring_minted(), 10);
+        assert_eq!(EthBridge::proposal(hash), None);
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)), "receipt already redeemed");
+
+        let mut kton = attestation(3, 10);
+        kton.locks[0].token = Token::Kton;
+        assert_ok!(EthBridge::attest(Origin::signed(10), kton.clone()));
+        assert_ok!(EthBridge::attest(Origin::signed(11), kton.clone()));
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), kton), "KTON mint limit exceeded");
+    });
+}
+
+#[test]
+fn mint_should_not_exceed_the_cap() {
+    with_externalities(&mut new_test_ext(), || {
+        let _ = Balances::deposit_creating(&1, 100_000_000_000 - 5);
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 10)));
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 10)));
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(1, 10)), "RING cap exceeded");
+
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(2, 5)));
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(2, 5)));
+        assert_eq!(Balances::total_issuance(), 100_000_000_000);
+    });
+}
+
+#[test]
+fn mint_should_not_drop_values_below_the_existential_deposit() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(1, 4)));
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(1, 4)));
+        assert_noop!(
+            EthBridge::mint_attested(Origin::signed(2), attestation(1, 4)),
+            "value below the existential deposit"
+        );
+
+        // an existing account receives any value
+        let _ = Balances::deposit_creating(&RECEIVER, 5);
+        assert_ok!(EthBridge::mint_attested(Origin::signed(2), attestation(1, 4)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 9);
+    });
+}
+
+#[test]
+fn mint_should_be_paused_by_root() {
+    with_externalities(&mut new_test_ext(), || {
+        relay(vec![mainnet_block_1(), block_2(), block_3()]);
+        assert_ok!(EthBridge::set_paused(true));
+        assert_noop!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()), "minting paused");
+
+        assert_ok!(EthBridge::attest(Origin::signed(10), attestation(2, 10)));
+        assert_ok!(EthBridge::attest(Origin::signed(11), attestation(2, 10)));
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)), "minting paused");
+        assert_noop!(EthBridge::mint_attested(Origin::signed(2), attestation(3, 10)), "unknown attestation");
+
+        assert_ok!(EthBridge::set_paused(false));
+        assert_ok!(EthBridge::redeem(Origin::signed(2), ring_receipt_proof()));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10_000_000_000);
+        assert_ok!(EthBridge::mint_attested(Origin::signed(2), attestation(2, 10)));
+        assert_eq!(Balances::free_balance(&RECEIVER), 10_000_000_010);
+    });
+}
@@ -0,0 +1,117 @@
+//! Tron addresses: the base58check encoding of a 20 byte account prefixed with `0x41`.
+
+use primitive_types::H160;
+use rstd::{iter, prelude::*};
+use sha2::{Digest, Sha256};
+
+/// The byte before the account in a Tron address.
+pub const ADDRESS_PREFIX: u8 = 0x41;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The base58 address of `account`.
+pub fn encode_address(account: &H160) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(25);
+    raw.push(ADDRESS_PREFIX);
+    raw.extend_from_slice(account.as_bytes());
+    let checksum = checksum(&raw);
+    raw.extend_from_slice(&checksum);
+    encode_base58(&raw)
+}
+
+/// The account of the base58 `address`.
+pub fn decode_address(address: &[u8]) -> Result<H160, &'static str> {
+    let raw = decode_base58(address).ok_or("invalid base58")?;
+    if raw.len() != 25 {
+        return Err("invalid address length");
+    }
+    if raw[0] != ADDRESS_PREFIX {
+        return Err("invalid address prefix");
+    }
+    if checksum(&raw[..21]) != raw[21..] {
+        return Err("invalid address checksum");
+    }
+    Ok(H160::from_slice(&raw[1..21]))
+}
+
+/// The first 4 bytes of the double SHA-256 of `data`.
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(&Sha256::digest(data));
+    let mut checksum = [0u8; 4];
+    checksum.copy_from_slice(&hash[..4]);
+    checksum
+}
+
+fn encode_base58(data: &[u8]) -> Vec<u8> {
+    // base 58 digits, the least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in data {
+        let mut carry = u32::from(*byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    // leading zero bytes are kept as leading `1`s
+    let zeros = data.iter().take_while(|byte| **byte == 0).count();
+    let mut encoded: Vec<u8> = iter::repeat(ALPHABET[0]).take(zeros).collect();
+    encoded.extend(digits.iter().rev().map(|digit| ALPHABET[*digit as usize]));
+    encoded
+}
+
+fn decode_base58(encoded: &[u8]) -> Option<Vec<u8>> {
+    // bytes, the least significant first
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in encoded {
+        let mut carry = ALPHABET.iter().position(|a| a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = encoded.iter().take_while(|c| **c == ALPHABET[0]).count();
+    let mut decoded: Vec<u8> = iter::repeat(0).take(zeros).collect();
+    decoded.extend(bytes.iter().rev());
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use super::*;
+
+    #[test]
+    fn addresses_should_convert() {
+        let vectors: [(&[u8], [u8; 20]); 3] = [
+            // the USDT contract
+            (b"TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t", hex!("a614f803b6fd780986a42c78ec9c7f77e6ded13c")),
+            (b"T9yD14Nj9j7xAB4dbGeiX9h8unkKHxuWwb", [0u8; 20]),
+            (b"TZJozAg1ruapycCicgz31GxvYJ1FraLjZa", [0xffu8; 20]),
+        ];
+        for (address, account) in vectors.iter() {
+            assert_eq!(encode_address(&H160(*account)), address.to_vec());
+            assert_eq!(decode_address(address), Ok(H160(*account)));
+        }
+    }
+
+    #[test]
+    fn invalid_addresses_should_not_decode() {
+        assert_eq!(decode_address(b"TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6u"), Err("invalid address checksum"));
+        assert_eq!(decode_address(b"TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj60"), Err("invalid base58"));
+        assert_eq!(decode_address(b"TR7NHqjeKQxGTCi8q8ZY4pL8otSzgj"), Err("invalid address length"));
+        // a Bitcoin address, prefixed with 0x00
+        assert_eq!(decode_address(b"1BoatSLRHtKNngkdXEeobR76b53LETtpyT"), Err("invalid address prefix"));
+    }
+}
@@ -0,0 +1,157 @@
+//! # Ethereum Outbound Module
+//!
+//! RING and KTON burnt on Darwinia, to be unlocked on Ethereum.
+//!
+//! `redeem` burns RING or KTON of the sender and records a `RedeemMessage` to an Ethereum
+//! address, numbered by `Nonce`. Messages are the leaves of a Merkle Mountain Range over
+//! Keccak-256, see `mmr`, whose root commits to every message so far; the root of every size
+//! of the range is kept in `MmrRoots`. Once a root is known on Ethereum, the contract there
+//! unlocks a message given its proof against that root, which the `EthOutboundApi` runtime API
+//! returns, and unlocks each nonce once.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256, U256};
+use primitives::traits::{SaturatedConversion, UniqueSaturatedInto, Zero};
+use rstd::prelude::*;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use system::ensure_signed;
+use dsupport::traits::{CurrencyId, MultiCurrency};
+
+pub mod mmr;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// RING and KTON have 9 decimals on Darwinia and 18 on Ethereum.
+const DECIMALS_MULTIPLIER: u64 = 1_000_000_000;
+
+type BalanceOf<T> = <<T as Trait>::Currency as MultiCurrency<<T as system::Trait>::AccountId, CurrencyId>>::Balance;
+
+/// RING or KTON burnt on Darwinia, to be unlocked on Ethereum.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RedeemMessage<AccountId, Balance> {
+    pub nonce: u64,
+    pub sender: AccountId,
+    pub currency: CurrencyId,
+    pub amount: Balance,
+    pub recipient: H160,
+}
+
+impl<AccountId, Balance: Copy + UniqueSaturatedInto<u128>> RedeemMessage<AccountId, Balance> {
+    /// The leaf of the message,
+    /// `keccak256(abi.encodePacked(uint64 nonce, uint8 currency, address recipient, uint256 amount))`
+    /// with RING as currency 0, KTON as 1 and `amount` in 18 decimals.
+    pub fn leaf(&self) -> H256 {
+        let currency = match self.currency {
+            CurrencyId::Ring => 0,
+            CurrencyId::Kton => 1,
+        };
+        let mut amount = [0u8; 32];
+        (U256::from(self.amount.saturated_into::<u128>()) * U256::from(DECIMALS_MULTIPLIER)).to_big_endian(&mut amount);
+
+        let mut data = Vec::with_capacity(61);
+        data.extend_from_slice(&self.nonce.to_be_bytes());
+        data.push(currency);
+        data.extend_from_slice(self.recipient.as_bytes());
+        data.extend_from_slice(&amount);
+        H256(runtime_io::keccak_256(&data))
+    }
+}
+
+/// A message and its proof against the root of the range of `mmr_size` nodes.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RedeemProof<AccountId, Balance> {
+    pub message: RedeemMessage<AccountId, Balance>,
+    pub mmr_size: u64,
+    /// The siblings of the nodes from the leaf of the message up to its peak.
+    pub siblings: Vec<H256>,
+    /// Every peak of the range, from the left.
+    pub peaks: Vec<H256>,
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// RING and KTON, burnt by `redeem`.
+    type Currency: MultiCurrency<Self::AccountId, CurrencyId>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as EthOutbound {
+        /// The nonce of the next message, which is also the number of messages.
+        pub Nonce get(nonce): u64;
+
+        /// Messages, by nonce.
+        pub Messages get(message): map u64 => Option<RedeemMessage<T::AccountId, BalanceOf<T>>>;
+
+        /// The nodes of the range, by position.
+        pub MmrNodes get(mmr_node): map u64 => H256;
+
+        /// The number of nodes of the range.
+        pub MmrSize get(mmr_size): u64;
+
+        /// The root of the range at each of its sizes, committing to every message before.
+        pub MmrRoots get(mmr_root): map u64 => Option<H256>;
+    }
+}
+
+/// The range of the messages.
+type Range = mmr::Mmr<MmrNodes, MmrSize, MmrRoots>;
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId,
+        Balance = BalanceOf<T>
+    {
+        /// A message was recorded: its nonce, the sender, the currency and the amount burnt, the
+        /// recipient on Ethereum and the new root.
+        Redeem(u64, AccountId, CurrencyId, Balance, H160, H256),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Burn `amount` of `currency` to unlock it to `eth_address` on Ethereum.
+        fn redeem(origin, currency: CurrencyId, #[compact] amount: BalanceOf<T>, eth_address: H160) {
+            let who = ensure_signed(origin)?;
+            ensure!(!amount.is_zero(), "zero amount");
+
+            T::Currency::withdraw(currency, &who, amount)?;
+
+            let nonce = Self::nonce();
+            let message = RedeemMessage {
+                nonce,
+                sender: who.clone(),
+                currency,
+                amount,
+                recipient: eth_address,
+            };
+            let root = Range::append(message.leaf());
+            Nonce::put(nonce + 1);
+            <Messages<T>>::insert(nonce, message);
+
+            Self::deposit_event(RawEvent::Redeem(nonce, who, currency, amount, eth_address, root));
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The message of `nonce` and its proof against the root of the range of `mmr_size` nodes,
+    /// if the message was in it.
+    pub fn redeem_proof(nonce: u64, mmr_size: u64) -> Option<RedeemProof<T::AccountId, BalanceOf<T>>> {
+        let message = Self::message(nonce)?;
+        let (siblings, peaks) = Range::proof(nonce, mmr_size)?;
+
+        Some(RedeemProof { message, mmr_size, siblings, peaks })
+    }
+}
@@ -0,0 +1,225 @@
+//! A Merkle Mountain Range over Keccak-256, as verified by the Ethereum contract.
+//!
+//! Nodes are numbered from 0 in the order they are appended: a leaf, then every parent it
+//! completes. A node is `keccak256(left ++ right)`, and the root of a range of `size` nodes is
+//! `keccak256(uint64 size ++ bag)`, where `bag` folds the peaks from the right with the same
+//! merge as the nodes.
+//!
+//! [`Mmr`](./struct.Mmr.html) keeps a range in the storage of a module, with the root of every
+//! size it had, so that a message can be proven against any root already sent to another chain.
+
+use primitive_types::H256;
+use rstd::{marker::PhantomData, prelude::*};
+use srml_support::{StorageMap, StorageValue};
+
+/// A range in the storage of a module: its nodes by position in `Nodes`, its size in `Size` and
+/// its root at each size it had in `Roots`.
+pub struct Mmr<Nodes, Size, Roots>(PhantomData<(Nodes, Size, Roots)>);
+
+impl<Nodes, Size, Roots> Mmr<Nodes, Size, Roots>
+where
+    Nodes: StorageMap<u64, H256, Query = H256>,
+    Size: StorageValue<u64, Query = u64>,
+    Roots: StorageMap<u64, H256, Query = Option<H256>>,
+{
+    /// Append `leaf`, returning the new root.
+    pub fn append(leaf: H256) -> H256 {
+        let size = append(Size::get(), leaf, |pos| Nodes::get(pos), |pos, hash| Nodes::insert(pos, hash));
+        let root = root(size, &peaks(size, |pos| Nodes::get(pos)));
+        Size::put(size);
+        Roots::insert(size, root);
+        root
+    }
+
+    /// The siblings and the peaks proving the leaf at `index` against the root of the range
+    /// when it had `size` nodes, if it had that size and the leaf was in it.
+    pub fn proof(index: u64, size: u64) -> Option<(Vec<H256>, Vec<H256>)> {
+        Roots::get(size)?;
+        // there are fewer leaves than nodes
+        if index >= size || leaf_index_to_pos(index) >= size {
+            return None;
+        }
+
+        Some((siblings(size, index, |pos| Nodes::get(pos)), peaks(size, |pos| Nodes::get(pos))))
+    }
+}
+
+/// The parent of `left` and `right`.
+pub fn merge(left: &H256, right: &H256) -> H256 {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(left.as_bytes());
+    data[32..].copy_from_slice(right.as_bytes());
+    H256(runtime_io::keccak_256(&data))
+}
+
+/// The height of the node at `pos`, leaves being at height 0.
+pub fn pos_height(pos: u64) -> u32 {
+    // numbered from 1, the left-most nodes of each height are all ones: move left until one
+    // of them is reached
+    let mut pos = pos + 1;
+    while !all_ones(pos) {
+        pos -= (1 << (bit_length(pos) - 1)) - 1;
+    }
+    bit_length(pos) - 1
+}
+
+/// The position of the leaf at `index`.
+pub fn leaf_index_to_pos(index: u64) -> u64 {
+    2 * index - u64::from(index.count_ones())
+}
+
+/// The positions of the peaks of a range of `size` nodes, from the left.
+pub fn peak_positions(size: u64) -> Vec<u64> {
+    let mut peaks = Vec::new();
+    let mut offset = 0;
+    let mut remaining = size;
+    while remaining > 0 {
+        // the largest perfect tree left
+        let mut tree = u64::max_value() >> remaining.leading_zeros();
+        if tree > remaining {
+            tree >>= 1;
+        }
+        offset += tree;
+        remaining -= tree;
+        peaks.push(offset - 1);
+    }
+    peaks
+}
+
+/// The root of a range of `size` nodes with `peaks`.
+pub fn root(size: u64, peaks: &[H256]) -> H256 {
+    let bag = match peaks.split_last() {
+        Some((last, rest)) => rest.iter().rev().fold(*last, |bag, peak| merge(peak, &bag)),
+        None => H256::zero(),
+    };
+
+    let mut data = [0u8; 40];
+    data[..8].copy_from_slice(&size.to_be_bytes());
+    data[8..].copy_from_slice(bag.as_bytes());
+    H256(runtime_io::keccak_256(&data))
+}
+
+/// The position of the parent of the node at `pos` of `height`, and whether the node is its
+/// left child.
+pub fn parent(pos: u64, height: u32) -> (u64, bool) {
+    if pos_height(pos + 1) > height {
+        (pos + 1, false)
+    } else {
+        (pos + (2 << height), true)
+    }
+}
+
+/// Append `leaf` and the parents it completes to a range of `size` nodes, read by `node` and
+/// written by `insert`, returning the new size.
+pub fn append(size: u64, leaf: H256, node: impl Fn(u64) -> H256, mut insert: impl FnMut(u64, H256)) -> u64 {
+    let mut pos = size;
+    let mut height = 0;
+    let mut hash = leaf;
+    insert(pos, hash);
+
+    // while the next position is a parent, the last node is its right child
+    while pos_height(pos + 1) > height {
+        let left = node(pos + 1 - (2 << height));
+        hash = merge(&left, &hash);
+        pos += 1;
+        height += 1;
+        insert(pos, hash);
+    }
+
+    pos + 1
+}
+
+/// The siblings of the nodes from the leaf at `index` up to its peak, in a range of `size`
+/// nodes read by `node`.
+pub fn siblings(size: u64, index: u64, node: impl Fn(u64) -> H256) -> Vec<H256> {
+    let peaks = peak_positions(size);
+    let mut pos = leaf_index_to_pos(index);
+    let mut height = 0;
+    let mut siblings = Vec::new();
+    while !peaks.contains(&pos) {
+        let (parent_pos, is_left) = parent(pos, height);
+        let sibling = if is_left { parent_pos - 1 } else { parent_pos - (2 << height) };
+        siblings.push(node(sibling));
+        pos = parent_pos;
+        height += 1;
+    }
+    siblings
+}
+
+/// The peaks of a range of `size` nodes read by `node`, from the left.
+pub fn peaks(size: u64, node: impl Fn(u64) -> H256) -> Vec<H256> {
+    peak_positions(size).into_iter().map(node).collect()
+}
+
+/// Whether `leaf` is the leaf at `index` of the range of `size` nodes with `root`, given the
+/// `siblings` from the leaf up to its peak and all the `peaks`.
+pub fn verify_proof(
+    root_hash: &H256,
+    size: u64,
+    index: u64,
+    leaf: &H256,
+    siblings: &[H256],
+    peaks: &[H256],
+) -> bool {
+    // there are fewer leaves than nodes, and fewer heights than bits
+    if index >= size || siblings.len() >= 64 {
+        return false;
+    }
+
+    let mut pos = leaf_index_to_pos(index);
+    let mut hash = *leaf;
+    for (height, sibling) in siblings.iter().enumerate() {
+        let (parent_pos, is_left) = parent(pos, height as u32);
+        hash = if is_left { merge(&hash, sibling) } else { merge(sibling, &hash) };
+        pos = parent_pos;
+    }
+
+    let positions = peak_positions(size);
+    if positions.len() != peaks.len() {
+        return false;
+    }
+    match positions.iter().position(|peak| *peak == pos) {
+        Some(i) => peaks[i] == hash && root(size, peaks) == *root_hash,
+        None => false,
+    }
+}
+
+fn all_ones(n: u64) -> bool {
+    n != 0 && n.count_zeros() == n.leading_zeros()
+}
+
+fn bit_length(n: u64) -> u32 {
+    64 - n.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_should_work() {
+        // 2       6
+        //       /   \
+        // 1    2     5     9
+        //     / \   / \   / \
+        // 0  0   1 3   4 7   8 10
+        let heights: Vec<u32> = (0..11).map(pos_height).collect();
+        assert_eq!(heights, vec![0, 0, 1, 0, 0, 1, 2, 0, 0, 1, 0]);
+
+        let leaves: Vec<u64> = (0..7).map(leaf_index_to_pos).collect();
+        assert_eq!(leaves, vec![0, 1, 3, 4, 7, 8, 10]);
+
+        assert_eq!(peak_positions(0), Vec::<u64>::new());
+        assert_eq!(peak_positions(1), vec![0]);
+        assert_eq!(peak_positions(3), vec![2]);
+        assert_eq!(peak_positions(4), vec![2, 3]);
+        assert_eq!(peak_positions(7), vec![6]);
+        assert_eq!(peak_positions(11), vec![6, 9, 10]);
+
+        assert_eq!(parent(0, 0), (2, true));
+        assert_eq!(parent(1, 0), (2, false));
+        assert_eq!(parent(2, 1), (6, true));
+        assert_eq!(parent(5, 1), (6, false));
+        assert_eq!(parent(8, 0), (9, false));
+    }
+}
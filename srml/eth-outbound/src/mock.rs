@@ -0,0 +1,104 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use dsupport::traits::RingKtonCurrency;
+use crate::{Module, Trait};
+
+pub type AccountId = u64;
+
+/// Units of RING or KTON in one of them.
+pub const COIN: u64 = 1_000_000_000;
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 0;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+}
+
+impl timestamp::Trait for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+}
+
+parameter_types! {
+    pub const MaxVestingSchedules: u32 = 8;
+    pub const MaxLocks: u32 = 16;
+    pub const MaxLockSweeps: u32 = 8;
+}
+
+impl kton::Trait for Test {
+    type Balance = u64;
+    type Event = ();
+    type OnMinted = ();
+    type OnRemoval = ();
+    type OnAccountBalanceChanged = ();
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type MaxLocks = MaxLocks;
+    type MaxLockSweeps = MaxLockSweeps;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type Currency = RingKtonCurrency<Balances, Kton>;
+}
+
+/// Accounts 1 and 2 with 100 RING and 100 KTON each.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(balances::GenesisConfig::<Test> {
+        balances: vec![(1, 100 * COIN), (2, 100 * COIN)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+    t.extend(kton::GenesisConfig::<Test> {
+        balances: vec![(1, 100 * COIN), (2, 100 * COIN)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+    t.into()
+}
+
+pub type Balances = balances::Module<Test>;
+pub type Kton = kton::Module<Test>;
+pub type EthOutbound = Module<Test>;
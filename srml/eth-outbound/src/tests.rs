@@ -0,0 +1,116 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use hex_literal::hex;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use srml_support::traits::Currency;
+use mock::*;
+use super::*;
+
+const RECIPIENT: [u8; 20] = hex!("7f5e1ad4ce3fb7d0b43c0e4ab2e8b2a45e27aa6f");
+
+/// Whether `proof` verifies against the root of its size.
+fn verify(proof: &RedeemProof<AccountId, u64>) -> bool {
+    mmr::verify_proof(
+        &EthOutbound::mmr_root(proof.mmr_size).unwrap_or_default(),
+        proof.mmr_size,
+        proof.message.nonce,
+        &proof.message.leaf(),
+        &proof.siblings,
+        &proof.peaks,
+    )
+}
+
+#[test]
+fn redeem_should_burn() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Ring, 10 * COIN, H160(RECIPIENT)));
+        assert_eq!(Balances::free_balance(&1), 90 * COIN);
+        assert_eq!(Balances::total_issuance(), 190 * COIN);
+
+        assert_ok!(EthOutbound::redeem(Origin::signed(2), CurrencyId::Kton, 5 * COIN, H160(RECIPIENT)));
+        assert_eq!(Kton::free_balance(&2), 95 * COIN);
+        assert_eq!(Kton::total_issuance(), 195 * COIN);
+
+        assert_eq!(EthOutbound::nonce(), 2);
+        assert_eq!(EthOutbound::message(1), Some(RedeemMessage {
+            nonce: 1,
+            sender: 2,
+            currency: CurrencyId::Kton,
+            amount: 5 * COIN,
+            recipient: H160(RECIPIENT),
+        }));
+
+        assert_noop!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Ring, 0, H160(RECIPIENT)), "zero amount");
+        assert!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Kton, 101 * COIN, H160(RECIPIENT)).is_err());
+        assert_eq!(EthOutbound::nonce(), 2);
+    });
+}
+
+#[test]
+fn messages_should_be_committed_to() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(EthOutbound::mmr_root(0), None);
+
+        assert_ok!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Ring, 10 * COIN, H160(RECIPIENT)));
+        assert_eq!(
+            EthOutbound::message(0).unwrap().leaf(),
+            H256(hex!("a562df0e73ee554fd85a92597e4a9d6d541b4cbb6d9af0ff705d0faddd626f7d"))
+        );
+        let first_root = H256(hex!("5e2142b7249bb84050c2b28b22ccb0872813500f9ef82973aa8b6b0b912a7535"));
+        assert_eq!(EthOutbound::mmr_root(1), Some(first_root));
+
+        assert_ok!(EthOutbound::redeem(Origin::signed(2), CurrencyId::Kton, 5 * COIN, H160(RECIPIENT)));
+        assert_ok!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Ring, COIN, H160(RECIPIENT)));
+        assert_eq!(EthOutbound::mmr_size(), 4);
+        assert_eq!(EthOutbound::mmr_root(4), Some(H256(hex!("88f5b8d9f48849c15b9a0a7541bd2bf94def71c6ed06ecfa27ff55557dc57232"))));
+        // the roots of past sizes are kept
+        assert_eq!(EthOutbound::mmr_root(1), Some(first_root));
+    });
+}
+
+#[test]
+fn proofs_should_verify() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(EthOutbound::redeem_proof(0, 0), None);
+
+        let mut sizes = Vec::new();
+        for n in 0..11 {
+            assert_ok!(EthOutbound::redeem(Origin::signed(1), CurrencyId::Ring, n + 1, H160(RECIPIENT)));
+            sizes.push(EthOutbound::mmr_size());
+            for nonce in 0..=n {
+                assert!(verify(&EthOutbound::redeem_proof(nonce, EthOutbound::mmr_size()).unwrap()));
+            }
+        }
+        assert_eq!(EthOutbound::mmr_size(), 19);
+
+        // messages are still proven against the roots of past sizes, which were in them
+        for (n, size) in sizes.iter().enumerate() {
+            for nonce in 0..11 {
+                let proof = EthOutbound::redeem_proof(nonce, *size);
+                assert_eq!(proof.as_ref().map(verify), if nonce <= n as u64 { Some(true) } else { None });
+            }
+        }
+        assert_eq!(EthOutbound::redeem_proof(0, 2), None);
+
+        let proof = EthOutbound::redeem_proof(4, 19).unwrap();
+
+        let mut other_amount = proof.clone();
+        other_amount.message.amount += 1;
+        assert!(!verify(&other_amount));
+
+        let mut other_nonce = proof.clone();
+        other_nonce.message.nonce = 5;
+        assert!(!verify(&other_nonce));
+
+        let mut other_sibling = proof.clone();
+        other_sibling.siblings[0] = H256::zero();
+        assert!(!verify(&other_sibling));
+
+        let mut other_size = proof.clone();
+        other_size.mmr_size = 18;
+        assert!(!verify(&other_size));
+    });
+}
@@ -0,0 +1,289 @@
+//! The difficulty and the seal of Ethash headers.
+//!
+//! The seal is verified with the elements of the Ethash dataset it mixes, each proved by the
+//! relayer against the Merkle root of the dataset of its epoch: the tree has the Keccak-256
+//! hashes of the 128 byte elements as leaves, padded with zero hashes to a power of two, and
+//! the Keccak-256 hashes of the concatenated children as nodes.
+
+use parity_codec::{Decode, Encode};
+use primitive_types::{H256, H512, U256};
+use rstd::{cmp, prelude::*};
+use tiny_keccak::Keccak;
+
+use crate::types::{EthHeader, HeaderInfo};
+
+/// Blocks between each doubling of the difficulty bomb.
+const EXP_DIFF_PERIOD: u64 = 100_000;
+
+/// Blocks the difficulty bomb was delayed by in Byzantium.
+const BYZANTIUM_BOMB_DELAY: u64 = 3_000_000;
+
+/// Blocks the difficulty bomb was delayed by in Constantinople, on top of Byzantium.
+const CONSTANTINOPLE_BOMB_DELAY: u64 = 2_000_000;
+
+/// `keccak256(rlp([]))`, the uncles hash of a block without uncles.
+const EMPTY_LIST_HASH: [u8; 32] = [
+    0x1d, 0xcc, 0x4d, 0xe8, 0xde, 0xc7, 0x5d, 0x7a, 0xab, 0x85, 0xb5, 0x67, 0xb6, 0xcc, 0xd4, 0x1a,
+    0xd3, 0x12, 0x45, 0x1b, 0x94, 0x8a, 0x74, 0x13, 0xf0, 0xa1, 0x42, 0xfd, 0x40, 0xd4, 0x93, 0x47,
+];
+
+/// Blocks sharing an Ethash dataset.
+pub const EPOCH_LENGTH: u64 = 30_000;
+
+/// Bytes of the dataset of the first epoch.
+const DATASET_BYTES_INIT: u64 = 1 << 30;
+
+/// Bytes the dataset grows by each epoch.
+const DATASET_BYTES_GROWTH: u64 = 1 << 23;
+
+/// Bytes of the mix, and of the dataset elements mixed into it.
+pub const MIX_BYTES: usize = 128;
+
+/// 32 bit words of the mix.
+const MIX_WORDS: usize = MIX_BYTES / 4;
+
+/// Dataset elements mixed into the seal of a header.
+pub const ACCESSES: usize = 64;
+
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// An element of the Ethash dataset, proved against the dataset root of its epoch.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct DatasetProof {
+    /// The two 64 byte dataset nodes making the element.
+    pub nodes: (H512, H512),
+    /// The siblings of the element and of its ancestors in the tree, from the leaves up.
+    pub siblings: Vec<H256>,
+}
+
+/// The Ethash parameters of a network.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EthashParams {
+    pub minimum_difficulty: u64,
+    pub difficulty_bound_divisor: u64,
+    /// Blocks mined faster than this, in seconds, raise the difficulty before Homestead.
+    pub duration_limit: u64,
+    pub homestead_transition: u64,
+    pub byzantium_transition: u64,
+    pub constantinople_transition: u64,
+}
+
+/// The Ethash parameters of the Ethereum mainnet.
+pub const MAINNET: EthashParams = EthashParams {
+    minimum_difficulty: 131_072,
+    difficulty_bound_divisor: 2048,
+    duration_limit: 13,
+    homestead_transition: 1_150_000,
+    byzantium_transition: 4_370_000,
+    constantinople_transition: 7_280_000,
+};
+
+/// The difficulty `header` must have as a child of `parent`.
+pub fn calculate_difficulty(params: &EthashParams, header: &EthHeader, parent: &HeaderInfo) -> U256 {
+    let minimum_difficulty = U256::from(params.minimum_difficulty);
+    let bound = parent.difficulty / U256::from(params.difficulty_bound_divisor);
+
+    let mut target = if header.number < params.homestead_transition {
+        if header.timestamp >= parent.timestamp + params.duration_limit {
+            parent.difficulty.saturating_sub(bound)
+        } else {
+            parent.difficulty.saturating_add(bound)
+        }
+    } else {
+        let (increment_divisor, threshold) = if header.number < params.byzantium_transition {
+            (10, 1)
+        } else if parent.uncles_hash == H256(EMPTY_LIST_HASH) {
+            (9, 1)
+        } else {
+            (9, 2)
+        };
+
+        let increment = (header.timestamp - parent.timestamp) / increment_divisor;
+        if increment <= threshold {
+            parent.difficulty.saturating_add(bound * U256::from(threshold - increment))
+        } else {
+            let multiplier = cmp::min(increment - threshold, 99);
+            parent.difficulty.saturating_sub(bound * U256::from(multiplier))
+        }
+    };
+    target = cmp::max(minimum_difficulty, target);
+
+    let mut number = header.number;
+    if header.number >= params.byzantium_transition {
+        number = number.saturating_sub(BYZANTIUM_BOMB_DELAY);
+    }
+    if header.number >= params.constantinople_transition {
+        number = number.saturating_sub(CONSTANTINOPLE_BOMB_DELAY);
+    }
+    let period = number / EXP_DIFF_PERIOD;
+    if period > 1 {
+        target = cmp::max(minimum_difficulty, target.saturating_add(U256::one() << (period - 2) as usize));
+    }
+
+    target
+}
+
+/// Whether the seal of `header` meets its difficulty, with the dataset elements it mixes
+/// proved by `proofs`, in the order they are mixed, against `dataset_root`.
+pub fn verify_seal(header: &EthHeader, dataset_root: &H256, proofs: &[DatasetProof]) -> bool {
+    let (mix_hash, nonce) = match header.ethash_seal() {
+        Some(seal) => seal,
+        None => return false,
+    };
+    if proofs.len() != ACCESSES {
+        return false;
+    }
+
+    let full_size = dataset_size(header.number / EPOCH_LENGTH);
+    let depth = dataset_depth(full_size);
+    let sealed = hashimoto(&header.bare_hash, &nonce, full_size, |access, index| {
+        let proof = &proofs[access];
+        let mut element = [0u8; MIX_BYTES];
+        element[..64].copy_from_slice(proof.nodes.0.as_bytes());
+        element[64..].copy_from_slice(proof.nodes.1.as_bytes());
+
+        if proof.siblings.len() != depth || merkle_root(&element, index, &proof.siblings) != *dataset_root {
+            return None;
+        }
+        Some(element)
+    });
+
+    match sealed {
+        Some((computed_mix_hash, pow)) => {
+            computed_mix_hash == mix_hash && U256::from_big_endian(pow.as_bytes()) <= boundary(&header.difficulty)
+        }
+        None => false,
+    }
+}
+
+/// The mix hash and the seal hash of `bare_hash` and `nonce`, mixing the elements of a dataset
+/// of `full_size` bytes given by `element` from the access and the index of each element.
+pub fn hashimoto(
+    bare_hash: &H256,
+    nonce: &[u8; 8],
+    full_size: u64,
+    mut element: impl FnMut(usize, u64) -> Option<[u8; MIX_BYTES]>,
+) -> Option<(H256, H256)> {
+    // the nonce is hashed little endian
+    let mut seed = [0u8; 40];
+    seed[..32].copy_from_slice(bare_hash.as_bytes());
+    for (i, byte) in nonce.iter().rev().enumerate() {
+        seed[32 + i] = *byte;
+    }
+    let seed_hash = keccak_512(&seed);
+
+    let mut mix = [0u32; MIX_WORDS];
+    for (i, word) in mix.iter_mut().enumerate() {
+        *word = le_word(&seed_hash, i % 16);
+    }
+
+    let seed_head = le_word(&seed_hash, 0);
+    let elements = full_size / MIX_BYTES as u64;
+    for access in 0..ACCESSES {
+        let index = u64::from(fnv(access as u32 ^ seed_head, mix[access % MIX_WORDS])) % elements;
+        let data = element(access, index)?;
+        for (i, word) in mix.iter_mut().enumerate() {
+            *word = fnv(*word, le_word(&data, i));
+        }
+    }
+
+    let mut mix_hash = H256::zero();
+    for i in 0..MIX_WORDS / 4 {
+        let compressed = fnv(fnv(fnv(mix[4 * i], mix[4 * i + 1]), mix[4 * i + 2]), mix[4 * i + 3]);
+        mix_hash.as_bytes_mut()[4 * i..4 * i + 4].copy_from_slice(&compressed.to_le_bytes());
+    }
+
+    let mut pow = [0u8; 96];
+    pow[..64].copy_from_slice(&seed_hash);
+    pow[64..].copy_from_slice(mix_hash.as_bytes());
+    Some((mix_hash, H256(runtime_io::keccak_256(&pow))))
+}
+
+/// The bytes of the Ethash dataset of `epoch`.
+pub fn dataset_size(epoch: u64) -> u64 {
+    let mut size = DATASET_BYTES_INIT + DATASET_BYTES_GROWTH * epoch - MIX_BYTES as u64;
+    while !is_prime(size / MIX_BYTES as u64) {
+        size -= 2 * MIX_BYTES as u64;
+    }
+    size
+}
+
+/// The depth of the Merkle tree of a dataset of `full_size` bytes.
+pub fn dataset_depth(full_size: u64) -> usize {
+    let elements = full_size / MIX_BYTES as u64;
+    (64 - (elements - 1).leading_zeros()) as usize
+}
+
+/// The leaf of a dataset element in the Merkle tree.
+pub fn element_hash(element: &[u8]) -> H256 {
+    H256(runtime_io::keccak_256(element))
+}
+
+/// The node of the Merkle tree above `left` and `right`.
+pub fn node_hash(left: &H256, right: &H256) -> H256 {
+    let mut pair = [0u8; 64];
+    pair[..32].copy_from_slice(left.as_bytes());
+    pair[32..].copy_from_slice(right.as_bytes());
+    H256(runtime_io::keccak_256(&pair))
+}
+
+/// The root of the Merkle tree with `element` at `index` and `siblings` on its path.
+fn merkle_root(element: &[u8], index: u64, siblings: &[H256]) -> H256 {
+    let mut hash = element_hash(element);
+    for (level, sibling) in siblings.iter().enumerate() {
+        hash = if (index >> level) & 1 == 0 {
+            node_hash(&hash, sibling)
+        } else {
+            node_hash(sibling, &hash)
+        };
+    }
+    hash
+}
+
+/// Whether `n` is prime, as the sizes of the Ethash datasets and caches divided by their items.
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut divisor = 2;
+    while divisor * divisor <= n {
+        if n % divisor == 0 {
+            return false;
+        }
+        divisor += 1;
+    }
+    true
+}
+
+/// The FNV-1 combination of `a` and `b`, mixing Ethash words.
+pub fn fnv(a: u32, b: u32) -> u32 {
+    a.wrapping_mul(FNV_PRIME) ^ b
+}
+
+/// The little endian 32 bit word `i` of `bytes`.
+pub fn le_word(bytes: &[u8], i: usize) -> u32 {
+    u32::from(bytes[4 * i])
+        | u32::from(bytes[4 * i + 1]) << 8
+        | u32::from(bytes[4 * i + 2]) << 16
+        | u32::from(bytes[4 * i + 3]) << 24
+}
+
+/// Keccak-512 of `data`.
+pub fn keccak_512(data: &[u8]) -> [u8; 64] {
+    let mut hash = [0u8; 64];
+    let mut keccak = Keccak::new_keccak512();
+    keccak.update(data);
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// The largest seal hash meeting `difficulty`.
+fn boundary(difficulty: &U256) -> U256 {
+    if *difficulty <= U256::one() {
+        U256::max_value()
+    } else {
+        ((U256::one() << 255) / *difficulty) << 1
+    }
+}
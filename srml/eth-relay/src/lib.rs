@@ -0,0 +1,286 @@
+//! # Ethereum Relay Module
+//!
+//! An Ethereum light client: headers relayed by anyone, verified and chained on-chain.
+//!
+//! Relayers submit RLP encoded headers, each a child of a relayed header, starting from the
+//! `genesis_header` of the chain spec. A header is verified up to `T::VerificationLevel`:
+//! its parent and number always, then its timestamp, gas and Ethash difficulty, then its
+//! Ethash seal. The relayed chain with the most total difficulty is canonical.
+//!
+//! The seal is recomputed from the Ethash dataset elements it mixes, submitted with the header
+//! and proved against the Merkle root of the dataset of its epoch. The roots are set in the
+//! chain spec or by root, ahead of the epochs: anyone can check them by generating the
+//! datasets.
+//!
+//! Only the headers up to `T::HeadersToKeep` below the best one are kept, forks included:
+//! older headers are pruned as the best header advances.
+//!
+//! Other modules verify receipts of Ethereum transactions through `VerifyEthReceipts`: a
+//! receipt is proved by the trie nodes from the receipts root of a canonical header, with at
+//! least `T::Confirmations` headers on top of it, down to the receipt.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use primitive_types::{H256, U256};
+use rstd::prelude::*;
+#[cfg(feature = "std")]
+use runtime_io::with_storage;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::Result;
+use srml_support::traits::Get;
+use system::ensure_signed;
+
+pub mod ethash;
+mod trie;
+mod types;
+
+pub use ethash::{DatasetProof, EthashParams};
+pub use trie::verify_proof;
+pub use types::{EthHeader, EthReceiptProof, HeaderInfo, LogEntry, Receipt, TransactionOutcome};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The most bytes of extra data in a header.
+const MAX_EXTRA_DATA_LEN: usize = 32;
+
+/// The least gas limit of a header.
+const MIN_GAS_LIMIT: u64 = 5000;
+
+/// Divisor of the parent gas limit bounding the gas limit change of a header.
+const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+
+/// The most heights pruned by a relayed header, when a longer fork becomes the best chain.
+const MAX_PRUNED_HEIGHTS: u64 = 4;
+
+/// How much of a relayed header is verified.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum VerificationLevel {
+    /// Only that it is the child of a relayed header, for tests with made up headers.
+    Parent,
+    /// Also its timestamp, gas and difficulty.
+    Difficulty,
+    /// Also its Ethash seal.
+    Seal,
+}
+
+/// Something that verifies receipts of Ethereum transactions.
+pub trait VerifyEthReceipts {
+    /// The receipt proved by `proof`.
+    fn verify_receipt(proof: &EthReceiptProof) -> rstd::result::Result<Receipt, &'static str>;
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+    /// How much of a relayed header is verified.
+    type VerificationLevel: Get<VerificationLevel>;
+
+    /// The Ethash parameters of the relayed network.
+    type EthashParams: Get<EthashParams>;
+
+    /// Number of headers on top of a header before its receipts are verified.
+    type Confirmations: Get<u64>;
+
+    /// Number of headers kept below the best one, before they are pruned.
+    type HeadersToKeep: Get<u64>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as EthRelay {
+        /// Relayed headers, by hash.
+        pub Headers get(headers): map H256 => Option<HeaderInfo>;
+
+        /// The hash of the canonical header at a height.
+        pub CanonicalHashes get(canonical_hash): map u64 => Option<H256>;
+
+        /// The hash of the relayed header with the most total difficulty.
+        pub BestHash get(best_hash): H256;
+
+        /// The number of the best header.
+        pub BestNumber get(best_number): u64;
+
+        /// The hashes of the relayed headers at a height.
+        pub HeaderHashes get(header_hashes): map u64 => Vec<H256>;
+
+        /// The lowest height of the headers not pruned yet.
+        pub OldestNumber get(oldest_number): u64;
+
+        /// The Merkle root of the Ethash dataset of an epoch.
+        pub DatasetRoots get(dataset_root): map u64 => Option<H256>;
+    }
+    add_extra_genesis {
+        /// The RLP encoded header relayed headers start from.
+        config(genesis_header): Vec<u8>;
+        /// The dataset roots of the first epochs, by epoch.
+        config(dataset_roots): Vec<(u64, H256)>;
+        build(|
+            storage: &mut primitives::StorageOverlay,
+            _: &mut primitives::ChildrenStorageOverlay,
+            config: &GenesisConfig
+        | {
+            with_storage(storage, || {
+                let header = EthHeader::decode_rlp(&config.genesis_header).expect("invalid genesis header");
+                OldestNumber::put(header.number);
+                <Module<T>>::import_header(&header, U256::zero());
+                for &(epoch, root) in &config.dataset_roots {
+                    DatasetRoots::insert(epoch, root);
+                }
+            });
+        });
+    }
+}
+
+decl_event!(
+    pub enum Event {
+        /// A header was relayed.
+        HeaderRelayed(u64, H256),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const Confirmations: u64 = T::Confirmations::get();
+
+        const HeadersToKeep: u64 = T::HeadersToKeep::get();
+
+        fn deposit_event() = default;
+
+        /// Relay an RLP encoded header, whose parent has been relayed, with the proofs of the
+        /// dataset elements its seal mixes.
+        fn relay_header(origin, header: Vec<u8>, dataset_proofs: Vec<DatasetProof>) {
+            let _ = ensure_signed(origin)?;
+
+            let header = EthHeader::decode_rlp(&header).map_err(|_| "invalid header")?;
+            ensure!(!Headers::exists(header.hash), "header already relayed");
+            let parent = Self::headers(header.parent_hash).ok_or("parent header not relayed")?;
+            Self::verify_header(&header, &parent, &dataset_proofs)?;
+
+            Self::import_header(&header, parent.total_difficulty);
+            Self::prune();
+
+            Self::deposit_event(Event::HeaderRelayed(header.number, header.hash));
+        }
+
+        /// Set the dataset roots of the epochs from `first_epoch` on.
+        fn set_dataset_roots(first_epoch: u64, roots: Vec<H256>) {
+            for (epoch, root) in (first_epoch..).zip(roots) {
+                DatasetRoots::insert(epoch, root);
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The receipt proved by `proof`, if its block is canonical and confirmed.
+    pub fn verify_receipt(proof: &EthReceiptProof) -> rstd::result::Result<Receipt, &'static str> {
+        let header = Self::headers(proof.block_hash).ok_or("header not relayed")?;
+        ensure!(Self::canonical_hash(header.number) == Some(proof.block_hash), "header not canonical");
+        ensure!(
+            Self::best_number() >= header.number.saturating_add(T::Confirmations::get()),
+            "header not confirmed"
+        );
+
+        let receipt = verify_proof(&header.receipts_root, &rlp::encode(&proof.index), &proof.proof)?;
+        Receipt::decode_rlp(&receipt).map_err(|_| "invalid receipt")
+    }
+
+    fn verify_header(header: &EthHeader, parent: &HeaderInfo, dataset_proofs: &[DatasetProof]) -> Result {
+        ensure!(header.number == parent.number + 1, "header number is not its parent number + 1");
+
+        let level = T::VerificationLevel::get();
+        if level >= VerificationLevel::Difficulty {
+            ensure!(header.timestamp > parent.timestamp, "header timestamp is not after its parent");
+            ensure!(header.extra_data.len() <= MAX_EXTRA_DATA_LEN, "header extra data too long");
+            ensure!(header.gas_used <= header.gas_limit, "header gas used above its gas limit");
+
+            let bound = parent.gas_limit / U256::from(GAS_LIMIT_BOUND_DIVISOR);
+            ensure!(
+                header.gas_limit >= U256::from(MIN_GAS_LIMIT)
+                    && header.gas_limit > parent.gas_limit.saturating_sub(bound)
+                    && header.gas_limit < parent.gas_limit.saturating_add(bound),
+                "invalid header gas limit"
+            );
+
+            ensure!(
+                header.difficulty == ethash::calculate_difficulty(&T::EthashParams::get(), header, parent),
+                "invalid header difficulty"
+            );
+        }
+        if level >= VerificationLevel::Seal {
+            let root = Self::dataset_root(header.number / ethash::EPOCH_LENGTH).ok_or("dataset root not set")?;
+            ensure!(ethash::verify_seal(header, &root, dataset_proofs), "invalid header seal");
+        }
+
+        Ok(())
+    }
+
+    /// Store `header`, a child of a header with `parent_total_difficulty`, and make it the
+    /// best header if it has the most total difficulty.
+    fn import_header(header: &EthHeader, parent_total_difficulty: U256) {
+        let total_difficulty = parent_total_difficulty.saturating_add(header.difficulty);
+        Headers::insert(header.hash, HeaderInfo {
+            parent_hash: header.parent_hash,
+            number: header.number,
+            timestamp: header.timestamp,
+            uncles_hash: header.uncles_hash,
+            receipts_root: header.receipts_root,
+            difficulty: header.difficulty,
+            gas_limit: header.gas_limit,
+            total_difficulty,
+        });
+        HeaderHashes::mutate(header.number, |hashes| hashes.push(header.hash));
+
+        if let Some(best) = Self::headers(Self::best_hash()) {
+            if total_difficulty <= best.total_difficulty {
+                return;
+            }
+        }
+
+        // the new best header may be on another fork, lower than the old one: drop the
+        // canonical hashes above it and make its ancestors canonical down to the common
+        // ancestor
+        for number in header.number + 1..=Self::best_number() {
+            CanonicalHashes::remove(number);
+        }
+        BestHash::put(header.hash);
+        BestNumber::put(header.number);
+
+        let mut hash = header.hash;
+        while let Some(info) = Self::headers(hash) {
+            if Self::canonical_hash(info.number) == Some(hash) {
+                break;
+            }
+            CanonicalHashes::insert(info.number, hash);
+            hash = info.parent_hash;
+        }
+    }
+
+    /// Remove the headers more than `T::HeadersToKeep` below the best one, a few heights at a
+    /// time.
+    fn prune() {
+        let until = Self::best_number().saturating_sub(T::HeadersToKeep::get());
+        let oldest = Self::oldest_number();
+        let pruned = rstd::cmp::min(until, oldest.saturating_add(MAX_PRUNED_HEIGHTS));
+        for number in oldest..pruned {
+            for hash in HeaderHashes::take(number) {
+                Headers::remove(hash);
+            }
+            CanonicalHashes::remove(number);
+        }
+        if pruned > oldest {
+            OldestNumber::put(pruned);
+        }
+    }
+}
+
+impl<T: Trait> VerifyEthReceipts for Module<T> {
+    fn verify_receipt(proof: &EthReceiptProof) -> rstd::result::Result<Receipt, &'static str> {
+        Self::verify_receipt(proof)
+    }
+}
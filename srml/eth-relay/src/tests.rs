@@ -0,0 +1,239 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use std::collections::BTreeMap;
+use hex_literal::hex;
+use primitive_types::H512;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use mock::*;
+use super::*;
+
+fn relay(headers: Vec<Vec<u8>>) {
+    for header in headers {
+        assert_ok!(EthRelay::relay_header(Origin::signed(1), header, vec![]));
+    }
+}
+
+/// The root of a dataset tree of epoch 0 with the elements mainnet block 1 mixes and zero
+/// leaves elsewhere, and the proofs of these elements.
+fn mainnet_block_1_dataset() -> (H256, Vec<DatasetProof>) {
+    let depth = ethash::dataset_depth(ethash::dataset_size(0));
+    let leaves: BTreeMap<u64, H256> = MAINNET_BLOCK_1_DATASET.iter()
+        .map(|(index, element)| (*index, ethash::element_hash(element)))
+        .collect();
+    let mut levels = vec![leaves];
+    let mut empty = vec![H256::zero()];
+    for level in 0..depth {
+        let mut parents = BTreeMap::new();
+        for (index, hash) in &levels[level] {
+            let sibling = levels[level].get(&(index ^ 1)).cloned().unwrap_or(empty[level]);
+            let parent = if index & 1 == 0 {
+                ethash::node_hash(hash, &sibling)
+            } else {
+                ethash::node_hash(&sibling, hash)
+            };
+            parents.insert(index / 2, parent);
+        }
+        empty.push(ethash::node_hash(&empty[level], &empty[level]));
+        levels.push(parents);
+    }
+
+    let proofs = MAINNET_BLOCK_1_DATASET.iter().map(|(index, element)| DatasetProof {
+        nodes: (H512::from_slice(&element[..64]), H512::from_slice(&element[64..])),
+        siblings: (0..depth)
+            .map(|level| levels[level].get(&((index >> level) ^ 1)).cloned().unwrap_or(empty[level]))
+            .collect(),
+    }).collect();
+    (levels[depth][&0], proofs)
+}
+
+fn info(header: &EthHeader) -> HeaderInfo {
+    HeaderInfo {
+        parent_hash: header.parent_hash,
+        number: header.number,
+        timestamp: header.timestamp,
+        uncles_hash: header.uncles_hash,
+        receipts_root: header.receipts_root,
+        difficulty: header.difficulty,
+        gas_limit: header.gas_limit,
+        total_difficulty: header.difficulty,
+    }
+}
+
+#[test]
+fn headers_should_decode() {
+    let header = EthHeader::decode_rlp(&mainnet_block_0()).unwrap();
+    assert_eq!(header.hash, H256(MAINNET_BLOCK_0_HASH));
+    assert_eq!(header.number, 0);
+    assert_eq!(header.difficulty, U256::from(0x400000000u64));
+    assert_eq!(header.seal.len(), 2);
+
+    let header = EthHeader::decode_rlp(&mainnet_block_1()).unwrap();
+    assert_eq!(header.hash, H256(MAINNET_BLOCK_1_HASH));
+    assert_eq!(header.bare_hash, H256(hex!("85913a3057ea8bec78cd916871ca73802e77724e014dda65add3405d02240eb7")));
+    assert_eq!(header.parent_hash, H256(MAINNET_BLOCK_0_HASH));
+    assert_eq!(header.number, 1);
+    assert_eq!(header.timestamp, 1438269988);
+    assert_eq!(header.ethash_seal(), Some((
+        H256(hex!("969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59")),
+        hex!("539bd4979fef1ec4"),
+    )));
+
+    let mut trailing = mainnet_block_1();
+    trailing.push(0);
+    assert!(EthHeader::decode_rlp(&trailing).is_err());
+}
+
+#[test]
+fn difficulty_should_be_calculated() {
+    let genesis = EthHeader::decode_rlp(&mainnet_block_0()).unwrap();
+    let mut header = EthHeader::decode_rlp(&mainnet_block_1()).unwrap();
+    assert_eq!(ethash::calculate_difficulty(&ethash::MAINNET, &header, &info(&genesis)), header.difficulty);
+
+    // Byzantium, 20 seconds after a parent without uncles, with the bomb 2_000_000 blocks in
+    let parent = HeaderInfo {
+        number: 4_999_999,
+        timestamp: 1_500_000_000,
+        uncles_hash: genesis.uncles_hash,
+        difficulty: U256::from(3_000_000_000_000_000u64),
+        ..Default::default()
+    };
+    header.number = 5_000_000;
+    header.timestamp = 1_500_000_020;
+    assert_eq!(
+        ethash::calculate_difficulty(&ethash::MAINNET, &header, &parent),
+        U256::from(3_000_000_000_000_000u64 - 1_464_843_750_000 + (1 << 18))
+    );
+}
+
+#[test]
+fn dataset_should_be_sized() {
+    assert_eq!(ethash::dataset_size(0), 1_073_739_904);
+    assert_eq!(ethash::dataset_size(1), 1_082_130_304);
+    assert_eq!(ethash::dataset_depth(ethash::dataset_size(0)), 23);
+}
+
+#[test]
+fn seal_should_be_verified() {
+    let header = EthHeader::decode_rlp(&mainnet_block_1()).unwrap();
+    let (root, proofs) = mainnet_block_1_dataset();
+    assert!(ethash::verify_seal(&header, &root, &proofs));
+
+    let mut harder = header.clone();
+    harder.difficulty = harder.difficulty * U256::from(1u64 << 40);
+    assert!(!ethash::verify_seal(&harder, &root, &proofs));
+
+    let mut other_nonce = header.clone();
+    other_nonce.seal[1] = rlp::encode(&hex!("539bd4979fef1ec5").to_vec());
+    assert!(!ethash::verify_seal(&other_nonce, &root, &proofs));
+
+    // a mix hash not computed from the dataset
+    let mut other_mix_hash = header.clone();
+    other_mix_hash.seal[0] = rlp::encode(&H256::repeat_byte(1));
+    assert!(!ethash::verify_seal(&other_mix_hash, &root, &proofs));
+
+    // elements of another dataset, or not in the dataset
+    assert!(!ethash::verify_seal(&header, &H256::repeat_byte(1), &proofs));
+    let mut forged = proofs.clone();
+    forged[10].nodes.1 = H512::repeat_byte(1);
+    assert!(!ethash::verify_seal(&header, &root, &forged));
+    let mut forged = proofs.clone();
+    forged[10].siblings.pop();
+    assert!(!ethash::verify_seal(&header, &root, &forged));
+    assert!(!ethash::verify_seal(&header, &root, &proofs[1..]));
+
+    assert!(!ethash::verify_seal(&EthHeader::decode_rlp(&block_2()).unwrap(), &root, &proofs));
+}
+
+#[test]
+fn relay_header_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(EthRelay::best_number(), 0);
+        assert_eq!(EthRelay::best_hash(), H256(MAINNET_BLOCK_0_HASH));
+        assert_eq!(EthRelay::canonical_hash(0), Some(H256(MAINNET_BLOCK_0_HASH)));
+
+        assert_noop!(EthRelay::relay_header(Origin::signed(1), vec![0xc0], vec![]), "invalid header");
+        assert_noop!(EthRelay::relay_header(Origin::signed(1), block_2(), vec![]), "parent header not relayed");
+
+        relay(vec![mainnet_block_1()]);
+        assert_eq!(EthRelay::best_number(), 1);
+        assert_eq!(EthRelay::canonical_hash(1), Some(H256(MAINNET_BLOCK_1_HASH)));
+        assert_eq!(
+            EthRelay::headers(H256(MAINNET_BLOCK_1_HASH)).unwrap().total_difficulty,
+            U256::from(0x400000000u64 + 17171480576u64)
+        );
+        assert_noop!(EthRelay::relay_header(Origin::signed(2), mainnet_block_1(), vec![]), "header already relayed");
+    });
+}
+
+#[test]
+fn relayed_headers_should_be_verified() {
+    with_externalities(&mut new_test_ext(), || {
+        set_verification_level(VerificationLevel::Difficulty);
+        assert_noop!(EthRelay::relay_header(Origin::signed(1), fork_block_1(), vec![]), "invalid header difficulty");
+        relay(vec![mainnet_block_1(), block_2(), block_3()]);
+    });
+
+    with_externalities(&mut new_test_ext(), || {
+        set_verification_level(VerificationLevel::Seal);
+        let (root, proofs) = mainnet_block_1_dataset();
+        assert_noop!(
+            EthRelay::relay_header(Origin::signed(1), mainnet_block_1(), proofs.clone()),
+            "dataset root not set"
+        );
+
+        assert_ok!(EthRelay::set_dataset_roots(0, vec![root]));
+        assert_eq!(EthRelay::dataset_root(0), Some(root));
+        assert_noop!(
+            EthRelay::relay_header(Origin::signed(1), mainnet_block_1(), proofs[1..].to_vec()),
+            "invalid header seal"
+        );
+        assert_ok!(EthRelay::relay_header(Origin::signed(1), mainnet_block_1(), proofs));
+        assert_noop!(EthRelay::relay_header(Origin::signed(1), block_2(), vec![]), "invalid header seal");
+    });
+}
+
+#[test]
+fn most_difficult_chain_should_be_canonical() {
+    with_externalities(&mut new_test_ext(), || {
+        relay(vec![mainnet_block_1(), block_2(), block_3()]);
+        assert_eq!(EthRelay::best_number(), 3);
+        assert_eq!(EthRelay::canonical_hash(3), Some(H256(BLOCK_3_HASH)));
+
+        // shorter, but with more difficulty
+        relay(vec![fork_block_1()]);
+        assert_eq!(EthRelay::best_hash(), H256(FORK_BLOCK_1_HASH));
+        assert_eq!(EthRelay::best_number(), 1);
+        assert_eq!(EthRelay::canonical_hash(1), Some(H256(FORK_BLOCK_1_HASH)));
+        assert_eq!(EthRelay::canonical_hash(2), None);
+        assert_eq!(EthRelay::canonical_hash(3), None);
+        assert_eq!(EthRelay::canonical_hash(0), Some(H256(MAINNET_BLOCK_0_HASH)));
+    });
+}
+
+#[test]
+fn old_headers_should_be_pruned() {
+    with_externalities(&mut new_test_ext(), || {
+        set_headers_to_keep(1);
+        relay(vec![mainnet_block_1()]);
+        assert_eq!(EthRelay::oldest_number(), 0);
+
+        relay(vec![block_2()]);
+        assert_eq!(EthRelay::oldest_number(), 1);
+        assert_eq!(EthRelay::headers(H256(MAINNET_BLOCK_0_HASH)), None);
+        assert_eq!(EthRelay::canonical_hash(0), None);
+        assert!(EthRelay::header_hashes(0).is_empty());
+        assert_noop!(
+            EthRelay::relay_header(Origin::signed(1), fork_block_1(), vec![]),
+            "parent header not relayed"
+        );
+
+        relay(vec![block_3()]);
+        assert_eq!(EthRelay::oldest_number(), 2);
+        assert_eq!(EthRelay::headers(H256(MAINNET_BLOCK_1_HASH)), None);
+        assert_eq!(EthRelay::header_hashes(2), vec![H256(BLOCK_2_HASH)]);
+        assert_eq!(EthRelay::canonical_hash(2), Some(H256(BLOCK_2_HASH)));
+    });
+}
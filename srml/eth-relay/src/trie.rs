@@ -0,0 +1,129 @@
+//! Proofs of values in an Ethereum Merkle-Patricia trie.
+
+use primitive_types::H256;
+use rlp::{DecoderError, Rlp};
+use rstd::{collections::btree_map::BTreeMap, prelude::*};
+
+fn invalid_node(_: DecoderError) -> &'static str {
+    "invalid trie node"
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        nibbles.push(b >> 4);
+        nibbles.push(b & 0x0f);
+    }
+    nibbles
+}
+
+/// Decode the hex-prefix encoded path of a leaf or extension node, returning the nibbles and
+/// whether the node is a leaf.
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), &'static str> {
+    let (first, rest) = encoded.split_first().ok_or("invalid trie node")?;
+    let is_leaf = first & 0x20 != 0;
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if first & 0x10 != 0 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend_from_slice(&to_nibbles(rest));
+
+    Ok((nibbles, is_leaf))
+}
+
+/// The value under `key` in the trie with root `root`, given the RLP encoded nodes on the
+/// path to it, in any order.
+///
+/// Nodes shorter than 32 bytes are embedded in their parent, as in Ethereum.
+pub fn verify_proof(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> Result<Vec<u8>, &'static str> {
+    let nodes: BTreeMap<H256, &[u8]> = proof
+        .iter()
+        .map(|node| (H256(runtime_io::keccak_256(node)), &node[..]))
+        .collect();
+
+    let nibbles = to_nibbles(key);
+    let mut path = &nibbles[..];
+    let mut node = nodes.get(root).cloned().ok_or("root node missing from the proof")?;
+
+    loop {
+        let r = Rlp::new(node);
+        let child = match r.item_count().map_err(invalid_node)? {
+            // branch
+            17 => match path.split_first() {
+                Some((&nibble, rest)) => {
+                    path = rest;
+                    r.at(nibble as usize).map_err(invalid_node)?
+                }
+                None => return value(&r.at(16).map_err(invalid_node)?),
+            },
+            // leaf or extension
+            2 => {
+                let (partial, is_leaf) = decode_path(r.at(0).and_then(|p| p.data()).map_err(invalid_node)?)?;
+                if !path.starts_with(&partial) {
+                    return Err("key not in the trie");
+                }
+                path = &path[partial.len()..];
+
+                if is_leaf {
+                    if !path.is_empty() {
+                        return Err("key not in the trie");
+                    }
+                    return value(&r.at(1).map_err(invalid_node)?);
+                }
+
+                r.at(1).map_err(invalid_node)?
+            }
+            _ => return Err("invalid trie node"),
+        };
+
+        node = if child.is_list() {
+            child.as_raw()
+        } else {
+            let hash = child.data().map_err(invalid_node)?;
+            match hash.len() {
+                0 => return Err("key not in the trie"),
+                32 => nodes.get(&H256::from_slice(hash)).cloned().ok_or("node missing from the proof")?,
+                _ => return Err("invalid trie node"),
+            }
+        };
+    }
+}
+
+fn value(r: &Rlp) -> Result<Vec<u8>, &'static str> {
+    let value = r.data().map_err(invalid_node)?;
+    if value.is_empty() {
+        return Err("key not in the trie");
+    }
+    Ok(value.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use super::*;
+
+    // the `dogs` trie of the Ethereum trie tests, where the node of `dogglesworth` is embedded
+    // in its parent
+    const DOGS_ROOT: [u8; 32] = hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
+
+    fn dogs_proof() -> Vec<Vec<u8>> {
+        vec![
+            hex!("e5831646f6a0db6ae1fda66890f6693f36560d36b4dca68b4d838f17016b151efe1d4c95c453").to_vec(),
+            hex!("f83b8080808080ca20887265696e6465657280a037efd11993cb04a54048c25320e9f29c50a432d28afdf01598b2978ce1ca3068808080808080808080").to_vec(),
+            hex!("e4808080808080ce89376c6573776f72746883636174808080808080808080857075707079").to_vec(),
+        ]
+    }
+
+    #[test]
+    fn proofs_should_work() {
+        let root = H256(DOGS_ROOT);
+        assert_eq!(verify_proof(&root, b"dog", &dogs_proof()), Ok(b"puppy".to_vec()));
+        assert_eq!(verify_proof(&root, b"dogglesworth", &dogs_proof()), Ok(b"cat".to_vec()));
+        assert_eq!(verify_proof(&root, b"doe", &dogs_proof()[..2]), Ok(b"reindeer".to_vec()));
+
+        assert_eq!(verify_proof(&root, b"do", &dogs_proof()), Err("key not in the trie"));
+        assert_eq!(verify_proof(&root, b"dogs", &dogs_proof()), Err("key not in the trie"));
+        assert_eq!(verify_proof(&root, b"dog", &dogs_proof()[..2]), Err("node missing from the proof"));
+        assert_eq!(verify_proof(&H256::zero(), b"dog", &dogs_proof()), Err("root node missing from the proof"));
+    }
+}
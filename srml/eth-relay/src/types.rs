@@ -0,0 +1,184 @@
+//! Ethereum headers and receipts, decoded from their RLP encoding.
+
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256, U256};
+use rlp::{Decodable, DecoderError, Rlp, RlpStream};
+use rstd::prelude::*;
+
+/// An Ethereum block header.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EthHeader {
+    pub parent_hash: H256,
+    pub uncles_hash: H256,
+    pub author: H160,
+    pub state_root: H256,
+    pub transactions_root: H256,
+    pub receipts_root: H256,
+    pub log_bloom: Vec<u8>,
+    pub difficulty: U256,
+    pub number: u64,
+    pub gas_limit: U256,
+    pub gas_used: U256,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    /// The RLP encoded seal fields: the mix hash and the nonce for Ethash.
+    pub seal: Vec<Vec<u8>>,
+    /// Keccak-256 of the RLP encoded header.
+    pub hash: H256,
+    /// Keccak-256 of the RLP encoded header without the seal, which the seal is computed over.
+    pub bare_hash: H256,
+}
+
+impl EthHeader {
+    /// Decode a header from its RLP encoding.
+    pub fn decode_rlp(bytes: &[u8]) -> Result<Self, DecoderError> {
+        let r = Rlp::new(bytes);
+        if r.payload_info()?.total() != bytes.len() {
+            return Err(DecoderError::RlpIsTooBig);
+        }
+
+        let item_count = r.item_count()?;
+        if item_count < 13 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let mut bare = RlpStream::new_list(13);
+        for i in 0..13 {
+            bare.append_raw(r.at(i)?.as_raw(), 1);
+        }
+
+        let mut seal = Vec::with_capacity(item_count - 13);
+        for i in 13..item_count {
+            seal.push(r.at(i)?.as_raw().to_vec());
+        }
+
+        Ok(EthHeader {
+            parent_hash: r.val_at(0)?,
+            uncles_hash: r.val_at(1)?,
+            author: r.val_at(2)?,
+            state_root: r.val_at(3)?,
+            transactions_root: r.val_at(4)?,
+            receipts_root: r.val_at(5)?,
+            log_bloom: r.val_at(6)?,
+            difficulty: r.val_at(7)?,
+            number: r.val_at(8)?,
+            gas_limit: r.val_at(9)?,
+            gas_used: r.val_at(10)?,
+            timestamp: r.val_at(11)?,
+            extra_data: r.val_at(12)?,
+            seal,
+            hash: H256(runtime_io::keccak_256(bytes)),
+            bare_hash: H256(runtime_io::keccak_256(&bare.out())),
+        })
+    }
+
+    /// The Ethash mix hash and nonce of the seal.
+    pub fn ethash_seal(&self) -> Option<(H256, [u8; 8])> {
+        if self.seal.len() != 2 {
+            return None;
+        }
+
+        let mix_hash = rlp::decode(&self.seal[0]).ok()?;
+        let nonce: Vec<u8> = rlp::decode(&self.seal[1]).ok()?;
+        if nonce.len() != 8 {
+            return None;
+        }
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&nonce);
+        Some((mix_hash, bytes))
+    }
+}
+
+/// What the module keeps of a relayed header.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct HeaderInfo {
+    pub parent_hash: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub uncles_hash: H256,
+    pub receipts_root: H256,
+    pub difficulty: U256,
+    pub gas_limit: U256,
+    /// The difficulty of the header and its relayed ancestors.
+    pub total_difficulty: U256,
+}
+
+/// A log emitted by a contract.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct LogEntry {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Vec<u8>,
+}
+
+impl Decodable for LogEntry {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        if r.item_count()? != 3 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        Ok(LogEntry {
+            address: r.val_at(0)?,
+            topics: r.list_at(1)?,
+            data: r.val_at(2)?,
+        })
+    }
+}
+
+/// The outcome of a transaction: the state root after it before Byzantium, its status after.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TransactionOutcome {
+    StateRoot(H256),
+    Status(u8),
+}
+
+/// The receipt of a transaction.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Receipt {
+    pub outcome: TransactionOutcome,
+    /// Gas used by the block up to and including this transaction.
+    pub gas_used: U256,
+    pub log_bloom: Vec<u8>,
+    pub logs: Vec<LogEntry>,
+}
+
+impl Receipt {
+    /// Decode a receipt from its RLP encoding.
+    pub fn decode_rlp(bytes: &[u8]) -> Result<Self, DecoderError> {
+        let r = Rlp::new(bytes);
+        if r.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let outcome = if r.at(0)?.size() == 32 {
+            TransactionOutcome::StateRoot(r.val_at(0)?)
+        } else {
+            TransactionOutcome::Status(r.val_at(0)?)
+        };
+
+        Ok(Receipt {
+            outcome,
+            gas_used: r.val_at(1)?,
+            log_bloom: r.val_at(2)?,
+            logs: r.list_at(3)?,
+        })
+    }
+}
+
+/// A receipt of a relayed block, proved by the trie nodes from the receipts root to it.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EthReceiptProof {
+    /// The hash of the block.
+    pub block_hash: H256,
+    /// The index of the transaction in the block.
+    pub index: u64,
+    /// The RLP encoded trie nodes on the path to the receipt.
+    pub proof: Vec<Vec<u8>>,
+}
@@ -0,0 +1,288 @@
+//! # I'm Online Module
+//!
+//! Tracks which validators are alive in each session.
+//!
+//! Every block, the offchain worker of a validator node checks whether its authority key
+//! already sent a heartbeat in the current session, and if not, signs one with that key
+//! and submits it as an unsigned `heartbeat` transaction. When the session ends, every
+//! validator of the session whose heartbeat did not make it on chain is handed to
+//! `HandleOffline`, which the runtime wires to `staking::on_offline_validator` through
+//! [`StakingSlasher`](./struct.StakingSlasher.html).
+//!
+//! Heartbeats can be signed with the session key of another module, such as Aura, by
+//! registering [`SharedKey`](./struct.SharedKey.html) in place of both session handlers.
+//!
+//! Unlike inferring offline validators from skipped Aura slots, this does not depend on the
+//! clock of the next block author, and also catches validators which never get a slot.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{Decode, Encode};
+use primitives::traits::{Extrinsic as ExtrinsicT, Member, ValidateUnsigned, Verify};
+use primitives::transaction_validity::{TransactionLongevity, TransactionPriority, TransactionValidity};
+use primitives::ApplyError;
+use rstd::{prelude::*, result};
+use session::SessionIndex;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, print, Parameter, StorageMap, StorageValue};
+use substrate_primitives::offchain::{CryptoKind, OpaqueNetworkState, StorageKind};
+use system::ensure_none;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Index of a validator in the `Keys` of a session.
+pub type AuthIndex = u32;
+
+/// Local storage key of the offchain worker status.
+const WORKER_STATUS_KEY: &[u8] = b"darwinia/im-online-worker-status";
+
+/// Blocks to wait for a submitted heartbeat to be included before sending it again.
+const RESEND_AFTER: u32 = 10;
+
+/// Proof of a validator being online in a session, signed with its session key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Heartbeat<BlockNumber> {
+    /// Block the heartbeat was sent at.
+    pub block_number: BlockNumber,
+    /// Network addresses of the validator node.
+    pub network_state: OpaqueNetworkState,
+    /// The session the validator is online in.
+    pub session_index: SessionIndex,
+    /// Index of the validator in the session `Keys`.
+    pub authority_index: AuthIndex,
+}
+
+/// Something that punishes validators without a heartbeat in a session.
+pub trait HandleOffline<AccountId> {
+    fn handle_offline(validator: &AccountId);
+}
+
+impl<AccountId> HandleOffline<AccountId> for () {
+    fn handle_offline(_validator: &AccountId) {}
+}
+
+pub trait Trait: system::Trait + session::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The runtime call type, to build heartbeat transactions.
+    type Call: From<Call<Self>>;
+
+    /// The runtime extrinsic type, to submit heartbeat transactions.
+    type UncheckedExtrinsic: ExtrinsicT<Call = <Self as Trait>::Call> + Encode + Decode;
+
+    /// The session key heartbeats are signed with.
+    type AuthorityId: Member + Parameter + Default;
+
+    /// Signature of a heartbeat.
+    type Signature: Verify<Signer = Self::AuthorityId> + Member + Parameter;
+
+    /// Punishes validators without a heartbeat in a session.
+    type HandleOffline: HandleOffline<Self::AccountId>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as ImOnline {
+        /// The validators of the current session and their session keys.
+        Keys get(keys) config(): Vec<(T::AccountId, T::AuthorityId)>;
+
+        /// Whether the validator at the given index sent a heartbeat in the given session.
+        ReceivedHeartbeats get(received_heartbeats): map (SessionIndex, AuthIndex) => bool;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// A heartbeat was received from the given validator.
+        HeartbeatReceived(AccountId),
+        /// The given validators sent no heartbeat in the session that just ended.
+        SomeOffline(Vec<AccountId>),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        /// Record that a validator is online in the current session.
+        ///
+        /// This is an unsigned transaction, checked by `validate_unsigned`.
+        fn heartbeat(origin, heartbeat: Heartbeat<T::BlockNumber>, signature: T::Signature) {
+            ensure_none(origin)?;
+
+            let validator = Self::check_heartbeat(&heartbeat, &signature)?;
+            ReceivedHeartbeats::insert((heartbeat.session_index, heartbeat.authority_index), true);
+
+            Self::deposit_event(RawEvent::HeartbeatReceived(validator));
+        }
+
+        fn offchain_worker(now: T::BlockNumber) {
+            if runtime_io::is_validator() {
+                if let Err(e) = Self::send_heartbeat(now) {
+                    print(e);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Check `heartbeat` was signed by a validator of the current session which has not
+    /// sent one yet, returning the validator.
+    pub fn check_heartbeat(
+        heartbeat: &Heartbeat<T::BlockNumber>,
+        signature: &T::Signature,
+    ) -> result::Result<T::AccountId, &'static str> {
+        ensure!(
+            heartbeat.session_index == <session::Module<T>>::current_index(),
+            "heartbeat is not for the current session"
+        );
+        ensure!(
+            !Self::received_heartbeats((heartbeat.session_index, heartbeat.authority_index)),
+            "heartbeat already received"
+        );
+
+        let (validator, key) = Self::keys()
+            .get(heartbeat.authority_index as usize)
+            .cloned()
+            .ok_or("unknown authority index")?;
+        ensure!(signature.verify(&heartbeat.encode()[..], &key), "bad heartbeat signature");
+
+        Ok(validator)
+    }
+
+    fn send_heartbeat(now: T::BlockNumber) -> result::Result<(), &'static str> {
+        let local_key = runtime_io::authority_pubkey(CryptoKind::Ed25519)
+            .map_err(|_| "no local authority key")?;
+        let key = T::AuthorityId::decode(&mut &local_key[..]).ok_or("cannot decode local authority key")?;
+        let authority_index = match Self::keys().iter().position(|(_, k)| *k == key) {
+            Some(index) => index as AuthIndex,
+            // not a validator in this session
+            None => return Ok(()),
+        };
+
+        let session_index = <session::Module<T>>::current_index();
+        if Self::received_heartbeats((session_index, authority_index)) {
+            return Ok(());
+        }
+        let status = runtime_io::local_storage_get(StorageKind::PERSISTENT, WORKER_STATUS_KEY)
+            .and_then(|s| <(SessionIndex, T::BlockNumber)>::decode(&mut &s[..]));
+        if let Some((sent_in, sent_at)) = status {
+            if sent_in == session_index && now < sent_at + RESEND_AFTER.into() {
+                return Ok(());
+            }
+        }
+
+        let heartbeat = Heartbeat {
+            block_number: now,
+            network_state: runtime_io::network_state().map_err(|_| "cannot get network state")?,
+            session_index,
+            authority_index,
+        };
+        let signature = runtime_io::sign(None, CryptoKind::Ed25519, &heartbeat.encode())
+            .map_err(|_| "cannot sign heartbeat")?;
+        let signature = T::Signature::decode(&mut &signature[..]).ok_or("cannot decode heartbeat signature")?;
+
+        let call = Call::heartbeat(heartbeat, signature);
+        let ex = T::UncheckedExtrinsic::new_unsigned(call.into()).ok_or("cannot create heartbeat extrinsic")?;
+        runtime_io::submit_transaction(&ex).map_err(|_| "cannot submit heartbeat")?;
+
+        runtime_io::local_storage_set(StorageKind::PERSISTENT, WORKER_STATUS_KEY, &(session_index, now).encode());
+        Ok(())
+    }
+}
+
+impl<T: Trait> session::OneSessionHandler<T::AccountId> for Module<T> {
+    type Key = T::AuthorityId;
+
+    fn on_new_session<'a, I: 'a>(_changed: bool, validators: I)
+        where I: Iterator<Item=(&'a T::AccountId, T::AuthorityId)>
+    {
+        // the session index has already been increased.
+        let ended = <session::Module<T>>::current_index().saturating_sub(1);
+        let offline = Self::keys()
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !ReceivedHeartbeats::take((ended, *i as AuthIndex)))
+            .map(|(_, (validator, _))| validator)
+            .collect::<Vec<_>>();
+
+        <Keys<T>>::put(validators.map(|(v, k)| (v.clone(), k)).collect::<Vec<_>>());
+
+        if !offline.is_empty() {
+            for validator in offline.iter() {
+                T::HandleOffline::handle_offline(validator);
+            }
+            Self::deposit_event(RawEvent::SomeOffline(offline));
+        }
+    }
+
+    fn on_disabled(_i: usize) {}
+}
+
+impl<T: Trait> ValidateUnsigned for Module<T> {
+    type Call = Call<T>;
+
+    fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+        match call {
+            Call::heartbeat(heartbeat, signature) => {
+                if heartbeat.session_index != <session::Module<T>>::current_index()
+                    || Self::received_heartbeats((heartbeat.session_index, heartbeat.authority_index))
+                {
+                    return TransactionValidity::Invalid(ApplyError::Stale as i8);
+                }
+                if Self::check_heartbeat(heartbeat, signature).is_err() {
+                    return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
+                }
+
+                TransactionValidity::Valid {
+                    priority: TransactionPriority::max_value(),
+                    requires: vec![],
+                    provides: vec![(heartbeat.session_index, heartbeat.authority_index).encode()],
+                    longevity: TransactionLongevity::max_value(),
+                    propagate: true,
+                }
+            }
+            _ => TransactionValidity::Invalid(0),
+        }
+    }
+}
+
+/// A type for reporting validators without a heartbeat to staking.
+pub struct StakingSlasher<T>(rstd::marker::PhantomData<T>);
+
+impl<T: staking::Trait> HandleOffline<T::AccountId> for StakingSlasher<T> {
+    fn handle_offline(validator: &T::AccountId) {
+        staking::Module::<T>::on_offline_validator(validator.clone(), 1);
+    }
+}
+
+/// A session handler handing the session keys of `H` to both `H` and ImOnline, for runtimes
+/// where heartbeats are signed with the key of `H`, so that ImOnline needs no session key of
+/// its own.
+pub struct SharedKey<T, H>(rstd::marker::PhantomData<(T, H)>);
+
+impl<T, H> session::OneSessionHandler<T::AccountId> for SharedKey<T, H>
+where
+    T: Trait,
+    H: session::OneSessionHandler<T::AccountId, Key = T::AuthorityId>,
+{
+    type Key = T::AuthorityId;
+
+    fn on_new_session<'a, I: 'a>(changed: bool, validators: I)
+        where I: Iterator<Item=(&'a T::AccountId, T::AuthorityId)>
+    {
+        let validators = validators.collect::<Vec<_>>();
+        H::on_new_session(changed, validators.iter().cloned());
+        <Module<T> as session::OneSessionHandler<T::AccountId>>::on_new_session(changed, validators.into_iter());
+    }
+
+    fn on_disabled(i: usize) {
+        H::on_disabled(i);
+        <Module<T> as session::OneSessionHandler<T::AccountId>>::on_disabled(i);
+    }
+}
@@ -0,0 +1,133 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use parity_codec::{Encode, Decode};
+use primitives::traits::{BlakeTwo256, IdentityLookup, Lazy, Verify};
+use primitives::testing::{Header, TestXt, UintAuthorityId};
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use crate::{Call, GenesisConfig, HandleOffline, Module, SharedKey, Trait};
+
+pub type AccountId = u64;
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+/// Signature of a test heartbeat: the signing key and the message it signed.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct TestSignature(pub u64, pub Vec<u8>);
+
+impl Verify for TestSignature {
+    type Signer = UintAuthorityId;
+
+    fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &UintAuthorityId) -> bool {
+        self.0 == signer.0 && &self.1[..] == msg.get()
+    }
+}
+
+thread_local! {
+    static OFFLINE: RefCell<Vec<AccountId>> = RefCell::new(vec![]);
+}
+
+/// Records the validators reported offline.
+pub struct OfflineRecorder;
+impl HandleOffline<AccountId> for OfflineRecorder {
+    fn handle_offline(validator: &AccountId) {
+        OFFLINE.with(|v| v.borrow_mut().push(*validator));
+    }
+}
+
+pub fn offline() -> Vec<AccountId> {
+    OFFLINE.with(|v| v.borrow().clone())
+}
+
+thread_local! {
+    static SHARED_KEYS: RefCell<Vec<(AccountId, UintAuthorityId)>> = RefCell::new(vec![]);
+}
+
+/// Records the keys of the last session, the handler ImOnline shares its key with.
+pub struct KeyRecorder;
+impl session::OneSessionHandler<AccountId> for KeyRecorder {
+    type Key = UintAuthorityId;
+
+    fn on_new_session<'a, I: 'a>(_changed: bool, validators: I)
+        where I: Iterator<Item=(&'a AccountId, UintAuthorityId)>
+    {
+        SHARED_KEYS.with(|k| *k.borrow_mut() = validators.map(|(v, k)| (*v, k)).collect());
+    }
+
+    fn on_disabled(_i: usize) {}
+}
+
+pub fn shared_keys() -> Vec<(AccountId, UintAuthorityId)> {
+    SHARED_KEYS.with(|k| k.borrow().clone())
+}
+
+pub struct TestSessionEnding;
+impl session::OnSessionEnding<AccountId> for TestSessionEnding {
+    fn on_session_ending(_: session::SessionIndex) -> Option<Vec<AccountId>> {
+        None
+    }
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const Period: u64 = 1;
+    pub const Offset: u64 = 0;
+}
+
+impl session::Trait for Test {
+    type OnSessionEnding = TestSessionEnding;
+    type Keys = UintAuthorityId;
+    type ShouldEndSession = session::PeriodicSessions<Period, Offset>;
+    type SessionHandler = (SharedKey<Test, KeyRecorder>,);
+    type Event = ();
+}
+
+impl Trait for Test {
+    type Event = ();
+    type Call = Call<Test>;
+    type UncheckedExtrinsic = TestXt<Call<Test>>;
+    type AuthorityId = UintAuthorityId;
+    type Signature = TestSignature;
+    type HandleOffline = OfflineRecorder;
+}
+
+/// Validators 1, 2 and 3, with session keys of the same number.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let validators = vec![1, 2, 3];
+    let keys = validators.iter().map(|v| (*v, UintAuthorityId(*v))).collect::<Vec<_>>();
+
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(session::GenesisConfig::<Test> {
+        validators,
+        keys: keys.clone(),
+    }.build_storage().unwrap().0);
+    t.extend(GenesisConfig::<Test> {
+        keys,
+    }.build_storage().unwrap().0);
+    t.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Session = session::Module<Test>;
+pub type ImOnline = Module<Test>;
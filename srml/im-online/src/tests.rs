@@ -0,0 +1,86 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use parity_codec::Encode;
+use primitives::traits::OnInitialize;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use substrate_primitives::offchain::{OpaqueNetworkState, OpaquePeerId};
+use crate::{AuthIndex, Heartbeat};
+use primitives::testing::UintAuthorityId;
+use crate::mock::{new_test_ext, offline, shared_keys, ImOnline, Session, System, TestSignature};
+
+/// A heartbeat of the validator at `authority_index` in `session_index`, signed by `signer`.
+fn heartbeat(session_index: u32, authority_index: AuthIndex, signer: u64) -> (Heartbeat<u64>, TestSignature) {
+    let heartbeat = Heartbeat {
+        block_number: 1,
+        network_state: OpaqueNetworkState { peer_id: OpaquePeerId(vec![]), external_addresses: vec![] },
+        session_index,
+        authority_index,
+    };
+    let signature = TestSignature(signer, heartbeat.encode());
+    (heartbeat, signature)
+}
+
+fn next_session() {
+    System::set_block_number(System::block_number() + 1);
+    Session::on_initialize(System::block_number());
+}
+
+#[test]
+fn heartbeat_should_be_recorded_once() {
+    with_externalities(&mut new_test_ext(), || {
+        let (beat, signature) = heartbeat(0, 1, 2);
+        assert_ok!(ImOnline::heartbeat(system::RawOrigin::None.into(), beat.clone(), signature.clone()));
+        assert!(ImOnline::received_heartbeats((0, 1)));
+
+        assert_noop!(
+            ImOnline::heartbeat(system::RawOrigin::None.into(), beat, signature),
+            "heartbeat already received"
+        );
+    });
+}
+
+#[test]
+fn invalid_heartbeats_should_be_rejected() {
+    with_externalities(&mut new_test_ext(), || {
+        let (beat, signature) = heartbeat(0, 1, 3);
+        assert_eq!(ImOnline::check_heartbeat(&beat, &signature), Err("bad heartbeat signature"));
+
+        let (beat, signature) = heartbeat(0, 3, 3);
+        assert_eq!(ImOnline::check_heartbeat(&beat, &signature), Err("unknown authority index"));
+
+        let (beat, signature) = heartbeat(1, 0, 1);
+        assert_eq!(ImOnline::check_heartbeat(&beat, &signature), Err("heartbeat is not for the current session"));
+    });
+}
+
+#[test]
+fn missing_heartbeats_should_be_reported_at_session_end() {
+    with_externalities(&mut new_test_ext(), || {
+        for (index, signer) in [(0, 1), (2, 3)].iter() {
+            let (beat, signature) = heartbeat(0, *index, *signer);
+            assert_ok!(ImOnline::heartbeat(system::RawOrigin::None.into(), beat, signature));
+        }
+
+        next_session();
+        assert_eq!(Session::current_index(), 1);
+        assert_eq!(offline(), vec![2]);
+        assert!(!ImOnline::received_heartbeats((0, 0)));
+
+        // nobody sent a heartbeat in session 1
+        next_session();
+        assert_eq!(offline(), vec![2, 1, 2, 3]);
+    });
+}
+
+#[test]
+fn shared_session_keys_should_reach_both_handlers() {
+    with_externalities(&mut new_test_ext(), || {
+        next_session();
+        let keys = vec![(1, UintAuthorityId(1)), (2, UintAuthorityId(2)), (3, UintAuthorityId(3))];
+        assert_eq!(ImOnline::keys(), keys);
+        assert_eq!(shared_keys(), keys);
+    });
+}
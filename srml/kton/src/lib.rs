@@ -2,20 +2,22 @@
 
 use parity_codec::{Codec, Decode, Encode};
 use primitives::traits::{
-    Bounded, CheckedAdd, CheckedSub, MaybeSerializeDebug, Member, Saturating,
+    Bounded, CheckedAdd, CheckedSub, MaybeSerializeDebug, Member, One, Saturating,
     SimpleArithmetic, StaticLookup, Zero,
 };
 use rstd::{cmp, result};
 use rstd::prelude::*;
 
-use srml_support::{decl_event, decl_module, decl_storage, Parameter, StorageMap, StorageValue};
+use srml_support::{decl_event, decl_module, decl_storage, ensure, Parameter, StorageMap, StorageValue};
 use srml_support::dispatch::Result;
 use srml_support::traits::{
-    Currency, ExistenceRequirement, Imbalance, LockableCurrency, LockIdentifier,
+    Currency, ExistenceRequirement, Get, Imbalance, LockableCurrency, LockIdentifier,
     OnUnbalanced, SignedImbalance, UpdateBalanceOutcome,
     WithdrawReason, WithdrawReasons,
 };
 use system::ensure_signed;
+use dsupport::breakdown::CurrencyBreakdown;
+use dsupport::traits::{OnAccountBalanceChanged, OnMinted};
 
 // customed
 use imbalance::{NegativeImbalance, PositiveImbalance};
@@ -29,6 +31,8 @@ mod tests;
 mod imbalance;
 
 /// Struct to encode the vesting schedule of an individual account.
+///
+/// An account may hold several of them, the locked amounts add up.
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct VestingSchedule<Balance> {
@@ -67,17 +71,38 @@ pub trait Trait: timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
     // kton
-    type OnMinted: OnUnbalanced<PositiveImbalance<Self>>;
+    /// Handler for newly issued KTON.
+    type OnMinted: OnMinted<Self::Balance>;
     type OnRemoval: OnUnbalanced<NegativeImbalance<Self>>;
+
+    /// Handler for changes of the total KTON balance of an account.
+    type OnAccountBalanceChanged: OnAccountBalanceChanged<Self::AccountId, Self::Balance>;
+
+    /// The maximum number of vesting schedules an account may hold at once.
+    type MaxVestingSchedules: Get<u32>;
+
+    /// The maximum number of locks an account may take through calls which can fail, see
+    /// `ensure_can_lock`.
+    type MaxLocks: Get<u32>;
+
+    /// The maximum number of accounts whose expired locks are swept in one block.
+    type MaxLockSweeps: Get<u32>;
 }
 
 decl_event!(
     pub enum Event<T> where
         < T as system::Trait>::AccountId,
+        < T as system::Trait>::BlockNumber,
         < T as Trait>::Balance,
     {
         /// Transfer succeeded (from, to, value, fees).
         TokenTransfer(AccountId, AccountId, Balance),
+        /// The vesting schedules of an account changed (who, amount still locked).
+        VestingUpdated(AccountId, Balance),
+        /// A lock was set or extended (who, id, amount, until).
+        LockSet(AccountId, LockIdentifier, Balance, BlockNumber),
+        /// A lock was removed or has expired (who, id).
+        LockRemoved(AccountId, LockIdentifier),
     }
 );
 
@@ -101,9 +126,18 @@ decl_storage! {
 
 		pub Locks get(locks): map T::AccountId => Vec<BalanceLock<T::Balance, T::BlockNumber>>;
 
+		/// Accounts holding a lock which expires at the given block, to be swept in `on_initialize`.
+		pub LockExpiry get(lock_expiry): map T::BlockNumber => Vec<T::AccountId>;
+
 		pub TotalLock get(total_lock): T::Balance;
 
-		pub Vesting get(vesting) build(|config: &GenesisConfig<T>| {
+		/// The single vesting schedule an account held before it could hold several, moved to
+		/// `VestingSchedules` the next time the schedules of the account change.
+		pub Vesting get(legacy_vesting): map T::AccountId => Option<VestingSchedule<T::Balance>>;
+
+		/// Vesting schedules of an account, at most `MaxVestingSchedules` of them with its
+		/// legacy `Vesting` schedule.
+		pub VestingSchedules get(vesting_schedules) build(|config: &GenesisConfig<T>| {
 			config.vesting.iter().filter_map(|&(ref who, begin, length)| {
 				let begin = <T::Balance as From<T::BlockNumber>>::from(begin);
 				let length = <T::Balance as From<T::BlockNumber>>::from(length);
@@ -117,10 +151,10 @@ decl_storage! {
 						let per_block = balance / length.max(primitives::traits::One::one());
 						let offset = begin * per_block + balance;
 
-						(who.clone(), VestingSchedule { offset, per_block })
+						(who.clone(), vec![VestingSchedule { offset, per_block }])
 					})
 			}).collect::<Vec<_>>()
-		}): map T::AccountId => Option<VestingSchedule<T::Balance>>;
+		}): map T::AccountId => Vec<VestingSchedule<T::Balance>>;
 	}
 	add_extra_genesis {
         config(balances): Vec<(T::AccountId, T::Balance)>;
@@ -130,8 +164,30 @@ decl_storage! {
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        /// The maximum number of vesting schedules an account may hold at once.
+        const MaxVestingSchedules: u32 = T::MaxVestingSchedules::get();
+
+        /// The maximum number of locks an account may take through calls which can fail.
+        const MaxLocks: u32 = T::MaxLocks::get();
+
+        /// The maximum number of accounts whose expired locks are swept in one block.
+        const MaxLockSweeps: u32 = T::MaxLockSweeps::get();
+
         fn deposit_event<T>() = default;
 
+        fn on_initialize(now: T::BlockNumber) {
+            let mut due = <LockExpiry<T>>::take(now);
+            let limit = T::MaxLockSweeps::get() as usize;
+            if due.len() > limit {
+                // carry the rest over to the next block
+                let rest = due.split_off(limit);
+                <LockExpiry<T>>::mutate(now + One::one(), |v| v.extend(rest));
+            }
+            for who in due.iter() {
+                Self::prune_locks(who);
+            }
+        }
+
         pub fn transfer(origin,
             dest: <T::Lookup as StaticLookup>::Source,
 			#[compact] value: T::Balance
@@ -141,30 +197,163 @@ decl_module! {
 
             <Self as Currency<_>>::transfer(&transactor, &dest, value)?;
         }
+
+        /// Transfer the amount still locked by `schedule` at the current block to `dest`
+        /// and add `schedule` to its vesting schedules.
+        pub fn vested_transfer(origin,
+            dest: <T::Lookup as StaticLookup>::Source,
+            schedule: VestingSchedule<T::Balance>
+        ) {
+            let transactor = ensure_signed(origin)?;
+            let dest = T::Lookup::lookup(dest)?;
+
+            ensure!(!schedule.per_block.is_zero(), "vesting schedule must unlock something every block");
+            let mut schedules = Self::vesting(&dest);
+            ensure!(
+                (schedules.len() as u32) < T::MaxVestingSchedules::get(),
+                "too many vesting schedules for destination"
+            );
+
+            let now = <system::Module<T>>::block_number();
+            let value = schedule.locked_at::<T::BlockNumber>(now);
+            ensure!(!value.is_zero(), "vesting schedule is already fully unlocked");
+
+            <Self as Currency<_>>::transfer(&transactor, &dest, value)?;
+
+            schedules.push(schedule);
+            Self::set_vesting(&dest, schedules);
+            Self::deposit_event(RawEvent::VestingUpdated(dest.clone(), Self::vesting_balance(&dest)));
+        }
+
+        /// Drop the vesting schedules of the sender which have been fully unlocked.
+        pub fn vest(origin) {
+            let who = ensure_signed(origin)?;
+            let schedules = Self::vesting(&who);
+            ensure!(!schedules.is_empty(), "no vesting schedule");
+
+            let now = <system::Module<T>>::block_number();
+            Self::set_vesting(&who, schedules.into_iter()
+                .filter(|s| !s.locked_at::<T::BlockNumber>(now).is_zero())
+                .collect());
+
+            Self::deposit_event(RawEvent::VestingUpdated(who.clone(), Self::vesting_balance(&who)));
+        }
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// The vesting schedules of `who`, its legacy one included.
+    pub fn vesting(who: &T::AccountId) -> Vec<VestingSchedule<T::Balance>> {
+        Self::legacy_vesting(who).into_iter().chain(Self::vesting_schedules(who)).collect()
+    }
+
+    /// Replace the vesting schedules of `who`, migrating its legacy one.
+    fn set_vesting(who: &T::AccountId, schedules: Vec<VestingSchedule<T::Balance>>) {
+        <Vesting<T>>::remove(who);
+        if schedules.is_empty() {
+            <VestingSchedules<T>>::remove(who);
+        } else {
+            <VestingSchedules<T>>::insert(who, schedules);
+        }
+    }
+
     pub fn vesting_balance(who: &T::AccountId) -> T::Balance {
-        if let Some(v) = Self::vesting(who) {
-            Self::free_balance(who)
-                .min(v.locked_at::<T::BlockNumber>(<system::Module<T>>::block_number()))
+        let now = <system::Module<T>>::block_number();
+        let locked = Self::vesting(who).iter()
+            .fold(Zero::zero(), |acc: T::Balance, s| acc.saturating_add(s.locked_at::<T::BlockNumber>(now)));
+        Self::free_balance(who).min(locked)
+    }
+
+    /// Free, reserved, locked and transferable KTON of `who`.
+    pub fn breakdown(who: &T::AccountId) -> CurrencyBreakdown<T::Balance, T::BlockNumber> {
+        CurrencyBreakdown::new(
+            Self::free_balance(who),
+            Self::reserved_balance(who),
+            Self::locks(who).into_iter().map(|l| (l.id, l.amount, l.until, l.reasons)).collect(),
+            Self::vesting_balance(who),
+            <system::Module<T>>::block_number(),
+        )
+    }
+
+
+    /// The locks of `who` still in force, expired ones are left out.
+    pub fn locks_in_force(who: &T::AccountId) -> Vec<BalanceLock<T::Balance, T::BlockNumber>> {
+        let now = <system::Module<T>>::block_number();
+        Self::locks(who).into_iter().filter(|l| l.until > now).collect()
+    }
+
+    /// Drop the expired locks of `who` and return the ones still in force.
+    pub fn prune_locks(who: &T::AccountId) -> Vec<BalanceLock<T::Balance, T::BlockNumber>> {
+        let now = <system::Module<T>>::block_number();
+        let (locks, expired): (Vec<_>, Vec<_>) = Self::locks(who).into_iter().partition(|l| l.until > now);
+        if !expired.is_empty() {
+            for l in expired {
+                Self::deposit_event(RawEvent::LockRemoved(who.clone(), l.id));
+            }
+            Self::update_locks(who, &locks);
+        }
+        locks
+    }
+
+    fn update_locks(who: &T::AccountId, locks: &[BalanceLock<T::Balance, T::BlockNumber>]) {
+        if locks.is_empty() {
+            <Locks<T>>::remove(who);
         } else {
-            Zero::zero()
+            <Locks<T>>::insert(who, locks.to_vec());
         }
     }
 
+    /// Whether `who` can take the lock `id`, holding fewer than `MaxLocks` other locks in force.
+    /// `LockableCurrency` can not fail and never refuses a lock: the modules setting locks from
+    /// calls which can fail check it first.
+    pub fn ensure_can_lock(who: &T::AccountId, id: LockIdentifier) -> Result {
+        let others = Self::locks_in_force(who).iter().filter(|l| l.id != id).count();
+        ensure!((others as u32) < T::MaxLocks::get(), "too many locks");
+        Ok(())
+    }
+
+    /// Store `locks`, in which `lock` was set.
+    fn set_lock_inner(
+        who: &T::AccountId,
+        lock: BalanceLock<T::Balance, T::BlockNumber>,
+        mut locks: Vec<BalanceLock<T::Balance, T::BlockNumber>>,
+        is_new: bool,
+    ) {
+        if lock.until <= <system::Module<T>>::block_number() {
+            // expired already, it never restricts anything
+            locks.retain(|l| l.id != lock.id);
+            Self::update_locks(who, &locks);
+            if !is_new {
+                Self::deposit_event(RawEvent::LockRemoved(who.clone(), lock.id));
+            }
+            return;
+        }
+
+        if lock.until < T::BlockNumber::max_value() {
+            <LockExpiry<T>>::mutate(lock.until, |v| if !v.contains(who) { v.push(who.clone()) });
+        }
+        Self::update_locks(who, &locks);
+        Self::deposit_event(RawEvent::LockSet(who.clone(), lock.id, lock.amount, lock.until));
+    }
 
     // PRIVATE MUTABLE
     // NOTE: different from balances module
     fn set_free_balance(who: &T::AccountId, balance: T::Balance) -> UpdateBalanceOutcome {
         //TODO: check the value of balance, but no ensure!(...)
+        let old = Self::free_balance(who);
         <FreeBalance<T>>::insert(who, balance);
+
+        let reserved = Self::reserved_balance(who);
+        T::OnAccountBalanceChanged::on_changed(who, old.saturating_add(reserved), balance.saturating_add(reserved));
         UpdateBalanceOutcome::Updated
     }
 
     fn set_reserved_balance(who: &T::AccountId, balance: T::Balance) -> UpdateBalanceOutcome {
+        let old = Self::reserved_balance(who);
         <ReservedBalance<T>>::insert(who, balance);
+
+        let free = Self::free_balance(who);
+        T::OnAccountBalanceChanged::on_changed(who, free.saturating_add(old), free.saturating_add(balance));
         UpdateBalanceOutcome::Updated
     }
 }
@@ -206,16 +395,14 @@ impl<T: Trait> Currency<T::AccountId> for Module<T> {
                 return Err("vesting balance too high to send value"),
             _ => {}
         }
-        let locks = Self::locks(who);
+        let locks = Self::locks_in_force(who);
         if locks.is_empty() {
             return Ok(());
         }
 
-        let now = <system::Module<T>>::block_number();
         if locks.into_iter()
             .all(|l|
-                now >= l.until
-                    || new_balance >= l.amount
+                new_balance >= l.amount
                     || !l.reasons.contains(reason)
             )
         {
@@ -370,6 +557,7 @@ impl<T: Trait> Currency<T::AccountId> for Module<T> {
                 Self::Balance::max_value()
             })
         );
+        T::OnMinted::on_minted(amount);
         NegativeImbalance::new(amount)
     }
 }
@@ -388,20 +576,19 @@ impl<T: Trait> LockableCurrency<T::AccountId> for Module<T>
         until: T::BlockNumber,
         reasons: WithdrawReasons,
     ) {
-        let now = <system::Module<T>>::block_number();
-        let mut new_lock = Some(BalanceLock { id, amount, until, reasons });
-        let mut locks = Self::locks(who).into_iter().filter_map(|l|
+        let lock = BalanceLock { id, amount, until, reasons };
+        let mut new_lock = Some(lock.clone());
+        let mut locks = Self::prune_locks(who).into_iter().filter_map(|l|
             if l.id == id {
                 new_lock.take()
-            } else if l.until > now {
-                Some(l)
             } else {
-                None
+                Some(l)
             }).collect::<Vec<_>>();
+        let is_new = new_lock.is_some();
         if let Some(lock) = new_lock {
             locks.push(lock)
         }
-        <Locks<T>>::insert(who, locks);
+        Self::set_lock_inner(who, lock, locks, is_new);
     }
 
     fn extend_lock(
@@ -411,9 +598,8 @@ impl<T: Trait> LockableCurrency<T::AccountId> for Module<T>
         until: T::BlockNumber,
         reasons: WithdrawReasons,
     ) {
-        let now = <system::Module<T>>::block_number();
         let mut new_lock = Some(BalanceLock { id, amount, until, reasons });
-        let mut locks = Self::locks(who).into_iter().filter_map(|l|
+        let mut locks = Self::prune_locks(who).into_iter().map(|l|
             if l.id == id {
                 new_lock.take().map(|nl| {
                     BalanceLock {
@@ -422,30 +608,29 @@ impl<T: Trait> LockableCurrency<T::AccountId> for Module<T>
                         until: l.until.max(nl.until),
                         reasons: l.reasons | nl.reasons,
                     }
-                })
-            } else if l.until > now {
-                Some(l)
+                }).unwrap_or(l)
             } else {
-                None
+                l
             }).collect::<Vec<_>>();
+        let is_new = new_lock.is_some();
         if let Some(lock) = new_lock {
             locks.push(lock)
         }
-        <Locks<T>>::insert(who, locks);
+        let lock = locks.iter().find(|l| l.id == id).cloned().expect("lock with `id` was just inserted; qed");
+        Self::set_lock_inner(who, lock, locks, is_new);
     }
 
     fn remove_lock(
         id: LockIdentifier,
         who: &T::AccountId,
     ) {
-        let now = <system::Module<T>>::block_number();
-        let locks = Self::locks(who).into_iter().filter_map(|l|
-            if l.until > now && l.id != id {
-                Some(l)
-            } else {
-                None
-            }).collect::<Vec<_>>();
-        <Locks<T>>::insert(who, locks);
+        let mut locks = Self::prune_locks(who);
+        let len = locks.len();
+        locks.retain(|l| l.id != id);
+        if locks.len() != len {
+            Self::update_locks(who, &locks);
+            Self::deposit_event(RawEvent::LockRemoved(who.clone(), id));
+        }
     }
 }
 
@@ -33,6 +33,29 @@ impl Convert<u128, u64> for CurrencyToVoteHandler {
 thread_local! {
 	static SESSION: RefCell<(Vec<AccountId>, HashSet<AccountId>)> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
+	static BALANCE_CHANGES: RefCell<Vec<(AccountId, Balance, Balance)>> = RefCell::new(vec![]);
+	static MINTED: RefCell<Balance> = RefCell::new(0);
+}
+
+/// Records every call of the balance hooks, see `balance_changes` and `minted`.
+pub struct HookRecorder;
+impl dsupport::traits::OnAccountBalanceChanged<AccountId, Balance> for HookRecorder {
+    fn on_changed(who: &AccountId, old: Balance, new: Balance) {
+        BALANCE_CHANGES.with(|v| v.borrow_mut().push((*who, old, new)));
+    }
+}
+impl dsupport::traits::OnMinted<Balance> for HookRecorder {
+    fn on_minted(value: Balance) {
+        MINTED.with(|v| *v.borrow_mut() += value);
+    }
+}
+
+pub fn balance_changes() -> Vec<(AccountId, Balance, Balance)> {
+    BALANCE_CHANGES.with(|v| v.borrow_mut().drain(..).collect())
+}
+
+pub fn minted() -> Balance {
+    MINTED.with(|v| *v.borrow())
 }
 
 pub struct ExistentialDeposit;
@@ -69,11 +92,21 @@ impl timestamp::Trait for Test {
 }
 
 
+parameter_types! {
+	pub const MaxLocks: u32 = 4;
+	pub const MaxLockSweeps: u32 = 1;
+	pub const MaxVestingSchedules: u32 = 2;
+}
+
 impl Trait for Test {
     type Balance = Balance;
     type Event = ();
-    type OnMinted = ();
+    type OnMinted = (HookRecorder, ());
     type OnRemoval = ();
+    type OnAccountBalanceChanged = (HookRecorder, ());
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type MaxLocks = MaxLocks;
+    type MaxLockSweeps = MaxLockSweeps;
 }
 
 pub struct ExtBuilder {
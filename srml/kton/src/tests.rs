@@ -2,7 +2,8 @@
 use runtime_io::with_externalities;
 use srml_support::{assert_err, assert_noop, assert_ok};
 use srml_support::traits::{Currency, ExistenceRequirement, Imbalance, WithdrawReason, WithdrawReasons, LockIdentifier};
-use mock::{ExtBuilder, Kton, Origin, System, Test, Timestamp};
+use primitives::traits::OnInitialize;
+use mock::{balance_changes, minted, ExtBuilder, Kton, Origin, System, Test, Timestamp};
 use super::*;
 
 #[test]
@@ -29,3 +30,224 @@ fn lock_should_work() {
     });
 }
 
+
+#[test]
+fn vested_transfer_should_work() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        let schedule = VestingSchedule { offset: 50, per_block: 10 };
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, schedule));
+        assert_eq!(Kton::free_balance(&1000), 50);
+        assert_eq!(Kton::free_balance(&1001), 50);
+        assert_eq!(Kton::vesting(&1000), vec![schedule]);
+        assert_eq!(Kton::vesting_balance(&1000), 50);
+        assert_err!(Kton::transfer(Origin::signed(1000), 1001, 10), "vesting balance too high to send value");
+
+        System::set_block_number(2);
+        assert_eq!(Kton::vesting_balance(&1000), 30);
+        assert_ok!(Kton::transfer(Origin::signed(1000), 1001, 20));
+        assert_err!(Kton::transfer(Origin::signed(1000), 1001, 1), "vesting balance too high to send value");
+    });
+}
+
+#[test]
+fn vested_transfer_should_respect_max_schedules() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        let schedule = VestingSchedule { offset: 20, per_block: 1 };
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, schedule));
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, schedule));
+        assert_eq!(Kton::vesting_balance(&1000), 40);
+        assert_noop!(
+            Kton::vested_transfer(Origin::signed(1001), 1000, schedule),
+            "too many vesting schedules for destination"
+        );
+        assert_noop!(
+            Kton::vested_transfer(Origin::signed(1001), 1002, VestingSchedule { offset: 20, per_block: 0 }),
+            "vesting schedule must unlock something every block"
+        );
+    });
+}
+
+#[test]
+fn vest_should_drop_finished_schedules() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, VestingSchedule { offset: 10, per_block: 5 }));
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, VestingSchedule { offset: 40, per_block: 5 }));
+
+        System::set_block_number(3);
+        assert_ok!(Kton::vest(Origin::signed(1000)));
+        assert_eq!(Kton::vesting(&1000), vec![VestingSchedule { offset: 40, per_block: 5 }]);
+        assert_eq!(Kton::vesting_balance(&1000), 25);
+
+        System::set_block_number(8);
+        assert_ok!(Kton::vest(Origin::signed(1000)));
+        assert!(!<VestingSchedules<Test>>::exists(&1000));
+        assert_noop!(Kton::vest(Origin::signed(1000)), "no vesting schedule");
+    });
+}
+
+#[test]
+fn legacy_vesting_should_be_migrated() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1000, 100);
+        <Vesting<Test>>::insert(&1000, VestingSchedule { offset: 50, per_block: 10 });
+        assert_eq!(Kton::vesting_balance(&1000), 50);
+        assert_err!(Kton::transfer(Origin::signed(1000), 1001, 60), "vesting balance too high to send value");
+
+        Kton::deposit_creating(&1001, 100);
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, VestingSchedule { offset: 20, per_block: 1 }));
+        assert!(!<Vesting<Test>>::exists(&1000));
+        assert_eq!(Kton::vesting(&1000), vec![
+            VestingSchedule { offset: 50, per_block: 10 },
+            VestingSchedule { offset: 20, per_block: 1 },
+        ]);
+        assert_eq!(Kton::vesting_balance(&1000), 70);
+    });
+}
+
+#[test]
+fn breakdown_should_follow_locks_and_vesting() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        assert_ok!(Kton::vested_transfer(Origin::signed(1001), 1000, VestingSchedule { offset: 30, per_block: 10 }));
+        Kton::deposit_creating(&1000, 70);
+        Kton::set_lock(*b"locklock", &1000, 60, 5, WithdrawReasons::all());
+        Kton::set_lock(*b"lockfee ", &1000, 90, 5, WithdrawReason::TransactionPayment.into());
+
+        let breakdown = Kton::breakdown(&1000);
+        assert_eq!(breakdown.free, 100);
+        assert_eq!(breakdown.vesting, 30);
+        assert_eq!(breakdown.locks.len(), 2);
+        assert_eq!(breakdown.transferable, 40);
+
+        System::set_block_number(5);
+        let breakdown = Kton::breakdown(&1000);
+        assert!(breakdown.locks.is_empty());
+        assert_eq!(breakdown.vesting, 0);
+        assert_eq!(breakdown.transferable, 100);
+    });
+}
+
+#[test]
+fn expired_locks_should_be_pruned_on_access() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        Kton::set_lock(*b"locklock", &1001, 90, 5, WithdrawReasons::all());
+        Kton::set_lock(*b"staking ", &1001, 10, u64::max_value(), WithdrawReasons::all());
+        assert_eq!(Kton::locks(&1001).len(), 2);
+        assert_eq!(Kton::lock_expiry(5), vec![1001]);
+
+        // expired locks no longer restrict withdrawals, checking them writes nothing
+        System::set_block_number(5);
+        assert_ok!(Kton::transfer(Origin::signed(1001), 1000, 80));
+        assert_eq!(Kton::locks(&1001).len(), 2);
+
+        Kton::set_lock(*b"vote    ", &1001, 5, 4, WithdrawReasons::all());
+        assert_eq!(Kton::locks(&1001), vec![BalanceLock {
+            id: *b"staking ", amount: 10, until: u64::max_value(), reasons: WithdrawReasons::all()
+        }]);
+
+        Kton::remove_lock(*b"staking ", &1001);
+        assert!(!<Locks<Test>>::exists(&1001));
+    });
+}
+
+#[test]
+fn locks_should_be_capped() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        for id in 0..4u8 {
+            assert_ok!(Kton::ensure_can_lock(&1001, [id; 8]));
+            Kton::set_lock([id; 8], &1001, 10, 3, WithdrawReasons::all());
+        }
+        assert_noop!(Kton::ensure_can_lock(&1001, [4; 8]), "too many locks");
+        // the locks already held can still change
+        assert_ok!(Kton::ensure_can_lock(&1001, [0; 8]));
+
+        // `LockableCurrency` never refuses a lock
+        Kton::set_lock([4; 8], &1001, 90, u64::max_value(), WithdrawReasons::all());
+        assert_eq!(Kton::locks(&1001).len(), 5);
+        assert_err!(Kton::transfer(Origin::signed(1001), 1000, 20), "account liquidity restrictions prevent withdrawal");
+
+        // expired locks do not count
+        System::set_block_number(3);
+        assert_ok!(Kton::ensure_can_lock(&1001, [5; 8]));
+    });
+}
+
+#[test]
+fn expired_locks_should_not_be_set() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        System::set_block_number(5);
+        Kton::set_lock(*b"locklock", &1001, 90, 5, WithdrawReasons::all());
+        assert!(!<Locks<Test>>::exists(&1001));
+
+        Kton::set_lock(*b"locklock", &1001, 90, 7, WithdrawReasons::all());
+        Kton::extend_lock(*b"locklock", &1001, 90, 4, WithdrawReasons::all());
+        assert_eq!(Kton::locks(&1001)[0].until, 7);
+        Kton::set_lock(*b"locklock", &1001, 90, 2, WithdrawReasons::all());
+        assert!(!<Locks<Test>>::exists(&1001));
+    });
+}
+
+#[test]
+fn expired_locks_should_be_swept_on_initialize() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1000, 100);
+        Kton::deposit_creating(&1001, 100);
+        Kton::set_lock(*b"locklock", &1000, 50, 3, WithdrawReasons::all());
+        Kton::extend_lock(*b"locklock", &1001, 50, 3, WithdrawReasons::all());
+        assert_eq!(Kton::lock_expiry(3), vec![1000, 1001]);
+
+        // at most `MaxLockSweeps` accounts per block, the rest waits for the next one
+        System::set_block_number(3);
+        Kton::on_initialize(3);
+        assert!(!<Locks<Test>>::exists(&1000));
+        assert_eq!(Kton::locks(&1001).len(), 1);
+        assert_eq!(Kton::lock_expiry(4), vec![1001]);
+
+        System::set_block_number(4);
+        Kton::on_initialize(4);
+        assert!(!<Locks<Test>>::exists(&1001));
+        assert!(Kton::lock_expiry(4).is_empty());
+    });
+}
+
+#[test]
+fn balance_hooks_should_be_called() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 100);
+        assert_ok!(Kton::transfer(Origin::signed(1001), 1000, 30));
+        assert_eq!(balance_changes(), vec![(1001, 0, 100), (1001, 100, 70), (1000, 0, 30)]);
+
+        let _ = Kton::slash(&1000, 10);
+        assert_eq!(balance_changes(), vec![(1000, 30, 20)]);
+
+        let minted_before = minted();
+        drop(Kton::issue(50));
+        assert_eq!(minted(), minted_before + 50);
+    });
+}
@@ -0,0 +1,347 @@
+//! # Messages Module
+//!
+//! Messages between accounts of Darwinia, contracts included, and contracts of Ethereum and Tron.
+//!
+//! `send_message` records a payload of the sender for a chain as an `OutboundMessage` numbered
+//! by `Nonce`. The messages are the leaves of a Merkle Mountain Range like those of the Ethereum
+//! outbound module, whose root commits to every message so far, and the inbox contract of the
+//! chain delivers a message given its proof against a root it knows. Contracts send messages by dispatching
+//! `send_message` as a runtime call.
+//!
+//! The `outbox` contract of a chain emits
+//! `MessageSent(address indexed sender, bytes32 indexed target, bytes payload)`. Anyone can
+//! `receive` it on Darwinia by submitting the receipt of the sending transaction, verified by
+//! `T::EthRelay`, and every receipt is received once. Messages are only sent to Tron: there is
+//! no relay of Tron blocks to receive them from. Calls have no weight, so the relayer pays
+//! `T::MessageFee` for each message of the receipt on top of the transaction fee.
+//! Each message is handed to `T::OnInbound` as a `Callback` for its target: with
+//! `ContractCallback`, a target which is a contract is called with the encoded `Callback` as
+//! input from the keyless `callback_account`, at the expense of the relayer, as NFTs arriving
+//! through the NFT bridge module are.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use eth_bridge::Chain;
+use eth_outbound::mmr;
+use eth_relay::{EthReceiptProof, LogEntry, Receipt, TransactionOutcome, VerifyEthReceipts};
+use hex_literal::hex;
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256, U256};
+use primitives::traits::{SaturatedConversion, Saturating, StaticLookup, Zero};
+use rstd::{marker::PhantomData, prelude::*};
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::{Dispatchable, Result};
+use srml_support::traits::{Currency, ExistenceRequirement, Get, OnUnbalanced, WithdrawReason};
+use system::ensure_signed;
+use dsupport::keyless;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The name of the keyless account calling back contracts.
+pub const CALLBACK_ACCOUNT_ID: [u8; 8] = *b"evo/msgs";
+
+/// `keccak256("MessageSent(address,bytes32,bytes)")`, the first topic of a `MessageSent` event.
+pub const MESSAGE_SENT_TOPIC: [u8; 32] = hex!("dbcbeeecf13f4bdec7f1b3bc8d32f3a2b961be3c106559957165f318ededdf1a");
+
+/// A payload sent from Darwinia to another chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OutboundMessage<AccountId> {
+    pub nonce: u64,
+    pub sender: AccountId,
+    pub chain: Chain,
+    pub payload: Vec<u8>,
+}
+
+impl<AccountId: Encode> OutboundMessage<AccountId> {
+    /// The leaf of the message, `keccak256(abi.encodePacked(uint64 nonce, uint8 chain,
+    /// bytes32 sender, bytes payload))` with Ethereum as chain 0 and Tron as 1.
+    pub fn leaf(&self) -> H256 {
+        let chain = match self.chain {
+            Chain::Ethereum => 0,
+            Chain::Tron => 1,
+        };
+
+        let mut data = Vec::with_capacity(41 + self.payload.len());
+        data.extend_from_slice(&self.nonce.to_be_bytes());
+        data.push(chain);
+        self.sender.encode_to(&mut data);
+        data.extend_from_slice(&self.payload);
+        H256(runtime_io::keccak_256(&data))
+    }
+}
+
+/// A message and its proof against the root of the range of `mmr_size` nodes.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OutboundMessageProof<AccountId> {
+    pub message: OutboundMessage<AccountId>,
+    pub mmr_size: u64,
+    /// The siblings of the nodes from the leaf of the message up to its peak.
+    pub siblings: Vec<H256>,
+    /// Every peak of the range, from the left.
+    pub peaks: Vec<H256>,
+}
+
+/// A verified inbound message, as its target on Darwinia is told of it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Callback {
+    /// The payload a contract of the chain sent.
+    Message(Chain, H160, Vec<u8>),
+    /// The NFT arrived from the chain.
+    NftArrived(Chain, H256),
+}
+
+/// Something that reacts to verified inbound messages.
+pub trait OnInboundMessage<AccountId> {
+    /// Check that `payer` can pay for telling each of `targets`, before anything is received.
+    fn ensure_can_pay(_payer: &AccountId, _targets: &[AccountId]) -> Result {
+        Ok(())
+    }
+
+    /// `callback` arrived for `target`, told at the expense of `payer`.
+    fn on_inbound_message(payer: &AccountId, target: &AccountId, callback: Callback);
+}
+
+impl<AccountId> OnInboundMessage<AccountId> for () {
+    fn on_inbound_message(_: &AccountId, _: &AccountId, _: Callback) {}
+}
+
+/// Calls the targets which are contracts with the encoded `Callback` as input and `G` gas, from
+/// the keyless `callback_account`. The payer buys the gas. Other targets are left alone.
+pub struct ContractCallback<T, G>(PhantomData<(T, G)>);
+
+impl<T: Trait + contracts::Trait, G: Get<contracts::Gas>> ContractCallback<T, G> {
+    fn cost() -> contracts::BalanceOf<T> {
+        <contracts::Module<T>>::gas_price().saturating_mul(G::get().saturated_into())
+    }
+}
+
+impl<T: Trait + contracts::Trait, G: Get<contracts::Gas>> OnInboundMessage<T::AccountId> for ContractCallback<T, G> {
+    fn ensure_can_pay(payer: &T::AccountId, targets: &[T::AccountId]) -> Result {
+        let callbacks = targets.iter().filter(|target| <contracts::ContractInfoOf<T>>::exists(*target)).count();
+        if callbacks == 0 {
+            return Ok(());
+        }
+
+        // the existential deposit is lent along with the gas of each callback, and the payer is
+        // kept alive
+        let cost = Self::cost().saturating_mul((callbacks as u64).saturated_into());
+        let minimum = <T as contracts::Trait>::Currency::minimum_balance();
+        let lent = cost.saturating_add(minimum);
+        let free = <T as contracts::Trait>::Currency::free_balance(payer);
+        ensure!(free >= lent.saturating_add(minimum), "cannot pay for the callbacks");
+        <T as contracts::Trait>::Currency::ensure_can_withdraw(payer, lent, WithdrawReason::Transfer, free - lent)
+    }
+
+    fn on_inbound_message(payer: &T::AccountId, target: &T::AccountId, callback: Callback) {
+        if !<contracts::ContractInfoOf<T>>::exists(target) {
+            return;
+        }
+
+        let account = <Module<T>>::callback_account();
+        let call = contracts::Call::<T>::call(T::Lookup::unlookup(target.clone()), Zero::zero(), G::get(), callback.encode());
+        // the message is received even if the contract fails, it is not delivered again
+        let _ = keyless::spend_through::<_, <T as contracts::Trait>::Currency>(&account, payer, Self::cost(), || {
+            let _ = call.dispatch(system::RawOrigin::Signed(account.clone()).into());
+        });
+    }
+}
+
+type RingBalanceOf<T> = <<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::Balance;
+type RingNegativeImbalanceOf<T> =
+    <<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Withdraw `fee` of `payer` for each of `targets` and hand it to `P`, if `payer` can then pay
+/// `O` for telling them. Calls have no weight: modules receiving receipts charge their relayer
+/// this way for every message of a receipt.
+pub fn charge_receipt<AccountId, C, O, P>(payer: &AccountId, fee: C::Balance, targets: &[AccountId]) -> Result
+where
+    C: Currency<AccountId>,
+    O: OnInboundMessage<AccountId>,
+    P: OnUnbalanced<C::NegativeImbalance>,
+{
+    let total = fee.saturating_mul((targets.len() as u64).saturated_into());
+    let imbalance = C::withdraw(payer, total, WithdrawReason::Fee, ExistenceRequirement::KeepAlive)?;
+    if let Err(e) = O::ensure_can_pay(payer, targets) {
+        C::resolve_creating(payer, imbalance);
+        return Err(e);
+    }
+
+    P::on_unbalanced(imbalance);
+    Ok(())
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The verifier of the receipts of sending transactions on Ethereum.
+    type EthRelay: VerifyEthReceipts;
+
+    /// What the targets of inbound messages are told.
+    type OnInbound: OnInboundMessage<Self::AccountId>;
+
+    /// The longest payload of a message.
+    type MaxPayloadLength: Get<u32>;
+
+    /// The currency of the fees of receipts.
+    type Ring: Currency<Self::AccountId>;
+
+    /// The fee of each message received, paid by the relayer of the receipt.
+    type MessageFee: Get<RingBalanceOf<Self>>;
+
+    /// What happens to the fees of receipts.
+    type FeePayment: OnUnbalanced<RingNegativeImbalanceOf<Self>>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Messages {
+        /// The contract emitting `MessageSent` events on a chain.
+        pub Outboxes get(outbox): map Chain => H160;
+
+        /// Receipts already received, by block hash or id and transaction index.
+        pub Processed get(processed): map (H256, u64) => bool;
+
+        /// The nonce of the next message, which is also the number of messages.
+        pub Nonce get(nonce): u64;
+
+        /// Outbound messages, by nonce.
+        pub Outbound get(outbound): map u64 => Option<OutboundMessage<T::AccountId>>;
+
+        /// The nodes of the range, by position.
+        pub MmrNodes get(mmr_node): map u64 => H256;
+
+        /// The number of nodes of the range.
+        pub MmrSize get(mmr_size): u64;
+
+        /// The root of the range at each of its sizes, committing to every message before.
+        pub MmrRoots get(mmr_root): map u64 => Option<H256>;
+    }
+}
+
+/// The range of the messages.
+type Range = mmr::Mmr<MmrNodes, MmrSize, MmrRoots>;
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId
+    {
+        /// A message was recorded: its nonce, its sender, the chain and the new root.
+        Sent(u64, AccountId, Chain, H256),
+        /// A message sent by a contract of the chain in the transaction at the index of the block
+        /// was received for an account.
+        Received(Chain, H160, AccountId, H256, u64),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const MaxPayloadLength: u32 = T::MaxPayloadLength::get();
+        const MessageFee: RingBalanceOf<T> = T::MessageFee::get();
+
+        fn deposit_event<T>() = default;
+
+        /// Send `payload` to `target_chain`.
+        fn send_message(origin, target_chain: Chain, payload: Vec<u8>) {
+            let who = ensure_signed(origin)?;
+            ensure!(payload.len() <= T::MaxPayloadLength::get() as usize, "payload too long");
+
+            let nonce = Self::nonce();
+            let message = OutboundMessage {
+                nonce,
+                sender: who.clone(),
+                chain: target_chain,
+                payload,
+            };
+            let root = Range::append(message.leaf());
+            Nonce::put(nonce + 1);
+            <Outbound<T>>::insert(nonce, message);
+
+            Self::deposit_event(RawEvent::Sent(nonce, who, target_chain, root));
+        }
+
+        /// Receive the `MessageSent` events in a receipt of Ethereum, paying `MessageFee` for each
+        /// of them.
+        fn receive(origin, proof: EthReceiptProof) {
+            let who = ensure_signed(origin)?;
+            ensure!(!Self::processed((proof.block_hash, proof.index)), "receipt already received");
+
+            let receipt = T::EthRelay::verify_receipt(&proof)?;
+            Self::receive_messages(&who, Chain::Ethereum, &receipt, proof.block_hash, proof.index)?;
+        }
+
+        /// Set the contract emitting `MessageSent` events on `chain`.
+        fn set_outbox(chain: Chain, outbox: H160) {
+            Outboxes::insert(chain, outbox);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The outbound message of `nonce` and its proof against the root of the range of `mmr_size`
+    /// nodes, if the message was in it.
+    pub fn message_proof(nonce: u64, mmr_size: u64) -> Option<OutboundMessageProof<T::AccountId>> {
+        let message = Self::outbound(nonce)?;
+        let (siblings, peaks) = Range::proof(nonce, mmr_size)?;
+
+        Some(OutboundMessageProof { message, mmr_size, siblings, peaks })
+    }
+
+    /// The keyless account contracts are called back from.
+    pub fn callback_account() -> T::AccountId {
+        keyless::account(&CALLBACK_ACCOUNT_ID)
+    }
+
+    /// Hand the messages sent in `receipt` of `chain`, at `index` of `block`, to their targets,
+    /// unless it was already received. The receipt is verified by `relayer`, relaying the chain,
+    /// who pays for the messages.
+    fn receive_messages(relayer: &T::AccountId, chain: Chain, receipt: &Receipt, block: H256, index: u64) -> Result {
+        ensure!(!Self::processed((block, index)), "receipt already received");
+        ensure!(receipt.outcome == TransactionOutcome::Status(1), "transaction failed");
+
+        let outbox = Self::outbox(chain);
+        let mut messages = Vec::new();
+        for log in receipt.logs.iter().filter(|log| is_message_event(log, &outbox)) {
+            messages.push(Self::parse_message_event(log)?);
+        }
+        ensure!(!messages.is_empty(), "no MessageSent event in the receipt");
+
+        // every message is parsed and paid for before anything is written, and handing them over
+        // cannot fail
+        let targets: Vec<_> = messages.iter().map(|(_, target, _)| target.clone()).collect();
+        charge_receipt::<_, T::Ring, T::OnInbound, T::FeePayment>(relayer, T::MessageFee::get(), &targets)?;
+        Processed::insert((block, index), true);
+
+        for (sender, target, payload) in messages {
+            T::OnInbound::on_inbound_message(relayer, &target, Callback::Message(chain, sender, payload));
+            Self::deposit_event(RawEvent::Received(chain, sender, target, block, index));
+        }
+
+        Ok(())
+    }
+
+    fn parse_message_event(log: &LogEntry) -> rstd::result::Result<(H160, T::AccountId, Vec<u8>), &'static str> {
+        // the payload is ABI encoded after its offset and its length
+        ensure!(log.topics.len() == 3 && log.data.len() >= 64, "invalid MessageSent event");
+        ensure!(U256::from_big_endian(&log.data[..32]) == U256::from(32), "invalid MessageSent event");
+        let length = U256::from_big_endian(&log.data[32..64]);
+        ensure!(length <= U256::from(T::MaxPayloadLength::get()), "payload too long");
+        let end = 64 + length.low_u64() as usize;
+        ensure!(log.data.len() >= end, "invalid MessageSent event");
+
+        let sender = H160::from_slice(&log.topics[1].as_bytes()[12..]);
+        let target = T::AccountId::decode(&mut log.topics[2].as_bytes()).ok_or("invalid target")?;
+
+        Ok((sender, target, log.data[64..end].to_vec()))
+    }
+}
+
+fn is_message_event(log: &LogEntry, outbox: &H160) -> bool {
+    log.address == *outbox && log.topics.first() == Some(&H256(MESSAGE_SENT_TOPIC))
+}
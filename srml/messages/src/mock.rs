@@ -0,0 +1,332 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use hex_literal::hex;
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use substrate_primitives::storage::well_known_keys;
+use runtime_io;
+use srml_support::{impl_outer_dispatch, impl_outer_origin, parameter_types};
+use srml_support::dispatch::Result as DispatchResult;
+use contracts::{ContractAddressFor, Gas, TrieId, TrieIdGenerator};
+use eth_bridge::Chain;
+use eth_relay::{EthReceiptProof, LogEntry, Receipt, TransactionOutcome, VerifyEthReceipts};
+use crate::{Callback, Module, OnInboundMessage, Trait, MESSAGE_SENT_TOPIC};
+
+pub type AccountId = u64;
+
+/// The relayer submitting receipts.
+pub const RELAYER: AccountId = 1;
+/// The account deploying contracts.
+pub const DEPLOYER: AccountId = 2;
+/// An account with the gas of a callback, but not the existential deposits lent and kept.
+pub const SHORT: AccountId = 3;
+/// An account without funds.
+pub const POOR: AccountId = 4;
+/// A target the relayer cannot pay for telling.
+pub const UNPAYABLE: AccountId = 13;
+/// The address of every contract.
+pub const CONTRACT: AccountId = 42;
+
+pub const ETH_OUTBOX: [u8; 20] = hex!("4e2f1a7c9b3d5e6f708192a3b4c5d6e7f8091a2b");
+pub const TRON_OUTBOX: [u8; 20] = hex!("6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b");
+/// A game contract on Ethereum.
+pub const GAME: [u8; 20] = hex!("d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0");
+
+thread_local! {
+    static RECEIPTS: RefCell<BTreeMap<(u8, [u8; 32], u64), Receipt>> = RefCell::new(BTreeMap::new());
+    static CALLBACKS: RefCell<Vec<(AccountId, Callback)>> = RefCell::new(Vec::new());
+}
+
+fn verify(chain: Chain, block: [u8; 32], index: u64) -> Result<Receipt, &'static str> {
+    RECEIPTS.with(|r| r.borrow().get(&(chain as u8, block, index)).cloned()).ok_or("header not relayed")
+}
+
+/// An Ethereum relay verifying the receipts of `add_receipt`, whatever their proof.
+pub struct TestEthRelay;
+impl VerifyEthReceipts for TestEthRelay {
+    fn verify_receipt(proof: &EthReceiptProof) -> Result<Receipt, &'static str> {
+        verify(Chain::Ethereum, proof.block_hash.0, proof.index)
+    }
+}
+
+/// Targets recording their callbacks, returned by `callbacks`, except `UNPAYABLE`.
+pub struct TestCallbacks;
+impl OnInboundMessage<AccountId> for TestCallbacks {
+    fn ensure_can_pay(_: &AccountId, targets: &[AccountId]) -> DispatchResult {
+        if targets.contains(&UNPAYABLE) {
+            return Err("cannot pay for the callbacks");
+        }
+        Ok(())
+    }
+
+    fn on_inbound_message(_: &AccountId, target: &AccountId, callback: Callback) {
+        CALLBACKS.with(|c| c.borrow_mut().push((*target, callback)));
+    }
+}
+
+/// The callbacks so far, with their target.
+pub fn callbacks() -> Vec<(AccountId, Callback)> {
+    CALLBACKS.with(|c| c.borrow().clone())
+}
+
+/// Make the relay of `chain` verify a successful receipt with `logs` at `index` of `block`.
+pub fn add_receipt(chain: Chain, block: [u8; 32], index: u64, logs: Vec<LogEntry>) {
+    let receipt = Receipt {
+        outcome: TransactionOutcome::Status(1),
+        gas_used: Default::default(),
+        log_bloom: vec![0; 256],
+        logs,
+    };
+    RECEIPTS.with(|r| r.borrow_mut().insert((chain as u8, block, index), receipt));
+}
+
+/// A `MessageSent` event of `outbox`, sending `payload` from `sender` to `target`.
+pub fn message_log(outbox: [u8; 20], sender: [u8; 20], target: AccountId, payload: &[u8]) -> LogEntry {
+    let mut sender_topic = [0u8; 32];
+    sender_topic[12..].copy_from_slice(&sender);
+    let mut target_topic = [0u8; 32];
+    target_topic[..8].copy_from_slice(&target.to_le_bytes());
+
+    let mut data = vec![0u8; 64];
+    data[31] = 32;
+    data[56..].copy_from_slice(&(payload.len() as u64).to_be_bytes());
+    data.extend_from_slice(payload);
+    data.resize(64 + (payload.len() + 31) / 32 * 32, 0);
+
+    LogEntry {
+        address: outbox.into(),
+        topics: vec![MESSAGE_SENT_TOPIC.into(), sender_topic.into(), target_topic.into()],
+        data,
+    }
+}
+
+/// A contract paying 1 to account 9 when it is called by `caller` with `input`, and failing
+/// otherwise.
+pub fn callback_contract(caller: &[u8], input: &[u8]) -> Vec<u8> {
+    let escaped = |bytes: &[u8]| bytes.iter().map(|b| format!("\\{:02x}", b)).collect::<String>();
+    let wat = format!(r#"
+(module
+    (import "env" "ext_caller" (func $ext_caller))
+    (import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+    (import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+    (import "env" "ext_scratch_copy" (func $ext_scratch_copy (param i32 i32 i32)))
+    (import "env" "memory" (memory 1 1))
+
+    ;; whether the scratch buffer holds the `len` bytes at `expected`, copied to 1024
+    (func $scratch_is (param $expected i32) (param $len i32) (result i32)
+        (local $at i32)
+        (if (i32.ne (call $ext_scratch_size) (get_local $len))
+            (then (return (i32.const 0)))
+        )
+        (call $ext_scratch_copy (i32.const 1024) (i32.const 0) (get_local $len))
+        (block $done
+            (loop $next
+                (br_if $done (i32.eq (get_local $at) (get_local $len)))
+                (br_if $done
+                    (i32.ne
+                        (i32.load8_u (i32.add (i32.const 1024) (get_local $at)))
+                        (i32.load8_u (i32.add (get_local $expected) (get_local $at)))
+                    )
+                )
+                (set_local $at (i32.add (get_local $at) (i32.const 1)))
+                (br $next)
+            )
+        )
+        (i32.eq (get_local $at) (get_local $len))
+    )
+
+    (func (export "deploy"))
+
+    (func (export "call")
+        (block $fail
+            ;; the input is in the scratch buffer until the caller is put there
+            (br_if $fail (i32.eqz (call $scratch_is (i32.const 512) (i32.const {input_len}))))
+            (call $ext_caller)
+            (br_if $fail (i32.eqz (call $scratch_is (i32.const 256) (i32.const {caller_len}))))
+
+            (drop
+                (call $ext_call
+                    (i32.const 0)   ;; Pointer to the address of account 9.
+                    (i32.const 8)   ;; Length of the address.
+                    (i64.const 0)   ;; All the gas left.
+                    (i32.const 8)   ;; Pointer to the value to transfer.
+                    (i32.const 8)   ;; Length of the value.
+                    (i32.const 0)   ;; Pointer to the input.
+                    (i32.const 0)   ;; Length of the input.
+                )
+            )
+            (return)
+        )
+        unreachable
+    )
+
+    (data (i32.const 0) "\09\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00")
+    (data (i32.const 256) "{caller}")
+    (data (i32.const 512) "{input}")
+)
+"#, caller_len = caller.len(), input_len = input.len(), caller = escaped(caller), input = escaped(input));
+
+    wabt::wat2wasm(wat).unwrap()
+}
+
+/// Gives every contract the address `CONTRACT`.
+pub struct TestContractAddressFor;
+impl ContractAddressFor<H256, AccountId> for TestContractAddressFor {
+    fn contract_address_for(_: &H256, _: &[u8], _: &AccountId) -> AccountId {
+        CONTRACT
+    }
+}
+
+/// Gives the storage of a contract a trie named after its address.
+pub struct TestTrieIdGenerator;
+impl TrieIdGenerator<AccountId> for TestTrieIdGenerator {
+    fn trie_id(account_id: &AccountId) -> TrieId {
+        let mut id = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
+        id.extend_from_slice(b"default:");
+        id.extend_from_slice(&account_id.to_le_bytes());
+        id
+    }
+}
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+impl_outer_dispatch! {
+    pub enum Call for Test where origin: Origin {
+        balances::Balances,
+        contracts::Contracts,
+    }
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 1;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = Contracts;
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+}
+
+impl timestamp::Trait for Test {
+    type Moment = u64;
+    type OnTimestampSet = ();
+}
+
+parameter_types! {
+    pub const SignedClaimHandicap: u64 = 2;
+    pub const TombstoneDeposit: u64 = 16;
+    pub const StorageSizeOffset: u32 = 8;
+    pub const RentByteFee: u64 = 4;
+    pub const RentDepositOffset: u64 = 10_000;
+    pub const SurchargeReward: u64 = 150;
+    pub const ContractFee: u64 = 0;
+    pub const CallBaseFee: Gas = 135;
+    pub const CreateBaseFee: Gas = 175;
+    pub const MaxDepth: u32 = 100;
+    pub const BlockGasLimit: Gas = 10_000_000;
+}
+
+impl contracts::Trait for Test {
+    type Currency = Balances;
+    type Call = Call;
+    type Event = ();
+    type DetermineContractAddress = TestContractAddressFor;
+    type ComputeDispatchFee = contracts::DefaultDispatchFeeComputor<Test>;
+    type TrieIdGenerator = TestTrieIdGenerator;
+    type GasPayment = ();
+    type SignedClaimHandicap = SignedClaimHandicap;
+    type TombstoneDeposit = TombstoneDeposit;
+    type StorageSizeOffset = StorageSizeOffset;
+    type RentByteFee = RentByteFee;
+    type RentDepositOffset = RentDepositOffset;
+    type SurchargeReward = SurchargeReward;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+    type ContractFee = ContractFee;
+    type CallBaseFee = CallBaseFee;
+    type CreateBaseFee = CreateBaseFee;
+    type MaxDepth = MaxDepth;
+    type BlockGasLimit = BlockGasLimit;
+}
+
+parameter_types! {
+    pub const MaxPayloadLength: u32 = 64;
+    pub const MessageFee: u64 = 2;
+    pub const CallbackGas: Gas = 100_000;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type EthRelay = TestEthRelay;
+    type OnInbound = TestCallbacks;
+    type MaxPayloadLength = MaxPayloadLength;
+    type Ring = Balances;
+    type MessageFee = MessageFee;
+    type FeePayment = ();
+}
+
+/// The outboxes of Ethereum and Tron, funds for the relayer and the deployer, a gas price of 1,
+/// no receipts and no callbacks.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    RECEIPTS.with(|r| r.borrow_mut().clear());
+    CALLBACKS.with(|c| c.borrow_mut().clear());
+
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(balances::GenesisConfig::<Test> {
+        balances: vec![(RELAYER, 1_000_000), (DEPLOYER, 1_000_000), (SHORT, 100_001)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+    t.extend(contracts::GenesisConfig::<Test> {
+        current_schedule: Default::default(),
+        gas_price: 1,
+    }.build_storage().unwrap().0);
+
+    let mut t: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+    runtime_io::with_externalities(&mut t, || {
+        assert!(Messages::set_outbox(Chain::Ethereum, ETH_OUTBOX.into()).is_ok());
+        assert!(Messages::set_outbox(Chain::Tron, TRON_OUTBOX.into()).is_ok());
+    });
+    t
+}
+
+pub type Balances = balances::Module<Test>;
+pub type Contracts = contracts::Module<Test>;
+pub type Messages = Module<Test>;
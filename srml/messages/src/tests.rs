@@ -0,0 +1,137 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use hex_literal::hex;
+use primitives::traits::{BlakeTwo256, Hash};
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use mock::*;
+use super::*;
+
+fn eth_proof(index: u64) -> EthReceiptProof {
+    EthReceiptProof { block_hash: H256([1; 32]), index, proof: vec![] }
+}
+
+#[test]
+fn send_message_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(Messages::send_message(Origin::signed(7), Chain::Tron, vec![0; 65]), "payload too long");
+
+        assert_ok!(Messages::send_message(Origin::signed(7), Chain::Tron, b"hello".to_vec()));
+        let message = Messages::outbound(0).unwrap();
+        assert_eq!(message, OutboundMessage { nonce: 0, sender: 7, chain: Chain::Tron, payload: b"hello".to_vec() });
+        assert_eq!(
+            message.leaf(),
+            H256(hex!("c569609d205848c3002829f8648fd477afb40485cf28628777b33923c915c788"))
+        );
+        assert_eq!(Messages::nonce(), 1);
+    });
+}
+
+#[test]
+fn messages_should_be_provable() {
+    with_externalities(&mut new_test_ext(), || {
+        for n in 0..5u8 {
+            assert_ok!(Messages::send_message(Origin::signed(7), Chain::Ethereum, vec![n]));
+        }
+        assert_eq!(Messages::mmr_size(), 8);
+
+        for nonce in 0..5 {
+            let proof = Messages::message_proof(nonce, 8).unwrap();
+            assert!(mmr::verify_proof(
+                &Messages::mmr_root(8).unwrap(),
+                proof.mmr_size,
+                nonce,
+                &proof.message.leaf(),
+                &proof.siblings,
+                &proof.peaks,
+            ));
+        }
+        assert_eq!(Messages::message_proof(5, 8), None);
+        // the root of 4 messages still proves the first ones
+        assert!(Messages::message_proof(3, 7).is_some());
+        assert_eq!(Messages::message_proof(4, 7), None);
+    });
+}
+
+#[test]
+fn receive_should_call_back() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(Messages::receive(Origin::signed(RELAYER), eth_proof(0)), "header not relayed");
+
+        add_receipt(Chain::Ethereum, [1; 32], 0, vec![
+            message_log(ETH_OUTBOX, GAME, 5, b"land arrived"),
+            message_log(ETH_OUTBOX, GAME, 6, &[]),
+        ]);
+        assert_ok!(Messages::receive(Origin::signed(RELAYER), eth_proof(0)));
+        assert_eq!(callbacks(), vec![
+            (5, Callback::Message(Chain::Ethereum, H160(GAME), b"land arrived".to_vec())),
+            (6, Callback::Message(Chain::Ethereum, H160(GAME), vec![])),
+        ]);
+        assert_eq!(Balances::free_balance(&RELAYER), 1_000_000 - 2 * 2);
+        assert_noop!(Messages::receive(Origin::signed(RELAYER), eth_proof(0)), "receipt already received");
+
+        // sent through the outbox of Tron
+        add_receipt(Chain::Ethereum, [1; 32], 1, vec![message_log(TRON_OUTBOX, GAME, 5, b"land")]);
+        assert_noop!(
+            Messages::receive(Origin::signed(RELAYER), eth_proof(1)),
+            "no MessageSent event in the receipt"
+        );
+        add_receipt(Chain::Ethereum, [1; 32], 2, vec![message_log(ETH_OUTBOX, GAME, 5, &[0; 65])]);
+        assert_noop!(Messages::receive(Origin::signed(RELAYER), eth_proof(2)), "payload too long");
+        add_receipt(Chain::Ethereum, [1; 32], 3, vec![message_log(ETH_OUTBOX, GAME, 5, b"land")]);
+        assert_ok!(Messages::receive(Origin::signed(RELAYER), eth_proof(3)));
+        assert_eq!(callbacks().len(), 3);
+        assert_eq!(Balances::free_balance(&RELAYER), 1_000_000 - 3 * 2);
+    });
+}
+
+#[test]
+fn receive_should_be_paid_for() {
+    with_externalities(&mut new_test_ext(), || {
+        add_receipt(Chain::Ethereum, [1; 32], 0, vec![message_log(ETH_OUTBOX, GAME, 5, b"land")]);
+        assert_noop!(Messages::receive(Origin::signed(POOR), eth_proof(0)), "too few free funds in account");
+
+        // the fee is given back when the callbacks cannot be paid for
+        add_receipt(Chain::Ethereum, [1; 32], 1, vec![
+            message_log(ETH_OUTBOX, GAME, 5, b"land"),
+            message_log(ETH_OUTBOX, GAME, UNPAYABLE, b"land"),
+        ]);
+        assert_noop!(Messages::receive(Origin::signed(RELAYER), eth_proof(1)), "cannot pay for the callbacks");
+        assert_eq!(Balances::free_balance(&RELAYER), 1_000_000);
+
+        assert_ok!(Messages::receive(Origin::signed(RELAYER), eth_proof(0)));
+        assert_eq!(Balances::free_balance(&RELAYER), 1_000_000 - 2);
+    });
+}
+
+#[test]
+fn contracts_should_be_called_back_from_the_callback_account() {
+    with_externalities(&mut new_test_ext(), || {
+        let callback = Callback::Message(Chain::Ethereum, H160(GAME), b"land".to_vec());
+        let wasm = callback_contract(&Messages::callback_account().encode(), &callback.encode());
+        assert_ok!(Contracts::put_code(Origin::signed(DEPLOYER), 100_000, wasm.clone()));
+        assert_ok!(Contracts::create(Origin::signed(DEPLOYER), 100_000, 100_000, BlakeTwo256::hash(&wasm), vec![]));
+
+        // anyone else calling the contract with the same input fails
+        assert!(Contracts::call(Origin::signed(RELAYER), CONTRACT, 0, 100_000, callback.encode()).is_err());
+        assert_eq!(Balances::free_balance(&9), 0);
+
+        type Callbacks = ContractCallback<Test, CallbackGas>;
+        assert_noop!(Callbacks::ensure_can_pay(&SHORT, &[5, CONTRACT]), "cannot pay for the callbacks");
+        assert_ok!(Callbacks::ensure_can_pay(&SHORT, &[5, 6]));
+        assert_ok!(Callbacks::ensure_can_pay(&RELAYER, &[5, CONTRACT]));
+
+        let relayer = Balances::free_balance(&RELAYER);
+        let contract = Balances::free_balance(&CONTRACT);
+        Callbacks::on_inbound_message(&RELAYER, &5, callback.clone());
+        Callbacks::on_inbound_message(&RELAYER, &CONTRACT, callback);
+
+        // the relayer bought the gas, and the contract only paid 9
+        assert_eq!(Balances::free_balance(&9), 1);
+        assert_eq!(Balances::free_balance(&CONTRACT), contract - 1);
+        assert!(Balances::free_balance(&RELAYER) < relayer);
+        assert_eq!(Balances::free_balance(&Messages::callback_account()), 0);
+    });
+}
@@ -0,0 +1,353 @@
+//! # NFT Bridge Module
+//!
+//! Tokens of the NFT module moving between Darwinia, Ethereum and Tron.
+//!
+//! Locking a token in the `locker` of a chain emits
+//! `NftLocked(address indexed contract, address indexed from, bytes32 indexed receiver, uint256 tokenId)`.
+//! Anyone can `receive` it on Darwinia by submitting the receipt of the locking transaction,
+//! verified by `T::EthRelay`, and every receipt is received once. Nothing is received from
+//! Tron, as there is no relay of Tron blocks: a token sent there stays there.
+//! The relayer pays `T::ArrivalFee` for each token of the receipt, and for telling receivers.
+//!
+//! A token of a contract root registered is minted to `receiver` in the class of the contract,
+//! and the native token id is mapped to the foreign contract and token id: locked again later,
+//! the same native token comes back. A native token which went to a chain comes back from the
+//! `mirror` contract of that chain, which holds native tokens under their native ids. The
+//! receiver is then told of the token by `T::OnArrival`, with a `Callback::NftArrived`.
+//!
+//! `send` burns a token of the sender to a recipient on a chain, and records a `NftMessage`
+//! numbered by `Nonce`. The messages are the leaves of a Merkle Mountain Range like those of the
+//! Ethereum outbound module, whose root commits to every message so far. A token coming from
+//! the chain is unlocked there from its contract, any other token is minted by the `mirror`,
+//! given the proof of its message against a root the chain knows.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use eth_bridge::Chain;
+use eth_outbound::mmr;
+use eth_relay::{EthReceiptProof, LogEntry, Receipt, TransactionOutcome, VerifyEthReceipts};
+use hex_literal::hex;
+use messages::{Callback, OnInboundMessage};
+use nft::{ClassId, TokenId};
+use parity_codec::{Decode, Encode};
+use primitive_types::{H160, H256};
+use rstd::prelude::*;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::Result;
+use srml_support::traits::{Currency, Get, OnUnbalanced};
+use system::ensure_signed;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// `keccak256("NftLocked(address,address,bytes32,uint256)")`, the first topic of a `NftLocked`
+/// event.
+pub const NFT_LOCKED_TOPIC: [u8; 32] = hex!("6f4279b20dd9baf522dd83836c1b182ee0c68dc5d0a2f67e82cda88f0eba0b93");
+
+/// A token burnt on Darwinia, to be unlocked or minted on another chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct NftMessage<AccountId> {
+    pub nonce: u64,
+    pub sender: AccountId,
+    pub token: TokenId,
+    pub chain: Chain,
+    pub recipient: H160,
+    /// The contract of the token on `chain` and its id there, if it comes from `chain`, or zero.
+    pub contract: H160,
+    pub foreign_token: H256,
+}
+
+impl<AccountId> NftMessage<AccountId> {
+    /// The leaf of the message, `keccak256(abi.encodePacked(uint64 nonce, uint8 chain,
+    /// address recipient, bytes32 token, address contract, bytes32 foreignToken))` with Ethereum
+    /// as chain 0 and Tron as 1.
+    pub fn leaf(&self) -> H256 {
+        let chain = match self.chain {
+            Chain::Ethereum => 0,
+            Chain::Tron => 1,
+        };
+
+        let mut data = Vec::with_capacity(113);
+        data.extend_from_slice(&self.nonce.to_be_bytes());
+        data.push(chain);
+        data.extend_from_slice(self.recipient.as_bytes());
+        data.extend_from_slice(self.token.as_bytes());
+        data.extend_from_slice(self.contract.as_bytes());
+        data.extend_from_slice(self.foreign_token.as_bytes());
+        H256(runtime_io::keccak_256(&data))
+    }
+}
+
+/// A message and its proof against the root of the range of `mmr_size` nodes.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct NftMessageProof<AccountId> {
+    pub message: NftMessage<AccountId>,
+    pub mmr_size: u64,
+    /// The siblings of the nodes from the leaf of the message up to its peak.
+    pub siblings: Vec<H256>,
+    /// Every peak of the range, from the left.
+    pub peaks: Vec<H256>,
+}
+
+/// A token locked on a chain, with the native token it is received as.
+struct Arrival<AccountId> {
+    contract: H160,
+    foreign_token: H256,
+    receiver: AccountId,
+    native: Native,
+}
+
+enum Native {
+    /// A token burnt before, minted again.
+    Remint(TokenId),
+    /// A token not seen before, minted in the class.
+    Mint(ClassId),
+}
+
+pub trait Trait: nft::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The verifier of the receipts of locking transactions on Ethereum.
+    type EthRelay: VerifyEthReceipts;
+
+    /// What the receivers of arriving tokens are told.
+    type OnArrival: OnInboundMessage<Self::AccountId>;
+
+    /// The fee of each token received, paid by the relayer of the receipt.
+    type ArrivalFee: Get<RingBalanceOf<Self>>;
+
+    /// What happens to the fees of receipts.
+    type FeePayment: OnUnbalanced<RingNegativeImbalanceOf<Self>>;
+}
+
+type RingBalanceOf<T> = <<T as nft::Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::Balance;
+type RingNegativeImbalanceOf<T> =
+    <<T as nft::Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+decl_storage! {
+    trait Store for Module<T: Trait> as NftBridge {
+        /// The contract emitting `NftLocked` events on a chain.
+        pub Lockers get(locker): map Chain => H160;
+
+        /// The contract holding native tokens on a chain.
+        pub Mirrors get(mirror): map Chain => H160;
+
+        /// The class of the tokens of a contract of a chain.
+        pub ForeignClasses get(foreign_class): map (Chain, H160) => Option<ClassId>;
+
+        /// The native token of a token of a contract of a chain.
+        pub NativeTokens get(native_token): map (Chain, H160, H256) => Option<TokenId>;
+
+        /// The chain, the contract and the id a native token comes from.
+        pub ForeignTokens get(foreign_token): map TokenId => Option<(Chain, H160, H256)>;
+
+        /// The chain a native token went to, through its mirror.
+        pub Departed get(departed): map TokenId => Option<Chain>;
+
+        /// Receipts already received, by block hash or id and transaction index.
+        pub Processed get(processed): map (H256, u64) => bool;
+
+        /// The nonce of the next message, which is also the number of messages.
+        pub Nonce get(nonce): u64;
+
+        /// Messages, by nonce.
+        pub Messages get(message): map u64 => Option<NftMessage<T::AccountId>>;
+
+        /// The nodes of the range, by position.
+        pub MmrNodes get(mmr_node): map u64 => H256;
+
+        /// The number of nodes of the range.
+        pub MmrSize get(mmr_size): u64;
+
+        /// The root of the range at each of its sizes, committing to every message before.
+        pub MmrRoots get(mmr_root): map u64 => Option<H256>;
+    }
+}
+
+/// The range of the messages.
+type Range = mmr::Mmr<MmrNodes, MmrSize, MmrRoots>;
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId
+    {
+        /// Tokens of a contract of a chain are minted in a class.
+        ContractRegistered(Chain, H160, ClassId),
+        /// A token locked in the transaction at the index of the block of the chain was received
+        /// by an account.
+        Received(Chain, TokenId, AccountId, H256, u64),
+        /// A message was recorded: its nonce, the token burnt and its sender, the chain and the
+        /// recipient there, and the new root.
+        Sent(u64, TokenId, AccountId, Chain, H160, H256),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const ArrivalFee: RingBalanceOf<T> = T::ArrivalFee::get();
+
+        fn deposit_event<T>() = default;
+
+        /// Receive the `NftLocked` events in a receipt of Ethereum, paying `ArrivalFee` for each
+        /// of them.
+        fn receive(origin, proof: EthReceiptProof) {
+            let who = ensure_signed(origin)?;
+            ensure!(!Self::processed((proof.block_hash, proof.index)), "receipt already received");
+
+            let receipt = T::EthRelay::verify_receipt(&proof)?;
+            Self::receive_locks(&who, Chain::Ethereum, &receipt, proof.block_hash, proof.index)?;
+        }
+
+        /// Burn `token` of the sender to `recipient` on `chain`.
+        fn send(origin, token: TokenId, chain: Chain, recipient: H160) {
+            let who = ensure_signed(origin)?;
+            ensure!(<nft::Module<T>>::token(token).map(|t| t.owner) == Some(who.clone()), "not the token owner");
+
+            let (contract, foreign_token) = match Self::foreign_token(token) {
+                Some((from, contract, foreign_token)) if from == chain => (contract, foreign_token),
+                _ => (H160::zero(), H256::zero()),
+            };
+
+            <nft::Module<T>>::depart(token)?;
+            if contract == H160::zero() {
+                Departed::insert(token, chain);
+            }
+
+            let nonce = Self::nonce();
+            let message = NftMessage {
+                nonce,
+                sender: who.clone(),
+                token,
+                chain,
+                recipient,
+                contract,
+                foreign_token,
+            };
+            let root = Range::append(message.leaf());
+            Nonce::put(nonce + 1);
+            <Messages<T>>::insert(nonce, message);
+
+            Self::deposit_event(RawEvent::Sent(nonce, token, who, chain, recipient, root));
+        }
+
+        /// Mint the tokens of `contract` on `chain` in a new class of `chain_id`, `contract_id`
+        /// and `producer_id`. `chain_id` is not the native chain id of the NFT module.
+        fn register_contract(chain: Chain, contract: H160, chain_id: u8, contract_id: u8, producer_id: u16) {
+            ensure!(!ForeignClasses::exists((chain, contract)), "contract already registered");
+            ensure!(chain_id != <T as nft::Trait>::NativeChainId::get(), "native chain id");
+
+            let class = <nft::Module<T>>::create(None, chain_id, contract_id, producer_id, Vec::new())?;
+            ForeignClasses::insert((chain, contract), class);
+
+            Self::deposit_event(RawEvent::ContractRegistered(chain, contract, class));
+        }
+
+        /// Set the locking contract and the mirror contract on `chain`.
+        fn set_contracts(chain: Chain, locker: H160, mirror: H160) {
+            Lockers::insert(chain, locker);
+            Mirrors::insert(chain, mirror);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The message of `nonce` and its proof against the root of the range of `mmr_size` nodes,
+    /// if the message was in it.
+    pub fn message_proof(nonce: u64, mmr_size: u64) -> Option<NftMessageProof<T::AccountId>> {
+        let message = Self::message(nonce)?;
+        let (siblings, peaks) = Range::proof(nonce, mmr_size)?;
+
+        Some(NftMessageProof { message, mmr_size, siblings, peaks })
+    }
+
+    /// Mint the tokens locked in `receipt` of `chain`, at `index` of `block`, unless it was
+    /// already received. The receipt is verified by `relayer`, relaying the chain, who pays for
+    /// the tokens.
+    fn receive_locks(relayer: &T::AccountId, chain: Chain, receipt: &Receipt, block: H256, index: u64) -> Result {
+        ensure!(!Self::processed((block, index)), "receipt already received");
+        ensure!(receipt.outcome == TransactionOutcome::Status(1), "transaction failed");
+
+        let locker = Self::locker(chain);
+        let mut arrivals = Vec::new();
+        for log in receipt.logs.iter().filter(|log| is_locked_event(log, &locker)) {
+            arrivals.push(Self::parse_locked_event(chain, log)?);
+        }
+        ensure!(!arrivals.is_empty(), "no NftLocked event in the receipt");
+
+        // nothing is written until every token is known to be mintable
+        let mut reminted = Vec::new();
+        let mut minted = Vec::new();
+        for arrival in &arrivals {
+            match arrival.native {
+                Native::Remint(token) => {
+                    <nft::Module<T>>::remintable_class(token)?;
+                    ensure!(!reminted.contains(&token), "token locked twice");
+                    reminted.push(token);
+                }
+                Native::Mint(_) => {
+                    let foreign = (arrival.contract, arrival.foreign_token);
+                    ensure!(!minted.contains(&foreign), "token locked twice");
+                    minted.push(foreign);
+                }
+            }
+        }
+
+        let receivers: Vec<_> = arrivals.iter().map(|arrival| arrival.receiver.clone()).collect();
+        messages::charge_receipt::<_, <T as nft::Trait>::Ring, T::OnArrival, T::FeePayment>(
+            relayer,
+            T::ArrivalFee::get(),
+            &receivers,
+        )?;
+        Processed::insert((block, index), true);
+
+        for Arrival { contract, foreign_token, receiver, native } in arrivals {
+            let token = match native {
+                Native::Remint(token) => {
+                    <nft::Module<T>>::remint(token, &receiver, Vec::new())?;
+                    Departed::remove(token);
+                    token
+                }
+                Native::Mint(class) => {
+                    let token = <nft::Module<T>>::mint_to(class, &receiver, Vec::new())?;
+                    NativeTokens::insert((chain, contract, foreign_token), token);
+                    ForeignTokens::insert(token, (chain, contract, foreign_token));
+                    token
+                }
+            };
+
+            T::OnArrival::on_inbound_message(relayer, &receiver, Callback::NftArrived(chain, token));
+            Self::deposit_event(RawEvent::Received(chain, token, receiver, block, index));
+        }
+
+        Ok(())
+    }
+
+    fn parse_locked_event(chain: Chain, log: &LogEntry) -> rstd::result::Result<Arrival<T::AccountId>, &'static str> {
+        ensure!(log.topics.len() == 4 && log.data.len() == 32, "invalid NftLocked event");
+
+        let contract = H160::from_slice(&log.topics[1].as_bytes()[12..]);
+        let receiver = T::AccountId::decode(&mut log.topics[3].as_bytes()).ok_or("invalid receiver")?;
+        let foreign_token = H256::from_slice(&log.data);
+
+        let native = if contract == Self::mirror(chain) {
+            ensure!(Self::departed(foreign_token) == Some(chain), "token not departed to the chain");
+            Native::Remint(foreign_token)
+        } else if let Some(token) = Self::native_token((chain, contract, foreign_token)) {
+            Native::Remint(token)
+        } else {
+            Native::Mint(Self::foreign_class((chain, contract)).ok_or("unregistered contract")?)
+        };
+
+        Ok(Arrival { contract, foreign_token, receiver, native })
+    }
+}
+
+fn is_locked_event(log: &LogEntry, locker: &H160) -> bool {
+    log.address == *locker && log.topics.first() == Some(&H256(NFT_LOCKED_TOPIC))
+}
@@ -0,0 +1,174 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use hex_literal::hex;
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use eth_bridge::Chain;
+use eth_relay::{EthReceiptProof, LogEntry, Receipt, TransactionOutcome, VerifyEthReceipts};
+use messages::{Callback, OnInboundMessage};
+use crate::{Module, Trait, NFT_LOCKED_TOPIC};
+
+pub type AccountId = u64;
+
+pub const ETH_LOCKER: [u8; 20] = hex!("b52fbe2b925ab79a821b261c82c5ba0814aaa5e0");
+pub const ETH_MIRROR: [u8; 20] = hex!("5a3f1b8c7d6e9f0a1b2c3d4e5f60718293a4b5c6");
+/// The Evolution Land contract of lands on Ethereum.
+pub const LAND: [u8; 20] = hex!("c2ac4a5b12da1e2a6fde2fa03d8cfa3e0f4fd0c3");
+
+pub const TRON_LOCKER: [u8; 20] = hex!("3c2a5f1b6e0d9c8b7a6f5e4d3c2b1a0f9e8d7c6b");
+pub const TRON_MIRROR: [u8; 20] = hex!("7e6d5c4b3a291807f6e5d4c3b2a1908f7e6d5c4b");
+
+thread_local! {
+    static RECEIPTS: RefCell<BTreeMap<(u8, [u8; 32], u64), Receipt>> = RefCell::new(BTreeMap::new());
+    static ARRIVALS: RefCell<Vec<(AccountId, Callback)>> = RefCell::new(Vec::new());
+}
+
+fn verify(chain: Chain, block: [u8; 32], index: u64) -> Result<Receipt, &'static str> {
+    RECEIPTS.with(|r| r.borrow().get(&(chain as u8, block, index)).cloned()).ok_or("header not relayed")
+}
+
+/// An Ethereum relay verifying the receipts of `add_receipt`, whatever their proof.
+pub struct TestEthRelay;
+impl VerifyEthReceipts for TestEthRelay {
+    fn verify_receipt(proof: &EthReceiptProof) -> Result<Receipt, &'static str> {
+        verify(Chain::Ethereum, proof.block_hash.0, proof.index)
+    }
+}
+
+/// Receivers recording the arrivals of their tokens, returned by `arrivals`.
+pub struct TestArrivals;
+impl OnInboundMessage<AccountId> for TestArrivals {
+    fn on_inbound_message(_: &AccountId, target: &AccountId, callback: Callback) {
+        ARRIVALS.with(|a| a.borrow_mut().push((*target, callback)));
+    }
+}
+
+/// The arrivals so far, with their receiver.
+pub fn arrivals() -> Vec<(AccountId, Callback)> {
+    ARRIVALS.with(|a| a.borrow().clone())
+}
+
+/// Make the relay of `chain` verify a successful receipt with `logs` at `index` of `block`.
+pub fn add_receipt(chain: Chain, block: [u8; 32], index: u64, logs: Vec<LogEntry>) {
+    let receipt = Receipt {
+        outcome: TransactionOutcome::Status(1),
+        gas_used: Default::default(),
+        log_bloom: vec![0; 256],
+        logs,
+    };
+    RECEIPTS.with(|r| r.borrow_mut().insert((chain as u8, block, index), receipt));
+}
+
+/// A `NftLocked` event of `locker`, locking `token` of `contract` for `receiver`.
+pub fn locked_log(locker: [u8; 20], contract: [u8; 20], receiver: AccountId, token: [u8; 32]) -> LogEntry {
+    let mut contract_topic = [0u8; 32];
+    contract_topic[12..].copy_from_slice(&contract);
+    let mut receiver_topic = [0u8; 32];
+    receiver_topic[..8].copy_from_slice(&receiver.to_le_bytes());
+
+    LogEntry {
+        address: locker.into(),
+        topics: vec![NFT_LOCKED_TOPIC.into(), contract_topic.into(), Default::default(), receiver_topic.into()],
+        data: token.to_vec(),
+    }
+}
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: u64 = 0;
+    pub const TransferFee: u64 = 0;
+    pub const CreationFee: u64 = 0;
+    pub const TransactionBaseFee: u64 = 0;
+    pub const TransactionByteFee: u64 = 0;
+}
+
+impl balances::Trait for Test {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type TransferPayment = ();
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type TransferFee = TransferFee;
+    type CreationFee = CreationFee;
+    type TransactionBaseFee = TransactionBaseFee;
+    type TransactionByteFee = TransactionByteFee;
+}
+
+parameter_types! {
+    pub const MaxMetadataLength: u32 = 16;
+    pub const ClassDeposit: u64 = 10;
+    pub const NativeChainId: u8 = 3;
+}
+
+impl nft::Trait for Test {
+    type Event = ();
+    type MaxMetadataLength = MaxMetadataLength;
+    type Ring = balances::Module<Test>;
+    type ClassDeposit = ClassDeposit;
+    type NativeChainId = NativeChainId;
+}
+
+parameter_types! {
+    pub const ArrivalFee: u64 = 3;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type EthRelay = TestEthRelay;
+    type OnArrival = TestArrivals;
+    type ArrivalFee = ArrivalFee;
+    type FeePayment = ();
+}
+
+/// The lockers and mirrors of Ethereum and Tron, funds for the relayer 1, no contract registered
+/// and no receipts.
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    RECEIPTS.with(|r| r.borrow_mut().clear());
+    ARRIVALS.with(|a| a.borrow_mut().clear());
+
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(balances::GenesisConfig::<Test> {
+        balances: vec![(1, 100)],
+        vesting: vec![],
+    }.build_storage().unwrap().0);
+
+    let mut t: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+    runtime_io::with_externalities(&mut t, || {
+        assert!(NftBridge::set_contracts(Chain::Ethereum, ETH_LOCKER.into(), ETH_MIRROR.into()).is_ok());
+        assert!(NftBridge::set_contracts(Chain::Tron, TRON_LOCKER.into(), TRON_MIRROR.into()).is_ok());
+    });
+    t
+}
+
+pub type Balances = balances::Module<Test>;
+pub type Nft = nft::Module<Test>;
+pub type NftBridge = Module<Test>;
@@ -0,0 +1,166 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use hex_literal::hex;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use mock::*;
+use super::*;
+
+const RECIPIENT: [u8; 20] = hex!("7f5e1ad4ce3fb7d0b43c0e4ab2e8b2a45e27aa6f");
+
+/// The first token of the class of `LAND`.
+const TOKEN_0: [u8; 32] = hex!("2a01010000000000000000000000000000000000000000000000000000000000");
+
+fn foreign_id(id: u8) -> [u8; 32] {
+    let mut token = [0u8; 32];
+    token[31] = id;
+    token
+}
+
+fn eth_proof(index: u64) -> EthReceiptProof {
+    EthReceiptProof { block_hash: H256([1; 32]), index, proof: vec![] }
+}
+
+#[test]
+fn register_contract_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(NftBridge::register_contract(Chain::Ethereum, LAND.into(), 1, 1, 0));
+        assert_eq!(NftBridge::foreign_class((Chain::Ethereum, H160(LAND))), Some(0));
+        assert_eq!(Nft::class(0).map(|c| c.owner), Some(None));
+
+        assert_noop!(
+            NftBridge::register_contract(Chain::Ethereum, LAND.into(), 1, 2, 0),
+            "contract already registered"
+        );
+        assert_noop!(NftBridge::register_contract(Chain::Tron, LAND.into(), 1, 1, 0), "class already exists");
+        assert_noop!(NftBridge::register_contract(Chain::Tron, LAND.into(), 3, 1, 0), "native chain id");
+        assert_ok!(NftBridge::register_contract(Chain::Tron, LAND.into(), 2, 1, 0));
+    });
+}
+
+#[test]
+fn receive_should_mint() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(0)), "header not relayed");
+
+        add_receipt(Chain::Ethereum, [1; 32], 0, vec![
+            locked_log(ETH_LOCKER, LAND, 5, foreign_id(7)),
+            locked_log(ETH_LOCKER, LAND, 6, foreign_id(8)),
+        ]);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(0)), "unregistered contract");
+
+        assert_ok!(NftBridge::register_contract(Chain::Ethereum, LAND.into(), 1, 1, 0));
+        assert_ok!(NftBridge::receive(Origin::signed(1), eth_proof(0)));
+        assert_eq!(Nft::token(H256(TOKEN_0)).map(|t| t.owner), Some(5));
+        assert_eq!(Nft::balance_of(6), 1);
+        assert_eq!(
+            NftBridge::native_token((Chain::Ethereum, H160(LAND), H256(foreign_id(7)))),
+            Some(H256(TOKEN_0))
+        );
+        assert_eq!(
+            NftBridge::foreign_token(H256(TOKEN_0)),
+            Some((Chain::Ethereum, H160(LAND), H256(foreign_id(7))))
+        );
+        assert_eq!(arrivals()[0], (5, Callback::NftArrived(Chain::Ethereum, H256(TOKEN_0))));
+        assert_eq!(arrivals().len(), 2);
+        assert_eq!(Balances::free_balance(&1), 100 - 2 * 3);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(0)), "receipt already received");
+
+        // locked through the locker of Tron
+        add_receipt(Chain::Ethereum, [1; 32], 1, vec![locked_log(TRON_LOCKER, LAND, 5, foreign_id(9))]);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(1)), "no NftLocked event in the receipt");
+        // a native token which never left
+        add_receipt(Chain::Ethereum, [1; 32], 2, vec![locked_log(ETH_LOCKER, ETH_MIRROR, 5, TOKEN_0)]);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(2)), "token not departed to the chain");
+        // the same token twice, of which nothing is minted
+        add_receipt(Chain::Ethereum, [1; 32], 3, vec![
+            locked_log(ETH_LOCKER, LAND, 5, foreign_id(9)),
+            locked_log(ETH_LOCKER, LAND, 6, foreign_id(9)),
+        ]);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(3)), "token locked twice");
+
+        // paid for by the relayer
+        add_receipt(Chain::Ethereum, [1; 32], 4, vec![locked_log(ETH_LOCKER, LAND, 5, foreign_id(9))]);
+        assert_noop!(NftBridge::receive(Origin::signed(2), eth_proof(4)), "too few free funds in account");
+        assert_ok!(NftBridge::receive(Origin::signed(1), eth_proof(4)));
+        assert_eq!(Balances::free_balance(&1), 100 - 3 * 3);
+    });
+}
+
+#[test]
+fn tokens_should_travel_between_chains() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(NftBridge::register_contract(Chain::Ethereum, LAND.into(), 1, 1, 0));
+        add_receipt(Chain::Ethereum, [1; 32], 0, vec![locked_log(ETH_LOCKER, LAND, 5, foreign_id(7))]);
+        assert_ok!(NftBridge::receive(Origin::signed(1), eth_proof(0)));
+
+        // back to Ethereum, unlocked from its contract
+        assert_noop!(
+            NftBridge::send(Origin::signed(6), H256(TOKEN_0), Chain::Ethereum, RECIPIENT.into()),
+            "not the token owner"
+        );
+        assert_ok!(NftBridge::send(Origin::signed(5), H256(TOKEN_0), Chain::Ethereum, RECIPIENT.into()));
+        assert_eq!(Nft::token(H256(TOKEN_0)), None);
+        let message = NftBridge::message(0).unwrap();
+        assert_eq!((message.contract, message.foreign_token), (H160(LAND), H256(foreign_id(7))));
+        assert_eq!(
+            message.leaf(),
+            H256(hex!("368b0388b3747af3b5d94dc74463407164882fa369698f07493c156ea2d359e5"))
+        );
+
+        // and to Darwinia again, as the same token
+        add_receipt(Chain::Ethereum, [1; 32], 1, vec![locked_log(ETH_LOCKER, LAND, 5, foreign_id(7))]);
+        assert_ok!(NftBridge::receive(Origin::signed(1), eth_proof(1)));
+        assert_eq!(Nft::token(H256(TOKEN_0)).map(|t| t.owner), Some(5));
+        assert_eq!(Nft::class(0).map(|c| c.issued), Some(1));
+
+        // to Tron, minted by the mirror
+        assert_ok!(NftBridge::send(Origin::signed(5), H256(TOKEN_0), Chain::Tron, RECIPIENT.into()));
+        assert_eq!(NftBridge::departed(H256(TOKEN_0)), Some(Chain::Tron));
+        let message = NftBridge::message(1).unwrap();
+        assert_eq!((message.contract, message.foreign_token), (H160::zero(), H256::zero()));
+        assert_eq!(
+            message.leaf(),
+            H256(hex!("ab9483c87bf644fd65dcb6048fcaf8bfb528ea4f360e2a927315de835c61d39e"))
+        );
+
+        // and not back from the mirror of Ethereum
+        add_receipt(Chain::Ethereum, [1; 32], 2, vec![locked_log(ETH_LOCKER, ETH_MIRROR, 6, TOKEN_0)]);
+        assert_noop!(NftBridge::receive(Origin::signed(1), eth_proof(2)), "token not departed to the chain");
+    });
+}
+
+#[test]
+fn messages_should_be_provable() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(NftBridge::register_contract(Chain::Ethereum, LAND.into(), 1, 1, 0));
+        let logs = (0..5).map(|id| locked_log(ETH_LOCKER, LAND, 5, foreign_id(id))).collect();
+        add_receipt(Chain::Ethereum, [1; 32], 0, logs);
+        assert_ok!(NftBridge::receive(Origin::signed(1), eth_proof(0)));
+
+        for index in 0..5u128 {
+            let token = nft::TokenIdParts { chain_id: 1, contract_id: 1, producer_id: 0, index }.encode_id();
+            assert_ok!(NftBridge::send(Origin::signed(5), token, Chain::Tron, RECIPIENT.into()));
+        }
+        assert_eq!(NftBridge::nonce(), 5);
+        assert_eq!(NftBridge::mmr_size(), 8);
+
+        for nonce in 0..5 {
+            let proof = NftBridge::message_proof(nonce, 8).unwrap();
+            assert!(mmr::verify_proof(
+                &NftBridge::mmr_root(8).unwrap(),
+                proof.mmr_size,
+                nonce,
+                &proof.message.leaf(),
+                &proof.siblings,
+                &proof.peaks,
+            ));
+        }
+        assert_eq!(NftBridge::message_proof(5, 8), None);
+        // the root of 4 messages still proves the first ones
+        assert!(NftBridge::message_proof(3, 7).is_some());
+        assert_eq!(NftBridge::message_proof(4, 7), None);
+    });
+}
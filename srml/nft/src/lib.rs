@@ -0,0 +1,336 @@
+//! # NFT Module
+//!
+//! Non-fungible tokens with a stable identity across chains.
+//!
+//! Tokens belong to classes. Anyone can `create_class` for a contract id and a producer id no
+//! other class of `NativeChainId`, the chain id of the tokens produced on Darwinia, has,
+//! reserving `ClassDeposit`; the owner of a class `mint`s its tokens, and gets the deposit back
+//! with `destroy_class` once all of them are burnt. Each token
+//! id is the RFC-0005 encoding of the fields of its class and the index of the token in it,
+//! see `token_id`, so that the id stays the same on every chain the token moves to.
+//!
+//! The owner of a token `transfer`s or `burn`s it, and may `approve` another account to
+//! transfer it once. Classes and tokens carry metadata of up to `MaxMetadataLength` bytes.
+//!
+//! Other modules create classes, possibly without owner, and mint, transfer and burn tokens
+//! through the `create`, `mint_to`, `transfer_from` and `burn_from` functions of the module.
+//! `depart` removes a token moving to another chain, and `remint` mints it again, with the same
+//! id, when it comes back. The classes of the other chain ids are left to them: those of the
+//! tokens arriving through the NFT bridge module are registered by root.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{Decode, Encode};
+use primitive_types::H256;
+use primitives::traits::StaticLookup;
+use rstd::prelude::*;
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::Result;
+use srml_support::traits::{Currency, Get, ReservableCurrency};
+use system::ensure_signed;
+
+pub mod token_id;
+
+pub use token_id::TokenIdParts;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+type RingBalanceOf<T> = <<T as Trait>::Ring as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Identifier of a class of tokens.
+pub type ClassId = u64;
+
+/// Identifier of a token, the RFC-0005 encoding of its `TokenIdParts`.
+pub type TokenId = H256;
+
+/// A class of tokens.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ClassInfo<AccountId> {
+    /// The account minting the tokens, if they are not only minted by other modules.
+    pub owner: Option<AccountId>,
+    pub chain_id: u8,
+    pub contract_id: u8,
+    pub producer_id: u16,
+    /// The number of tokens minted, and the index of the next one.
+    pub issued: u128,
+    pub metadata: Vec<u8>,
+}
+
+/// A token.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TokenInfo<AccountId> {
+    pub class: ClassId,
+    pub owner: AccountId,
+    pub metadata: Vec<u8>,
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The most bytes of metadata of a class or a token.
+    type MaxMetadataLength: Get<u32>;
+
+    /// The currency of the class deposits.
+    type Ring: ReservableCurrency<Self::AccountId>;
+
+    /// The deposit reserved for a class created by an account, kept until the class is destroyed.
+    type ClassDeposit: Get<RingBalanceOf<Self>>;
+
+    /// The chain id of the tokens produced on Darwinia, that of the classes accounts create.
+    type NativeChainId: Get<u8>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Nft {
+        /// The id of the next class.
+        pub NextClassId get(next_class_id): ClassId;
+
+        /// Classes, by id.
+        pub Classes get(class): map ClassId => Option<ClassInfo<T::AccountId>>;
+
+        /// The class of a chain id, a contract id and a producer id.
+        pub ClassOf get(class_of): map (u8, u8, u16) => Option<ClassId>;
+
+        /// The number of tokens of a class which are not burnt, on this chain or departed to
+        /// another one.
+        pub Supply get(supply): map ClassId => u128;
+
+        /// Tokens, by id.
+        pub Tokens get(token): map TokenId => Option<TokenInfo<T::AccountId>>;
+
+        /// The number of tokens of an account.
+        pub Balances get(balance_of): map T::AccountId => u64;
+
+        /// The account approved to transfer a token.
+        pub Approvals get(approved): map TokenId => Option<T::AccountId>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId
+    {
+        /// A class was created, owned by an account or by other modules.
+        ClassCreated(ClassId, Option<AccountId>),
+        /// A class without tokens was destroyed by its owner.
+        ClassDestroyed(ClassId, AccountId),
+        /// A token was minted to an account.
+        Minted(TokenId, AccountId),
+        /// A token was transferred from an account to another.
+        Transferred(TokenId, AccountId, AccountId),
+        /// The owner of a token approved an account to transfer it, or cancelled the approval.
+        Approved(TokenId, AccountId, Option<AccountId>),
+        /// A token of an account was burnt.
+        Burned(TokenId, AccountId),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const MaxMetadataLength: u32 = T::MaxMetadataLength::get();
+        const ClassDeposit: RingBalanceOf<T> = T::ClassDeposit::get();
+        const NativeChainId: u8 = T::NativeChainId::get();
+
+        fn deposit_event<T>() = default;
+
+        /// Create a class of tokens of `NativeChainId`, minted by the sender, reserving
+        /// `ClassDeposit`.
+        fn create_class(origin, contract_id: u8, producer_id: u16, metadata: Vec<u8>) {
+            let who = ensure_signed(origin)?;
+            ensure!(T::Ring::can_reserve(&who, T::ClassDeposit::get()), "not enough to reserve the class deposit");
+
+            Self::create(Some(who.clone()), T::NativeChainId::get(), contract_id, producer_id, metadata)?;
+            T::Ring::reserve(&who, T::ClassDeposit::get())?;
+        }
+
+        /// Destroy a class of the sender none of whose tokens are left, unreserving
+        /// `ClassDeposit`.
+        fn destroy_class(origin, class: ClassId) {
+            let who = ensure_signed(origin)?;
+            let info = Self::class(class).ok_or("unknown class")?;
+            ensure!(info.owner.as_ref() == Some(&who), "not the class owner");
+            ensure!(Self::supply(class) == 0, "class has tokens left");
+
+            <Classes<T>>::remove(class);
+            ClassOf::remove((info.chain_id, info.contract_id, info.producer_id));
+            Supply::remove(class);
+            T::Ring::unreserve(&who, T::ClassDeposit::get());
+
+            Self::deposit_event(RawEvent::ClassDestroyed(class, who));
+        }
+
+        /// Mint a token of a class of the sender to `dest`.
+        fn mint(origin, class: ClassId, dest: <T::Lookup as StaticLookup>::Source, metadata: Vec<u8>) {
+            let who = ensure_signed(origin)?;
+            let dest = T::Lookup::lookup(dest)?;
+            ensure!(Self::class(class).and_then(|c| c.owner) == Some(who), "not the class owner");
+
+            Self::mint_to(class, &dest, metadata)?;
+        }
+
+        /// Transfer a token owned by the sender, or it was approved to transfer, to `dest`.
+        fn transfer(origin, dest: <T::Lookup as StaticLookup>::Source, token: TokenId) {
+            let who = ensure_signed(origin)?;
+            let dest = T::Lookup::lookup(dest)?;
+            let owner = Self::token(token).ok_or("unknown token")?.owner;
+            ensure!(owner == who || Self::approved(token) == Some(who), "not the token owner or approved");
+
+            Self::transfer_from(&owner, &dest, token)?;
+        }
+
+        /// Approve `approved` to transfer a token of the sender, or cancel the approval.
+        fn approve(origin, token: TokenId, approved: Option<<T::Lookup as StaticLookup>::Source>) {
+            let who = ensure_signed(origin)?;
+            let approved = match approved {
+                Some(approved) => Some(T::Lookup::lookup(approved)?),
+                None => None,
+            };
+            ensure!(Self::token(token).map(|t| t.owner) == Some(who.clone()), "not the token owner");
+
+            match approved {
+                Some(ref approved) => <Approvals<T>>::insert(token, approved),
+                None => <Approvals<T>>::remove(token),
+            }
+
+            Self::deposit_event(RawEvent::Approved(token, who, approved));
+        }
+
+        /// Burn a token of the sender.
+        fn burn(origin, token: TokenId) {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::token(token).map(|t| t.owner) == Some(who), "not the token owner");
+
+            Self::burn_from(token)?;
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Create a class of tokens minted by `owner`, or only by other modules.
+    pub fn create(
+        owner: Option<T::AccountId>,
+        chain_id: u8,
+        contract_id: u8,
+        producer_id: u16,
+        metadata: Vec<u8>,
+    ) -> rstd::result::Result<ClassId, &'static str> {
+        ensure!(metadata.len() <= T::MaxMetadataLength::get() as usize, "metadata too long");
+        ensure!(!ClassOf::exists((chain_id, contract_id, producer_id)), "class already exists");
+
+        let class = Self::next_class_id();
+        NextClassId::put(class.checked_add(1).ok_or("class id overflow")?);
+        ClassOf::insert((chain_id, contract_id, producer_id), class);
+        <Classes<T>>::insert(class, ClassInfo {
+            owner: owner.clone(),
+            chain_id,
+            contract_id,
+            producer_id,
+            issued: 0,
+            metadata,
+        });
+
+        Self::deposit_event(RawEvent::ClassCreated(class, owner));
+        Ok(class)
+    }
+
+    /// Mint the next token of `class` to `dest`.
+    pub fn mint_to(
+        class: ClassId,
+        dest: &T::AccountId,
+        metadata: Vec<u8>,
+    ) -> rstd::result::Result<TokenId, &'static str> {
+        let mut info = Self::class(class).ok_or("unknown class")?;
+
+        let token = TokenIdParts {
+            chain_id: info.chain_id,
+            contract_id: info.contract_id,
+            producer_id: info.producer_id,
+            index: info.issued,
+        }.encode_id();
+        info.issued = info.issued.checked_add(1).ok_or("token index overflow")?;
+
+        Self::insert_token(token, class, dest, metadata)?;
+        <Classes<T>>::insert(class, info);
+        Supply::mutate(class, |supply| *supply += 1);
+        Ok(token)
+    }
+
+    /// Mint `token`, minted and departed before, to `dest` again.
+    pub fn remint(token: TokenId, dest: &T::AccountId, metadata: Vec<u8>) -> Result {
+        let class = Self::remintable_class(token)?;
+        Self::insert_token(token, class, dest, metadata)
+    }
+
+    /// The class of `token`, if it was minted and burnt, so that it can be minted again.
+    pub fn remintable_class(token: TokenId) -> rstd::result::Result<ClassId, &'static str> {
+        ensure!(!<Tokens<T>>::exists(token), "token already exists");
+        let parts = TokenIdParts::decode_id(&token)?;
+        let class = Self::class_of((parts.chain_id, parts.contract_id, parts.producer_id)).ok_or("unknown class")?;
+        ensure!(
+            Self::class(class).map(|c| parts.index < c.issued) == Some(true),
+            "token never minted"
+        );
+        Ok(class)
+    }
+
+    /// Transfer `token` of `from` to `dest`, cancelling its approval.
+    pub fn transfer_from(from: &T::AccountId, dest: &T::AccountId, token: TokenId) -> Result {
+        let mut info = Self::token(token).ok_or("unknown token")?;
+        ensure!(info.owner == *from, "not the token owner");
+
+        if from != dest {
+            let balance = Self::balance_of(dest).checked_add(1).ok_or("too many tokens")?;
+            <Balances<T>>::mutate(from, |b| *b -= 1);
+            <Balances<T>>::insert(dest, balance);
+        }
+        info.owner = dest.clone();
+        <Tokens<T>>::insert(token, info);
+        <Approvals<T>>::remove(token);
+
+        Self::deposit_event(RawEvent::Transferred(token, from.clone(), dest.clone()));
+        Ok(())
+    }
+
+    fn insert_token(token: TokenId, class: ClassId, dest: &T::AccountId, metadata: Vec<u8>) -> Result {
+        ensure!(metadata.len() <= T::MaxMetadataLength::get() as usize, "metadata too long");
+        let balance = Self::balance_of(dest).checked_add(1).ok_or("too many tokens")?;
+
+        <Tokens<T>>::insert(token, TokenInfo {
+            class,
+            owner: dest.clone(),
+            metadata,
+        });
+        <Balances<T>>::insert(dest, balance);
+
+        Self::deposit_event(RawEvent::Minted(token, dest.clone()));
+        Ok(())
+    }
+
+    /// Burn `token`.
+    pub fn burn_from(token: TokenId) -> Result {
+        let class = Self::token(token).ok_or("unknown token")?.class;
+        Self::depart(token)?;
+        Supply::mutate(class, |supply| *supply = supply.saturating_sub(1));
+        Ok(())
+    }
+
+    /// Remove `token`, which moves to another chain, keeping it in the supply of its class since
+    /// it can come back.
+    pub fn depart(token: TokenId) -> Result {
+        let info = Self::token(token).ok_or("unknown token")?;
+
+        <Balances<T>>::mutate(&info.owner, |b| *b -= 1);
+        <Tokens<T>>::remove(token);
+        <Approvals<T>>::remove(token);
+
+        Self::deposit_event(RawEvent::Burned(token, info.owner));
+        Ok(())
+    }
+}
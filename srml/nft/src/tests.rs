@@ -0,0 +1,181 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use hex_literal::hex;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use mock::*;
+use super::*;
+
+const TOKEN_0: [u8; 32] = hex!("2a01020000000000000300000000000000000000000000000000000000000000");
+const TOKEN_1: [u8; 32] = hex!("2a01020000000000000300000000000000000000000000000000000000000001");
+
+#[test]
+fn classes_should_be_created() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, b"land".to_vec()));
+        assert_eq!(Nft::class_of((1, 2, 3)), Some(0));
+        assert_eq!(Nft::class(0), Some(ClassInfo {
+            owner: Some(1),
+            chain_id: 1,
+            contract_id: 2,
+            producer_id: 3,
+            issued: 0,
+            metadata: b"land".to_vec(),
+        }));
+        assert_eq!(Ring::reserved_balance(&1), 10);
+
+        assert_noop!(Nft::create_class(Origin::signed(2), 2, 3, vec![]), "class already exists");
+        assert_noop!(Nft::create_class(Origin::signed(2), 2, 4, vec![0; 17]), "metadata too long");
+
+        assert_noop!(
+            Nft::create_class(Origin::signed(3), 2, 4, vec![]),
+            "not enough to reserve the class deposit"
+        );
+        assert_eq!(Ring::reserved_balance(&2), 0);
+
+        assert_ok!(Nft::create_class(Origin::signed(2), 2, 4, vec![]));
+        assert_eq!(Ring::reserved_balance(&2), 10);
+        assert_eq!(Nft::next_class_id(), 2);
+    });
+}
+
+#[test]
+fn mint_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, vec![]));
+        assert_noop!(Nft::mint(Origin::signed(2), 0, 2, vec![]), "not the class owner");
+        assert_noop!(Nft::mint(Origin::signed(1), 1, 2, vec![]), "not the class owner");
+        assert_noop!(Nft::mint(Origin::signed(1), 0, 2, vec![0; 17]), "metadata too long");
+
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, b"apostle".to_vec()));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        assert_eq!(Nft::token(H256(TOKEN_0)), Some(TokenInfo {
+            class: 0,
+            owner: 2,
+            metadata: b"apostle".to_vec(),
+        }));
+        assert_eq!(Nft::token(H256(TOKEN_1)).map(|t| t.owner), Some(2));
+        assert_eq!(Nft::balance_of(2), 2);
+        assert_eq!(Nft::class(0).unwrap().issued, 2);
+
+        assert_eq!(TokenIdParts::decode_id(&H256(TOKEN_1)), Ok(TokenIdParts {
+            chain_id: 1,
+            contract_id: 2,
+            producer_id: 3,
+            index: 1,
+        }));
+    });
+}
+
+#[test]
+fn transfer_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, vec![]));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        let token = H256(TOKEN_0);
+
+        assert_noop!(Nft::transfer(Origin::signed(3), 3, token), "not the token owner or approved");
+        assert_noop!(Nft::transfer(Origin::signed(2), 3, H256(TOKEN_1)), "unknown token");
+
+        assert_ok!(Nft::transfer(Origin::signed(2), 3, token));
+        assert_eq!(Nft::token(token).unwrap().owner, 3);
+        assert_eq!(Nft::balance_of(2), 0);
+        assert_eq!(Nft::balance_of(3), 1);
+    });
+}
+
+#[test]
+fn approvals_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, vec![]));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        let token = H256(TOKEN_0);
+
+        assert_noop!(Nft::approve(Origin::signed(3), token, Some(3)), "not the token owner");
+        assert_ok!(Nft::approve(Origin::signed(2), token, Some(3)));
+        assert_eq!(Nft::approved(token), Some(3));
+        assert_ok!(Nft::approve(Origin::signed(2), token, None));
+        assert_noop!(Nft::transfer(Origin::signed(3), 4, token), "not the token owner or approved");
+
+        // the approval is used up by the transfer
+        assert_ok!(Nft::approve(Origin::signed(2), token, Some(3)));
+        assert_ok!(Nft::transfer(Origin::signed(3), 4, token));
+        assert_eq!(Nft::token(token).unwrap().owner, 4);
+        assert_eq!(Nft::approved(token), None);
+        assert_noop!(Nft::transfer(Origin::signed(3), 3, token), "not the token owner or approved");
+    });
+}
+
+#[test]
+fn burn_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, vec![]));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        let token = H256(TOKEN_0);
+        assert_ok!(Nft::approve(Origin::signed(2), token, Some(3)));
+
+        assert_noop!(Nft::burn(Origin::signed(3), token), "not the token owner");
+        assert_ok!(Nft::burn(Origin::signed(2), token));
+        assert_eq!(Nft::token(token), None);
+        assert_eq!(Nft::approved(token), None);
+        assert_eq!(Nft::balance_of(2), 0);
+
+        // the index is not reused
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        assert_eq!(Nft::token(H256(TOKEN_1)).map(|t| t.owner), Some(2));
+    });
+}
+
+#[test]
+fn remint_should_work() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_eq!(Nft::create(None, 1, 2, 3, vec![]), Ok(0));
+        assert_noop!(Nft::mint(Origin::signed(1), 0, 2, vec![]), "not the class owner");
+        assert_eq!(Nft::mint_to(0, &2, vec![]), Ok(H256(TOKEN_0)));
+
+        assert_noop!(Nft::remint(H256(TOKEN_0), &3, vec![]), "token already exists");
+        assert_noop!(Nft::remint(H256(TOKEN_1), &3, vec![]), "token never minted");
+        assert_noop!(Nft::remint(H256([0x2a; 32]), &3, vec![]), "invalid token id reserved bytes");
+
+        assert_ok!(Nft::depart(H256(TOKEN_0)));
+        assert_eq!(Nft::supply(0), 1);
+        assert_ok!(Nft::remint(H256(TOKEN_0), &3, b"back".to_vec()));
+        assert_eq!(Nft::token(H256(TOKEN_0)), Some(TokenInfo {
+            class: 0,
+            owner: 3,
+            metadata: b"back".to_vec(),
+        }));
+        assert_eq!(Nft::balance_of(3), 1);
+    });
+}
+
+#[test]
+fn destroy_class_should_unreserve_the_deposit() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Nft::create_class(Origin::signed(1), 2, 3, vec![]));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        assert_ok!(Nft::mint(Origin::signed(1), 0, 2, vec![]));
+        assert_eq!(Nft::supply(0), 2);
+
+        assert_noop!(Nft::destroy_class(Origin::signed(2), 0), "not the class owner");
+        assert_noop!(Nft::destroy_class(Origin::signed(1), 1), "unknown class");
+
+        // a departed token may come back
+        assert_ok!(Nft::burn(Origin::signed(2), H256(TOKEN_0)));
+        assert_ok!(Nft::depart(H256(TOKEN_1)));
+        assert_noop!(Nft::destroy_class(Origin::signed(1), 0), "class has tokens left");
+
+        assert_ok!(Nft::remint(H256(TOKEN_1), &2, vec![]));
+        assert_ok!(Nft::burn(Origin::signed(2), H256(TOKEN_1)));
+        assert_ok!(Nft::destroy_class(Origin::signed(1), 0));
+        assert_eq!(Nft::class(0), None);
+        assert_eq!(Nft::class_of((1, 2, 3)), None);
+        assert_eq!(Ring::reserved_balance(&1), 0);
+
+        // classes without owner are never destroyed
+        assert_eq!(Nft::create(None, 1, 2, 4, vec![]), Ok(1));
+        assert_noop!(Nft::destroy_class(Origin::signed(1), 1), "not the class owner");
+    });
+}
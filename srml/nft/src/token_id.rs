@@ -0,0 +1,107 @@
+//! RFC-0005 token ids: 256 bits identifying a token on every chain it moves to.
+//!
+//! Big endian, by byte:
+//!
+//! | bytes  | field         |
+//! |--------|---------------|
+//! | 0      | magic number  |
+//! | 1      | chain id      |
+//! | 2      | contract id   |
+//! | 3..8   | reserved      |
+//! | 8..10  | producer id   |
+//! | 10..16 | reserved      |
+//! | 16..32 | index         |
+//!
+//! Reserved bytes are zero.
+
+use parity_codec::{Decode, Encode};
+use primitive_types::H256;
+
+/// The first byte of every token id.
+pub const MAGIC_NUMBER: u8 = 0x2a;
+
+/// The fields of a token id.
+#[derive(Encode, Decode, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TokenIdParts {
+    /// The chain the token was created on.
+    pub chain_id: u8,
+    /// The contract of the token on that chain.
+    pub contract_id: u8,
+    /// What produced the token, a game for instance.
+    pub producer_id: u16,
+    /// The index of the token among those of the same chain, contract and producer.
+    pub index: u128,
+}
+
+impl TokenIdParts {
+    /// The token id made of these fields.
+    pub fn encode_id(&self) -> H256 {
+        let mut id = [0u8; 32];
+        id[0] = MAGIC_NUMBER;
+        id[1] = self.chain_id;
+        id[2] = self.contract_id;
+        id[8..10].copy_from_slice(&self.producer_id.to_be_bytes());
+        id[16..].copy_from_slice(&self.index.to_be_bytes());
+        H256(id)
+    }
+
+    /// The fields of the token id `id`.
+    pub fn decode_id(id: &H256) -> Result<Self, &'static str> {
+        let id = id.as_bytes();
+        if id[0] != MAGIC_NUMBER {
+            return Err("invalid token id magic number");
+        }
+        if id[3..8].iter().chain(&id[10..16]).any(|byte| *byte != 0) {
+            return Err("invalid token id reserved bytes");
+        }
+
+        let mut producer_id = [0u8; 2];
+        producer_id.copy_from_slice(&id[8..10]);
+        let mut index = [0u8; 16];
+        index.copy_from_slice(&id[16..]);
+        Ok(TokenIdParts {
+            chain_id: id[1],
+            contract_id: id[2],
+            producer_id: u16::from_be_bytes(producer_id),
+            index: u128::from_be_bytes(index),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use super::*;
+
+    #[test]
+    fn token_ids_should_convert() {
+        let parts = TokenIdParts {
+            chain_id: 1,
+            contract_id: 2,
+            producer_id: 0x0304,
+            index: 0x0506,
+        };
+        let id = H256(hex!("2a01020000000000030400000000000000000000000000000000000000000506"));
+        assert_eq!(parts.encode_id(), id);
+        assert_eq!(TokenIdParts::decode_id(&id), Ok(parts));
+
+        let max = TokenIdParts {
+            chain_id: 0xff,
+            contract_id: 0xff,
+            producer_id: 0xffff,
+            index: u128::max_value(),
+        };
+        assert_eq!(TokenIdParts::decode_id(&max.encode_id()), Ok(max));
+    }
+
+    #[test]
+    fn invalid_token_ids_should_not_decode() {
+        let id = H256(hex!("2b01020000000000030400000000000000000000000000000000000000000506"));
+        assert_eq!(TokenIdParts::decode_id(&id), Err("invalid token id magic number"));
+        let id = H256(hex!("2a01020000000001030400000000000000000000000000000000000000000506"));
+        assert_eq!(TokenIdParts::decode_id(&id), Err("invalid token id reserved bytes"));
+        let id = H256(hex!("2a01020000000000030400000000000100000000000000000000000000000506"));
+        assert_eq!(TokenIdParts::decode_id(&id), Err("invalid token id reserved bytes"));
+    }
+}
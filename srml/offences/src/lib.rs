@@ -0,0 +1,382 @@
+//! # Offences Module
+//!
+//! Collects misbehaviour reports of validators and turns them into slashes.
+//!
+//! Reports come from Aura (equivocations, through `aura::OffenceReporter`) and from GRANDPA
+//! voters casting two different votes in the same round of the current authority set, which
+//! anyone can prove with the unsigned `report_grandpa_equivocation` call. Offline validators
+//! are reported to staking by the ImOnline module.
+//!
+//! The id of the GRANDPA authority set is tracked here: it starts at 0 with the genesis
+//! authorities and is incremented every time a change the GRANDPA module logged is enacted,
+//! even one keeping the same authorities, like the set id of the GRANDPA client.
+//!
+//! GRANDPA justifications are checked against the current authority set as well: anyone can
+//! note one with the unsigned `note_grandpa_justification` call, and the highest block
+//! justified so far is kept in `GrandpaFinalized`, the finality staking waits for.
+//!
+//! Each validator is punished at most once per kind of offence in a session. The slash
+//! fraction of a kind grows with the number of validators committing it in the same
+//! session: a lone offender is likely a misconfigured node, many of them an attack.
+//! When another offender shows up, the ones reported earlier in the session are slashed
+//! by the increase of the fraction, so all of them end up paying (roughly) the same share.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_codec::{CompactAs, Decode, Encode};
+use primitives::{ApplyError, Perbill};
+use primitives::traits::{ValidateUnsigned, Verify};
+use primitives::transaction_validity::{TransactionLongevity, TransactionPriority, TransactionValidity};
+use rstd::{prelude::*, result};
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use system::ensure_none;
+use session::SessionIndex;
+use grandpa::AuthorityId as GrandpaId;
+use grandpa::fg_primitives::{AuthoritySignature as GrandpaSignature, ConsensusLog, GRANDPA_ENGINE_ID};
+use dsupport::traits::{OffenceKind, OnOffenceHandler, ReportOffence};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// A GRANDPA vote, encoded like the `Message` of the `finality-grandpa` crate.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum GrandpaVote<Hash, Number> {
+    Prevote { target_hash: Hash, target_number: Number },
+    Precommit { target_hash: Hash, target_number: Number },
+}
+
+/// Proof that a GRANDPA voter signed two different votes of the same kind in one round.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GrandpaEquivocationProof<Hash, Number> {
+    /// The authority set the votes were cast in.
+    pub set_id: u64,
+    /// The round the votes were cast in.
+    pub round: u64,
+    /// The voter.
+    pub offender: GrandpaId,
+    /// The first vote and its signature.
+    pub first: (GrandpaVote<Hash, Number>, GrandpaSignature),
+    /// The second vote and its signature.
+    pub second: (GrandpaVote<Hash, Number>, GrandpaSignature),
+}
+
+impl<Hash: Encode, Number: Encode> GrandpaEquivocationProof<Hash, Number> {
+    /// The message signed by a GRANDPA voter for `vote`.
+    pub fn signed_payload(&self, vote: &GrandpaVote<Hash, Number>) -> Vec<u8> {
+        (vote, self.round, self.set_id).encode()
+    }
+}
+
+/// A signed GRANDPA precommit, encoded like the `SignedPrecommit` of the `finality-grandpa` crate.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GrandpaSignedPrecommit<Hash, Number> {
+    pub target_hash: Hash,
+    pub target_number: Number,
+    pub signature: GrandpaSignature,
+    pub id: GrandpaId,
+}
+
+/// A GRANDPA justification, encoded like the ones the GRANDPA client stores with the blocks
+/// it finalizes.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GrandpaJustification<Hash, Number, Header> {
+    /// The round the block was finalized in.
+    pub round: u64,
+    /// The finalized block.
+    pub target_hash: Hash,
+    pub target_number: Number,
+    /// Precommits for the finalized block or its descendants.
+    pub precommits: Vec<GrandpaSignedPrecommit<Hash, Number>>,
+    /// The headers between the finalized block and the precommitted ones, not needed on chain,
+    /// where the hashes of past blocks are known.
+    pub votes_ancestries: Vec<Header>,
+}
+
+/// Slash fraction for `offenders` out of `validators` validators equivocating in one
+/// session: `min(1, (3 * offenders / validators) ^ 2)`.
+pub fn equivocation_slash(offenders: u32, validators: u32) -> Perbill {
+    if validators == 0 {
+        return Perbill::zero();
+    }
+
+    let x = (3 * u64::from(offenders)).min(u64::from(validators));
+    Perbill::from_rational_approximation(x * x, u64::from(validators) * u64::from(validators))
+}
+
+/// Slash fraction of `kind` for `offenders` out of `validators` validators.
+pub fn slash_fraction(kind: OffenceKind, offenders: u32, validators: u32) -> Perbill {
+    match kind {
+        OffenceKind::AuraEquivocation | OffenceKind::GrandpaEquivocation =>
+            equivocation_slash(offenders, validators),
+    }
+}
+
+pub trait Trait: system::Trait + session::Trait + grandpa::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// Punishes the offenders, called with their session account.
+    type OnOffenceHandler: OnOffenceHandler<Self::AccountId>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Offences {
+        /// The session `Offenders` and `SlashFractions` belong to.
+        pub ReportedSession get(reported_session): SessionIndex;
+
+        /// Validators punished for each kind of offence in `ReportedSession`.
+        pub Offenders get(offenders): map OffenceKind => Vec<T::AccountId>;
+
+        /// The fraction `Offenders` of each kind have been slashed by so far.
+        pub SlashFractions get(slash_fractions): map OffenceKind => Perbill;
+
+        /// The id of the current GRANDPA authority set.
+        pub GrandpaSetId get(grandpa_set_id): u64;
+
+        /// The block the pending GRANDPA change is enacted at, ending `GrandpaSetId`.
+        pub NextGrandpaChange get(next_grandpa_change): Option<T::BlockNumber>;
+
+        /// The highest block a GRANDPA justification of the current authorities was noted for.
+        pub GrandpaFinalized get(grandpa_finalized): T::BlockNumber;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where <T as system::Trait>::AccountId {
+        /// A validator committed an offence in the session; the slash fraction of
+        /// this kind of offence is now the given one.
+        Offence(OffenceKind, SessionIndex, AccountId, Perbill),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
+        fn on_finalize(now: T::BlockNumber) {
+            // the GRANDPA module logs the changes it schedules in its own `on_finalize`, before
+            // this one, and the client starts a new set once a change is enacted, standard or forced
+            let changes = <system::Module<T>>::digest().logs().iter()
+                .filter_map(|item| item.as_consensus())
+                .filter(|(id, _)| *id == GRANDPA_ENGINE_ID)
+                .filter_map(|(_, mut data)| ConsensusLog::<T::BlockNumber>::decode(&mut data))
+                .filter_map(|log| match log {
+                    ConsensusLog::ScheduledChange(change) | ConsensusLog::ForcedChange(_, change) => Some(change),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            for change in changes {
+                <NextGrandpaChange<T>>::put(now + change.delay);
+            }
+
+            if Self::next_grandpa_change() == Some(now) {
+                GrandpaSetId::mutate(|id| *id += 1);
+                <NextGrandpaChange<T>>::kill();
+            }
+        }
+
+        /// Report a GRANDPA voter for signing two different votes in the same round.
+        ///
+        /// This is an unsigned transaction, checked by `validate_unsigned`.
+        fn report_grandpa_equivocation(origin, proof: GrandpaEquivocationProof<T::Hash, T::BlockNumber>) {
+            ensure_none(origin)?;
+
+            let offender = Self::check_grandpa_equivocation_proof(&proof)?;
+            ensure!(
+                !Self::is_reported(OffenceKind::GrandpaEquivocation, &offender),
+                "offence already reported for this session"
+            );
+
+            Self::report_offence(OffenceKind::GrandpaEquivocation, offender);
+        }
+
+        /// Note a GRANDPA justification of the current authority set.
+        ///
+        /// This is an unsigned transaction, checked by `validate_unsigned`.
+        fn note_grandpa_justification(origin, justification: GrandpaJustification<T::Hash, T::BlockNumber, T::Header>) {
+            ensure_none(origin)?;
+
+            Self::check_grandpa_justification(&justification)?;
+            <GrandpaFinalized<T>>::put(justification.target_number);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// Whether `offender` has already been punished for `kind` in the current session.
+    pub fn is_reported(kind: OffenceKind, offender: &T::AccountId) -> bool {
+        Self::reported_session() == <session::Module<T>>::current_index()
+            && Self::offenders(kind).contains(offender)
+    }
+
+    /// Check `proof` against the current GRANDPA authorities, returning the session
+    /// account of the offender. Votes of other authority sets are rejected.
+    pub fn check_grandpa_equivocation_proof(
+        proof: &GrandpaEquivocationProof<T::Hash, T::BlockNumber>,
+    ) -> result::Result<T::AccountId, &'static str> {
+        ensure!(proof.set_id == Self::grandpa_set_id(), "votes are not of the current GRANDPA set");
+        let same_kind = match (&proof.first.0, &proof.second.0) {
+            (GrandpaVote::Prevote { .. }, GrandpaVote::Prevote { .. }) => true,
+            (GrandpaVote::Precommit { .. }, GrandpaVote::Precommit { .. }) => true,
+            _ => false,
+        };
+        ensure!(same_kind, "votes are of different kinds");
+        ensure!(proof.first.0 != proof.second.0, "votes are identical");
+        ensure!(
+            proof.first.1.verify(&proof.signed_payload(&proof.first.0)[..], &proof.offender),
+            "bad signature on first vote"
+        );
+        ensure!(
+            proof.second.1.verify(&proof.signed_payload(&proof.second.0)[..], &proof.offender),
+            "bad signature on second vote"
+        );
+
+        // GRANDPA authorities are kept in the order of the session validators
+        let index = <grandpa::Module<T>>::grandpa_authorities()
+            .iter()
+            .position(|(id, _)| *id == proof.offender)
+            .ok_or("offender is not a GRANDPA authority")?;
+        <session::Module<T>>::validators()
+            .get(index)
+            .cloned()
+            .ok_or("offender is not a validator")
+    }
+
+    /// Check that `justification` is signed by a supermajority of the current GRANDPA
+    /// authorities, for a block of this chain above `GrandpaFinalized`.
+    pub fn check_grandpa_justification(
+        justification: &GrandpaJustification<T::Hash, T::BlockNumber, T::Header>,
+    ) -> result::Result<(), &'static str> {
+        let in_chain = |hash: &T::Hash, number: T::BlockNumber| {
+            number < <system::Module<T>>::block_number() && <system::Module<T>>::block_hash(number) == *hash
+        };
+
+        ensure!(justification.target_number > Self::grandpa_finalized(), "block already finalized");
+        ensure!(in_chain(&justification.target_hash, justification.target_number), "target is not in the chain");
+
+        let authorities = <grandpa::Module<T>>::grandpa_authorities();
+        let total: u64 = authorities.iter().map(|(_, weight)| weight).sum();
+        ensure!(total > 0, "no GRANDPA authorities");
+        // like the GRANDPA client, more than two thirds of the weight, rounded down
+        let threshold = total - (total - 1) / 3;
+
+        let set_id = Self::grandpa_set_id();
+        let mut signers = Vec::new();
+        let mut weight = 0u64;
+        for precommit in justification.precommits.iter() {
+            // the precommitted block is a descendant of the target if both are in the chain
+            ensure!(
+                precommit.target_number >= justification.target_number
+                    && in_chain(&precommit.target_hash, precommit.target_number),
+                "precommit is not for a descendant of the target"
+            );
+            let vote = GrandpaVote::Precommit {
+                target_hash: precommit.target_hash.clone(),
+                target_number: precommit.target_number,
+            };
+            ensure!(
+                precommit.signature.verify(&(vote, justification.round, set_id).encode()[..], &precommit.id),
+                "bad precommit signature"
+            );
+            let voter_weight = authorities.iter()
+                .find(|(id, _)| *id == precommit.id)
+                .map(|(_, weight)| *weight)
+                .ok_or("precommit of a non authority")?;
+
+            if !signers.contains(&precommit.id) {
+                signers.push(precommit.id.clone());
+                weight += voter_weight;
+            }
+        }
+        ensure!(weight >= threshold, "not enough precommits");
+
+        Ok(())
+    }
+}
+
+impl<T: Trait> ReportOffence<T::AccountId> for Module<T> {
+    fn report_offence(kind: OffenceKind, offender: T::AccountId) {
+        let session = <session::Module<T>>::current_index();
+        if Self::reported_session() != session {
+            for stale in OffenceKind::ALL.iter() {
+                <Offenders<T>>::remove(stale);
+                SlashFractions::remove(stale);
+            }
+            ReportedSession::put(session);
+        }
+
+        let mut offenders = Self::offenders(kind);
+        if offenders.contains(&offender) {
+            return;
+        }
+        offenders.push(offender.clone());
+
+        let validators = <session::Module<T>>::validators().len() as u32;
+        let fraction = slash_fraction(kind, offenders.len() as u32, validators);
+        let previous = *Self::slash_fractions(kind).encode_as();
+        let current = *fraction.encode_as();
+
+        // the earlier offenders only pay for the increase.
+        if current > previous {
+            let increase = Perbill::from_parts(current - previous);
+            for earlier in offenders.iter().filter(|o| **o != offender) {
+                T::OnOffenceHandler::on_offence(earlier, kind, increase);
+            }
+        }
+        T::OnOffenceHandler::on_offence(&offender, kind, fraction);
+
+        <Offenders<T>>::insert(kind, offenders);
+        SlashFractions::insert(kind, fraction);
+        Self::deposit_event(RawEvent::Offence(kind, session, offender, fraction));
+    }
+}
+
+impl<T: Trait> ValidateUnsigned for Module<T> {
+    type Call = Call<T>;
+
+    fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+        match call {
+            Call::report_grandpa_equivocation(proof) => {
+                let offender = match Self::check_grandpa_equivocation_proof(proof) {
+                    Ok(offender) => offender,
+                    Err(_) => return TransactionValidity::Invalid(ApplyError::BadSignature as i8),
+                };
+                if Self::is_reported(OffenceKind::GrandpaEquivocation, &offender) {
+                    return TransactionValidity::Invalid(ApplyError::Stale as i8);
+                }
+
+                TransactionValidity::Valid {
+                    priority: TransactionPriority::max_value(),
+                    requires: vec![],
+                    provides: vec![(OffenceKind::GrandpaEquivocation, offender).encode()],
+                    longevity: TransactionLongevity::max_value(),
+                    propagate: true,
+                }
+            }
+            Call::note_grandpa_justification(justification) => {
+                if justification.target_number <= Self::grandpa_finalized() {
+                    return TransactionValidity::Invalid(ApplyError::Stale as i8);
+                }
+                if Self::check_grandpa_justification(justification).is_err() {
+                    return TransactionValidity::Invalid(ApplyError::BadSignature as i8);
+                }
+
+                TransactionValidity::Valid {
+                    priority: TransactionPriority::max_value(),
+                    requires: vec![],
+                    provides: vec![(b"grandpa_justification", justification.target_number).encode()],
+                    longevity: TransactionLongevity::max_value(),
+                    propagate: true,
+                }
+            }
+            _ => TransactionValidity::Invalid(0),
+        }
+    }
+}
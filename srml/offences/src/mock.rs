@@ -0,0 +1,111 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use primitives::Perbill;
+use primitives::traits::{IdentityLookup, OpaqueKeys, BlakeTwo256};
+use primitives::testing::{Header, UintAuthorityId};
+use substrate_primitives::{ed25519, H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{impl_outer_origin, parameter_types};
+use dsupport::traits::{OffenceKind, OnOffenceHandler};
+use crate::{Module, Trait};
+
+pub type AccountId = u64;
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+thread_local! {
+    static OFFENCES: RefCell<Vec<(AccountId, OffenceKind, Perbill)>> = RefCell::new(vec![]);
+}
+
+/// Records the slashes handed out by the module.
+pub struct OffenceRecorder;
+impl OnOffenceHandler<AccountId> for OffenceRecorder {
+    fn on_offence(offender: &AccountId, kind: OffenceKind, slash: Perbill) {
+        OFFENCES.with(|v| v.borrow_mut().push((*offender, kind, slash)));
+    }
+}
+
+pub fn offences() -> Vec<(AccountId, OffenceKind, Perbill)> {
+    OFFENCES.with(|v| v.borrow().clone())
+}
+
+pub struct TestSessionHandler;
+impl session::SessionHandler<AccountId> for TestSessionHandler {
+    fn on_new_session<Ks: OpaqueKeys>(_changed: bool, _validators: &[(AccountId, Ks)]) {}
+    fn on_disabled(_validator_index: usize) {}
+}
+
+pub struct TestSessionEnding;
+impl session::OnSessionEnding<AccountId> for TestSessionEnding {
+    fn on_session_ending(_: session::SessionIndex) -> Option<Vec<AccountId>> {
+        None
+    }
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const Period: u64 = 1;
+    pub const Offset: u64 = 0;
+}
+
+impl session::Trait for Test {
+    type OnSessionEnding = TestSessionEnding;
+    type Keys = UintAuthorityId;
+    type ShouldEndSession = session::PeriodicSessions<Period, Offset>;
+    type SessionHandler = TestSessionHandler;
+    type Event = ();
+}
+
+impl grandpa::Trait for Test {
+    type Event = ();
+}
+
+impl Trait for Test {
+    type Event = ();
+    type OnOffenceHandler = OffenceRecorder;
+}
+
+/// The GRANDPA key of validator `i`.
+pub fn grandpa_pair(i: u8) -> ed25519::Pair {
+    use substrate_primitives::Pair;
+    ed25519::Pair::from_seed(&[i; 32])
+}
+
+/// `validators` validators, numbered from 1, whose GRANDPA keys come from `grandpa_pair`.
+pub fn new_test_ext(validators: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+    use substrate_primitives::Pair;
+
+    let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap().0;
+    t.extend(session::GenesisConfig::<Test> {
+        validators: (1..=validators).collect(),
+        keys: vec![],
+    }.build_storage().unwrap().0);
+    t.extend(grandpa::GenesisConfig {
+        authorities: (1..=validators).map(|i| (grandpa_pair(i as u8).public(), 1)).collect(),
+    }.build_storage().unwrap().0);
+    t.into()
+}
+
+pub type Offences = Module<Test>;
+pub type Session = session::Module<Test>;
+pub type Grandpa = grandpa::Module<Test>;
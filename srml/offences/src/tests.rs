@@ -0,0 +1,227 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use parity_codec::Encode;
+use primitives::traits::OnFinalize;
+use primitives::Perbill;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok, StorageValue};
+use substrate_primitives::{H256, Pair};
+use dsupport::traits::{OffenceKind, ReportOffence};
+use primitives::testing::Header;
+use crate::{
+    equivocation_slash, GrandpaEquivocationProof, GrandpaJustification, GrandpaSignedPrecommit, GrandpaVote,
+    GrandpaSetId,
+};
+use crate::mock::{grandpa_pair, new_test_ext, offences, Grandpa, Offences, Session, Test};
+
+fn prevote(target: u8) -> GrandpaVote<H256, u64> {
+    GrandpaVote::Prevote { target_hash: H256::repeat_byte(target), target_number: 1 }
+}
+
+/// Validator `voter` casting both votes in round 1 of set 0.
+fn proof(voter: u8, first: GrandpaVote<H256, u64>, second: GrandpaVote<H256, u64>) -> GrandpaEquivocationProof<H256, u64> {
+    let pair = grandpa_pair(voter);
+    let sign = |vote: &GrandpaVote<H256, u64>| pair.sign(&(vote, 1u64, 0u64).encode()[..]);
+    GrandpaEquivocationProof {
+        set_id: 0,
+        round: 1,
+        offender: pair.public(),
+        first: (first.clone(), sign(&first)),
+        second: (second.clone(), sign(&second)),
+    }
+}
+
+#[test]
+fn slash_fractions_should_scale_with_offenders() {
+    assert_eq!(equivocation_slash(1, 30), Perbill::from_percent(1));
+    assert_eq!(equivocation_slash(5, 30), Perbill::from_percent(25));
+    assert_eq!(equivocation_slash(10, 30), Perbill::from_percent(100));
+    assert_eq!(equivocation_slash(1, 0), Perbill::zero());
+}
+
+#[test]
+fn offences_should_be_deduplicated_per_session() {
+    with_externalities(&mut new_test_ext(10), || {
+        let kind = OffenceKind::AuraEquivocation;
+
+        Offences::report_offence(kind, 1);
+        Offences::report_offence(kind, 1);
+        assert_eq!(offences(), vec![(1, kind, Perbill::from_percent(9))]);
+        assert_eq!(Offences::offenders(kind), vec![1]);
+
+        // another kind of offence is punished on its own.
+        Offences::report_offence(OffenceKind::GrandpaEquivocation, 1);
+        assert_eq!(offences().len(), 2);
+
+        Session::rotate_session();
+        Offences::report_offence(kind, 1);
+        assert_eq!(offences().len(), 3);
+        assert_eq!(Offences::offenders(OffenceKind::GrandpaEquivocation), Vec::<u64>::new());
+        assert_eq!(Offences::reported_session(), Session::current_index());
+    });
+}
+
+#[test]
+fn earlier_offenders_should_pay_the_increase() {
+    with_externalities(&mut new_test_ext(10), || {
+        let kind = OffenceKind::AuraEquivocation;
+
+        Offences::report_offence(kind, 1);
+        Offences::report_offence(kind, 2);
+        assert_eq!(offences(), vec![
+            (1, kind, Perbill::from_percent(9)),
+            (1, kind, Perbill::from_percent(27)),
+            (2, kind, Perbill::from_percent(36)),
+        ]);
+        assert_eq!(Offences::slash_fractions(kind), Perbill::from_percent(36));
+    });
+}
+
+/// Finalize block `n` with the GRANDPA module, then the offences module.
+fn finalize(n: u64) {
+    system::Module::<Test>::initialize(&n, &Default::default(), &Default::default(), &Default::default());
+    Grandpa::on_finalize(n);
+    Offences::on_finalize(n);
+}
+
+#[test]
+fn grandpa_set_id_should_follow_authority_changes() {
+    with_externalities(&mut new_test_ext(4), || {
+        finalize(1);
+        finalize(2);
+        assert_eq!(Offences::grandpa_set_id(), 0);
+
+        system::Module::<Test>::initialize(&3, &Default::default(), &Default::default(), &Default::default());
+        assert_ok!(Grandpa::schedule_change(vec![(grandpa_pair(1).public(), 1)], 0, None));
+        Grandpa::on_finalize(3);
+        Offences::on_finalize(3);
+        assert_eq!(Offences::grandpa_set_id(), 1);
+        finalize(4);
+        assert_eq!(Offences::grandpa_set_id(), 1);
+
+        // the same authorities again, enacted two blocks later
+        system::Module::<Test>::initialize(&5, &Default::default(), &Default::default(), &Default::default());
+        assert_ok!(Grandpa::schedule_change(Grandpa::grandpa_authorities(), 2, None));
+        Grandpa::on_finalize(5);
+        Offences::on_finalize(5);
+        assert_eq!(Offences::next_grandpa_change(), Some(7));
+        finalize(6);
+        assert_eq!(Offences::grandpa_set_id(), 1);
+        finalize(7);
+        assert_eq!(Offences::grandpa_set_id(), 2);
+        assert_eq!(Offences::next_grandpa_change(), None);
+    });
+}
+
+#[test]
+fn grandpa_equivocation_should_be_reported() {
+    with_externalities(&mut new_test_ext(4), || {
+        let report = proof(3, prevote(1), prevote(2));
+        assert_eq!(Offences::check_grandpa_equivocation_proof(&report), Ok(3));
+
+        assert_ok!(Offences::report_grandpa_equivocation(system::RawOrigin::None.into(), report.clone()));
+        assert!(Offences::is_reported(OffenceKind::GrandpaEquivocation, &3));
+        assert_eq!(offences(), vec![(3, OffenceKind::GrandpaEquivocation, Perbill::from_rational_approximation(9u64, 16u64))]);
+
+        assert_noop!(
+            Offences::report_grandpa_equivocation(system::RawOrigin::None.into(), report),
+            "offence already reported for this session"
+        );
+    });
+}
+
+#[test]
+fn invalid_grandpa_equivocation_proofs_should_be_rejected() {
+    with_externalities(&mut new_test_ext(4), || {
+        let precommit = GrandpaVote::Precommit { target_hash: H256::repeat_byte(2), target_number: 1 };
+        assert_eq!(
+            Offences::check_grandpa_equivocation_proof(&proof(3, prevote(1), precommit)),
+            Err("votes are of different kinds")
+        );
+        assert_eq!(
+            Offences::check_grandpa_equivocation_proof(&proof(3, prevote(1), prevote(1))),
+            Err("votes are identical")
+        );
+
+        let mut forged = proof(3, prevote(1), prevote(2));
+        forged.second.1 = grandpa_pair(2).sign(&(prevote(2), 1u64, 0u64).encode()[..]);
+        assert_eq!(Offences::check_grandpa_equivocation_proof(&forged), Err("bad signature on second vote"));
+
+        assert_eq!(
+            Offences::check_grandpa_equivocation_proof(&proof(9, prevote(1), prevote(2))),
+            Err("offender is not a GRANDPA authority")
+        );
+
+        // the votes of set 0 are stale once the authorities change
+        GrandpaSetId::put(1);
+        assert_eq!(
+            Offences::check_grandpa_equivocation_proof(&proof(3, prevote(1), prevote(2))),
+            Err("votes are not of the current GRANDPA set")
+        );
+        assert!(offences().is_empty());
+    });
+}
+
+/// A justification of block 2 in round 1 of set 0, precommitted by `voters`.
+fn justification(voters: &[u8]) -> GrandpaJustification<H256, u64, Header> {
+    let target_hash = H256::repeat_byte(2);
+    let precommits = voters.iter().map(|voter| {
+        let pair = grandpa_pair(*voter);
+        let vote = GrandpaVote::Precommit { target_hash, target_number: 2 };
+        GrandpaSignedPrecommit {
+            target_hash,
+            target_number: 2,
+            signature: pair.sign(&(vote, 1u64, 0u64).encode()[..]),
+            id: pair.public(),
+        }
+    }).collect();
+    GrandpaJustification { round: 1, target_hash, target_number: 2, precommits, votes_ancestries: vec![] }
+}
+
+#[test]
+fn grandpa_justifications_should_need_a_supermajority() {
+    with_externalities(&mut new_test_ext(4), || {
+        // block 2 has hash 0x0202..
+        system::Module::<Test>::initialize(&3, &H256::repeat_byte(2), &Default::default(), &Default::default());
+
+        // votes are counted once per voter, and 2 out of 4 are not enough
+        assert_noop!(
+            Offences::note_grandpa_justification(system::RawOrigin::None.into(), justification(&[1, 2, 2])),
+            "not enough precommits"
+        );
+        assert_ok!(Offences::note_grandpa_justification(system::RawOrigin::None.into(), justification(&[1, 2, 4])));
+        assert_eq!(Offences::grandpa_finalized(), 2);
+
+        assert_noop!(
+            Offences::note_grandpa_justification(system::RawOrigin::None.into(), justification(&[1, 2, 3])),
+            "block already finalized"
+        );
+    });
+}
+
+#[test]
+fn invalid_grandpa_justifications_should_be_rejected() {
+    with_externalities(&mut new_test_ext(4), || {
+        system::Module::<Test>::initialize(&3, &H256::repeat_byte(2), &Default::default(), &Default::default());
+
+        let mut fork = justification(&[1, 2, 3]);
+        fork.target_hash = H256::repeat_byte(9);
+        assert_eq!(Offences::check_grandpa_justification(&fork), Err("target is not in the chain"));
+
+        let mut forged = justification(&[1, 2, 3]);
+        forged.precommits[2].signature = grandpa_pair(2).sign(&b"precommit"[..]);
+        assert_eq!(Offences::check_grandpa_justification(&forged), Err("bad precommit signature"));
+
+        assert_eq!(
+            Offences::check_grandpa_justification(&justification(&[1, 2, 9])),
+            Err("precommit of a non authority")
+        );
+
+        // precommits of set 0 are stale once the authorities change
+        GrandpaSetId::put(1);
+        assert_eq!(Offences::check_grandpa_justification(&justification(&[1, 2, 3])), Err("bad precommit signature"));
+        assert_eq!(Offences::grandpa_finalized(), 0);
+    });
+}
@@ -0,0 +1,274 @@
+//! # Randomness Module
+//!
+//! An on-chain randomness beacon for games and other dapps.
+//!
+//! Every block mixes its parent hash with the entropy of `T::BlockEntropy` (the BABE epoch
+//! randomness, built from the VRF outputs of the block authors, in the node runtime) into a
+//! ring of the last `RANDOM_MATERIAL_LEN` seeds. `random(subject)` hashes the subject with
+//! all of them, so a single block author can only influence a small part of the result.
+//!
+//! Other modules read it through `dsupport::traits::Randomness`. That value is known one
+//! block in advance, so anything at stake has to be decided before it can be read: accounts
+//! (and contracts, through `ext_dispatch_call`) `request` randomness to be revealed after a
+//! delay. When the reveal block comes, the value is stored in `Fulfilled`, announced with a
+//! `Fulfilled` event and handed to `T::OnRandomness`.
+//!
+//! `srml-contracts` has no way to extend the functions available to contracts at this
+//! version, so contracts cannot read `Fulfilled` themselves. Instead, a contract requests
+//! randomness with the gas of a callback and, with `ContractCallback`, is called with the
+//! encoded `(RequestId, Hash)` when it is revealed. The callback comes from the keyless
+//! `callback_account`, the only caller a contract should accept it from, and the contract pays
+//! for the gas.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use contracts::Gas;
+use parity_codec::{Decode, Encode};
+use primitives::traits::{Hash, SaturatedConversion, Saturating, StaticLookup, Zero};
+use rstd::{marker::PhantomData, prelude::*};
+use srml_support::{decl_event, decl_module, decl_storage, ensure, StorageMap, StorageValue};
+use srml_support::dispatch::{Dispatchable, Result};
+use srml_support::traits::Get;
+use system::ensure_signed;
+use dsupport::{keyless, traits::Randomness};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of recent block seeds `random` is derived from.
+pub const RANDOM_MATERIAL_LEN: u32 = 81;
+
+/// The name of the keyless account calling back contracts.
+pub const CALLBACK_ACCOUNT_ID: [u8; 8] = *b"evo/rand";
+
+/// Identifier of a randomness request.
+pub type RequestId = u64;
+
+/// Randomness requested by an account, to be revealed at a future block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RandomnessRequest<AccountId, BlockNumber> {
+    /// The account which requested the randomness.
+    pub requester: AccountId,
+    /// What the randomness is for, mixed into the revealed value.
+    pub subject: Vec<u8>,
+    /// The block the randomness is revealed at.
+    pub reveal_at: BlockNumber,
+    /// The gas of the callback of the requester, none if it is not called back.
+    pub callback_gas: Gas,
+}
+
+/// Something that provides the fresh entropy of the current block.
+pub trait BlockEntropy {
+    fn block_entropy() -> Vec<u8>;
+}
+
+impl BlockEntropy for () {
+    fn block_entropy() -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// Something that is told about randomness requests and their reveal.
+pub trait OnRandomness<AccountId, Hash> {
+    /// `requester` requests randomness with `callback_gas` for its callback, which may be refused.
+    fn on_request(requester: &AccountId, callback_gas: Gas) -> Result;
+
+    /// The randomness of request `id` was revealed.
+    fn on_randomness(id: RequestId, requester: &AccountId, random: &Hash, callback_gas: Gas);
+}
+
+impl<AccountId, Hash> OnRandomness<AccountId, Hash> for () {
+    fn on_request(_requester: &AccountId, callback_gas: Gas) -> Result {
+        ensure!(callback_gas == 0, "requesters are not called back");
+        Ok(())
+    }
+
+    fn on_randomness(_id: RequestId, _requester: &AccountId, _random: &Hash, _callback_gas: Gas) {}
+}
+
+/// Calls the requesters which are contracts with the encoded `(RequestId, Hash)` and their
+/// callback gas, from the keyless `callback_account`. The requester pays for the gas when its
+/// request is revealed, and is not called back if it cannot.
+pub struct ContractCallback<T>(PhantomData<T>);
+
+impl<T: Trait + contracts::Trait> OnRandomness<T::AccountId, T::Hash> for ContractCallback<T> {
+    fn on_request(requester: &T::AccountId, callback_gas: Gas) -> Result {
+        if callback_gas != 0 {
+            ensure!(<contracts::ContractInfoOf<T>>::exists(requester), "only contracts are called back");
+        }
+        Ok(())
+    }
+
+    fn on_randomness(id: RequestId, requester: &T::AccountId, random: &T::Hash, callback_gas: Gas) {
+        if callback_gas == 0 {
+            return;
+        }
+
+        let account = <Module<T>>::callback_account();
+        let cost = <contracts::Module<T>>::gas_price().saturating_mul(callback_gas.saturated_into());
+        let call = contracts::Call::<T>::call(
+            T::Lookup::unlookup(requester.clone()),
+            Zero::zero(),
+            callback_gas,
+            (id, random).encode(),
+        );
+        // the randomness stays in `Fulfilled` whether the contract is called back or not
+        let _ = keyless::spend_through::<_, <T as contracts::Trait>::Currency>(&account, requester, cost, || {
+            let _ = call.dispatch(system::RawOrigin::Signed(account.clone()).into());
+        });
+    }
+}
+
+pub trait Trait: system::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// Entropy mixed into the seed of every block.
+    type BlockEntropy: BlockEntropy;
+
+    /// The least number of blocks between a request and its reveal.
+    type MinDelay: Get<Self::BlockNumber>;
+
+    /// The most number of blocks between a request and its reveal.
+    type MaxDelay: Get<Self::BlockNumber>;
+
+    /// The most requests revealed in a single block.
+    type MaxRequestsPerBlock: Get<u32>;
+
+    /// Told about every revealed request.
+    type OnRandomness: OnRandomness<Self::AccountId, Self::Hash>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Randomness {
+        /// Seeds of the last `RANDOM_MATERIAL_LEN` blocks, indexed by block number modulo
+        /// `RANDOM_MATERIAL_LEN`.
+        pub RandomMaterial get(random_material): Vec<T::Hash>;
+
+        /// The identifier of the next request.
+        pub NextRequestId get(next_request_id): RequestId;
+
+        /// Requests waiting to be revealed.
+        pub Requests get(requests): map RequestId => Option<RandomnessRequest<T::AccountId, T::BlockNumber>>;
+
+        /// The requests to reveal at a block.
+        pub Pending get(pending): map T::BlockNumber => Vec<RequestId>;
+
+        /// Revealed requests, with their requester, until the requester forgets them.
+        pub Fulfilled get(fulfilled): map RequestId => Option<(T::AccountId, T::Hash)>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber,
+        <T as system::Trait>::Hash
+    {
+        /// Randomness was requested, to be revealed at the given block.
+        Requested(RequestId, AccountId, BlockNumber),
+        /// The randomness of a request was revealed.
+        Fulfilled(RequestId, AccountId, Hash),
+    }
+);
+
+decl_module! {
+    pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const MinDelay: T::BlockNumber = T::MinDelay::get();
+        const MaxDelay: T::BlockNumber = T::MaxDelay::get();
+        const MaxRequestsPerBlock: u32 = T::MaxRequestsPerBlock::get();
+
+        fn deposit_event<T>() = default;
+
+        fn on_initialize(now: T::BlockNumber) {
+            Self::collect_seed(now);
+            Self::reveal(now);
+        }
+
+        /// Request randomness for `subject`, revealed `delay` blocks from now, and to be called
+        /// back with `callback_gas` then.
+        fn request(origin, subject: Vec<u8>, delay: T::BlockNumber, callback_gas: Gas) {
+            let requester = ensure_signed(origin)?;
+            ensure!(delay >= T::MinDelay::get(), "delay is too short");
+            ensure!(delay <= T::MaxDelay::get(), "delay is too long");
+
+            let reveal_at = <system::Module<T>>::block_number() + delay;
+            let mut pending = Self::pending(reveal_at);
+            ensure!(
+                (pending.len() as u32) < T::MaxRequestsPerBlock::get(),
+                "too many requests revealed at this block"
+            );
+            T::OnRandomness::on_request(&requester, callback_gas)?;
+
+            let id = Self::next_request_id();
+            NextRequestId::put(id + 1);
+            pending.push(id);
+            <Pending<T>>::insert(reveal_at, pending);
+            <Requests<T>>::insert(id, RandomnessRequest {
+                requester: requester.clone(),
+                subject,
+                reveal_at,
+                callback_gas,
+            });
+
+            Self::deposit_event(RawEvent::Requested(id, requester, reveal_at));
+        }
+
+        /// Remove the revealed randomness of a request made by the sender.
+        fn forget(origin, id: RequestId) {
+            let who = ensure_signed(origin)?;
+            let (requester, _) = Self::fulfilled(id).ok_or("request is not fulfilled")?;
+            ensure!(requester == who, "not the requester");
+
+            <Fulfilled<T>>::remove(id);
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// The keyless account contracts are called back from.
+    pub fn callback_account() -> T::AccountId {
+        keyless::account(&CALLBACK_ACCOUNT_ID)
+    }
+
+    /// Random value for `subject`, derived from the seeds of the recent blocks.
+    pub fn random(subject: &[u8]) -> T::Hash {
+        T::Hashing::hash_of(&(subject, Self::random_material()))
+    }
+
+    fn collect_seed(now: T::BlockNumber) {
+        let seed = T::Hashing::hash_of(&(<system::Module<T>>::parent_hash(), T::BlockEntropy::block_entropy()));
+        let index = (now.saturated_into::<u64>() % u64::from(RANDOM_MATERIAL_LEN)) as usize;
+
+        <RandomMaterial<T>>::mutate(|material| {
+            if material.len() < RANDOM_MATERIAL_LEN as usize {
+                material.push(seed);
+            } else {
+                material[index] = seed;
+            }
+        });
+    }
+
+    fn reveal(now: T::BlockNumber) {
+        for id in <Pending<T>>::take(now) {
+            if let Some(request) = <Requests<T>>::take(id) {
+                // the id keeps requests with the same subject apart
+                let random = Self::random(&(id, &request.subject).encode());
+                <Fulfilled<T>>::insert(id, (request.requester.clone(), random));
+
+                T::OnRandomness::on_randomness(id, &request.requester, &random, request.callback_gas);
+                Self::deposit_event(RawEvent::Fulfilled(id, request.requester, random));
+            }
+        }
+    }
+}
+
+impl<T: Trait> Randomness<T::Hash> for Module<T> {
+    fn random(subject: &[u8]) -> T::Hash {
+        Self::random(subject)
+    }
+}
@@ -0,0 +1,94 @@
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use primitives::traits::{BlakeTwo256, IdentityLookup};
+use primitives::testing::Header;
+use substrate_primitives::{H256, Blake2Hasher};
+use runtime_io;
+use srml_support::{ensure, impl_outer_origin, parameter_types};
+use srml_support::dispatch::Result;
+use contracts::Gas;
+use crate::{BlockEntropy, Module, OnRandomness, RequestId, Trait};
+
+pub type AccountId = u64;
+
+/// The only requester which is called back.
+pub const CONTRACT: AccountId = 100;
+
+impl_outer_origin!{
+    pub enum Origin for Test {}
+}
+
+thread_local! {
+    static ENTROPY: RefCell<Vec<u8>> = RefCell::new(vec![]);
+    static REVEALED: RefCell<Vec<(RequestId, AccountId, H256, Gas)>> = RefCell::new(vec![]);
+}
+
+/// Entropy set by the tests with `set_entropy`.
+pub struct TestEntropy;
+impl BlockEntropy for TestEntropy {
+    fn block_entropy() -> Vec<u8> {
+        ENTROPY.with(|e| e.borrow().clone())
+    }
+}
+
+pub fn set_entropy(entropy: Vec<u8>) {
+    ENTROPY.with(|e| *e.borrow_mut() = entropy);
+}
+
+/// Records the revealed requests, calling back `CONTRACT` only.
+pub struct RevealRecorder;
+impl OnRandomness<AccountId, H256> for RevealRecorder {
+    fn on_request(requester: &AccountId, callback_gas: Gas) -> Result {
+        ensure!(callback_gas == 0 || *requester == CONTRACT, "only contracts are called back");
+        Ok(())
+    }
+
+    fn on_randomness(id: RequestId, requester: &AccountId, random: &H256, callback_gas: Gas) {
+        REVEALED.with(|r| r.borrow_mut().push((id, *requester, *random, callback_gas)));
+    }
+}
+
+pub fn revealed() -> Vec<(RequestId, AccountId, H256, Gas)> {
+    REVEALED.with(|r| r.borrow().clone())
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Test;
+
+impl system::Trait for Test {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+}
+
+parameter_types! {
+    pub const MinDelay: u64 = 2;
+    pub const MaxDelay: u64 = 10;
+    pub const MaxRequestsPerBlock: u32 = 2;
+}
+
+impl Trait for Test {
+    type Event = ();
+    type BlockEntropy = TestEntropy;
+    type MinDelay = MinDelay;
+    type MaxDelay = MaxDelay;
+    type MaxRequestsPerBlock = MaxRequestsPerBlock;
+    type OnRandomness = RevealRecorder;
+}
+
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    system::GenesisConfig::default().build_storage::<Test>().unwrap().0.into()
+}
+
+pub type System = system::Module<Test>;
+pub type Randomness = Module<Test>;
@@ -0,0 +1,117 @@
+//! Tests for the module.
+
+#![cfg(test)]
+
+use primitives::traits::OnInitialize;
+use runtime_io::with_externalities;
+use srml_support::{assert_noop, assert_ok};
+use substrate_primitives::H256;
+use crate::RANDOM_MATERIAL_LEN;
+use crate::mock::{new_test_ext, revealed, set_entropy, Origin, Randomness, System, CONTRACT};
+
+/// Initialize the next block, whose parent hash is `parent_hash`.
+fn next_block(parent_hash: u8) {
+    let now = System::block_number() + 1;
+    System::initialize(&now, &H256::repeat_byte(parent_hash), &Default::default(), &Default::default());
+    Randomness::on_initialize(now);
+}
+
+#[test]
+fn random_should_depend_on_subject_and_recent_blocks() {
+    with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        let first = Randomness::random(b"dice");
+        assert_ne!(first, Randomness::random(b"cards"));
+
+        next_block(2);
+        assert_ne!(first, Randomness::random(b"dice"));
+    });
+}
+
+#[test]
+fn block_entropy_should_be_mixed_into_the_seed() {
+    let without = with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        Randomness::random_material()
+    });
+    set_entropy(vec![7]);
+    let with = with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        Randomness::random_material()
+    });
+
+    assert_ne!(without, with);
+}
+
+#[test]
+fn random_material_should_be_a_ring() {
+    with_externalities(&mut new_test_ext(), || {
+        for i in 0..RANDOM_MATERIAL_LEN + 5 {
+            next_block(i as u8);
+        }
+
+        assert_eq!(Randomness::random_material().len(), RANDOM_MATERIAL_LEN as usize);
+    });
+}
+
+#[test]
+fn requests_should_be_revealed_after_the_delay() {
+    with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        assert_ok!(Randomness::request(Origin::signed(10), b"dice".to_vec(), 2, 0));
+        assert_ok!(Randomness::request(Origin::signed(20), b"dice".to_vec(), 3, 0));
+        assert_eq!(Randomness::pending(3), vec![0]);
+
+        next_block(2);
+        assert!(Randomness::fulfilled(0).is_none());
+
+        next_block(3);
+        let (requester, random) = Randomness::fulfilled(0).unwrap();
+        assert_eq!(requester, 10);
+        assert_eq!(revealed(), vec![(0, 10, random, 0)]);
+        assert!(Randomness::requests(0).is_none());
+
+        next_block(4);
+        let (_, other) = Randomness::fulfilled(1).unwrap();
+        assert_ne!(random, other);
+
+        assert_noop!(Randomness::forget(Origin::signed(20), 0), "not the requester");
+        assert_ok!(Randomness::forget(Origin::signed(10), 0));
+        assert!(Randomness::fulfilled(0).is_none());
+    });
+}
+
+#[test]
+fn invalid_requests_should_be_rejected() {
+    with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        assert_noop!(Randomness::request(Origin::signed(10), vec![], 1, 0), "delay is too short");
+        assert_noop!(Randomness::request(Origin::signed(10), vec![], 11, 0), "delay is too long");
+
+        assert_ok!(Randomness::request(Origin::signed(10), vec![], 5, 0));
+        assert_ok!(Randomness::request(Origin::signed(20), vec![], 5, 0));
+        assert_noop!(
+            Randomness::request(Origin::signed(30), vec![], 5, 0),
+            "too many requests revealed at this block"
+        );
+        assert_noop!(Randomness::forget(Origin::signed(10), 0), "request is not fulfilled");
+    });
+}
+
+#[test]
+fn requesters_should_be_called_back_with_their_gas() {
+    with_externalities(&mut new_test_ext(), || {
+        next_block(1);
+        assert_noop!(
+            Randomness::request(Origin::signed(10), b"dice".to_vec(), 2, 1_000),
+            "only contracts are called back"
+        );
+        assert_eq!(Randomness::next_request_id(), 0);
+
+        assert_ok!(Randomness::request(Origin::signed(CONTRACT), b"dice".to_vec(), 2, 1_000));
+        next_block(2);
+        next_block(3);
+        let (_, random) = Randomness::fulfilled(0).unwrap();
+        assert_eq!(revealed(), vec![(0, CONTRACT, random, 1_000)]);
+    });
+}
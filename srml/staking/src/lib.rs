@@ -31,7 +31,7 @@ use srml_support::{
     StorageValue, StorageMap, EnumerableStorageMap, decl_module, decl_event,
     decl_storage, ensure, traits::{
         Currency, OnFreeBalanceZero, LockIdentifier, LockableCurrency,
-        WithdrawReasons, WithdrawReason, OnUnbalanced, Imbalance, Get,
+        WithdrawReasons, OnUnbalanced, Imbalance, Get,
     },
 };
 use session::{OnSessionEnding, SessionIndex};
@@ -42,6 +42,7 @@ use primitives::traits::{Convert,
 #[cfg(feature = "std")]
 use primitives::{Serialize, Deserialize};
 use system::ensure_signed;
+use dsupport::traits::{CurrencyId, MultiCurrency, MultiLockableCurrency, OffenceKind, OnOffenceHandler};
 
 use rstd::convert::TryInto;
 use phragmen::{ACCURACY, elect, equalize, ExtendedBalance};
@@ -130,13 +131,23 @@ impl<
 
     fn is_kton(&self) -> (bool, Option<KtonBalance>) {
         let res = match self {
-            StakingBalance::Ring(_) => (true, None),
-            StakingBalance::Kton(k) => (false, Some(*k)),
+            StakingBalance::Ring(_) => (false, None),
+            StakingBalance::Kton(k) => (true, Some(*k)),
         };
         res
     }
 }
 
+impl<RingBalance, KtonBalance> StakingBalance<RingBalance, KtonBalance> {
+    /// The currency this balance is denominated in.
+    pub fn currency_id(&self) -> CurrencyId {
+        match self {
+            StakingBalance::Ring(_) => CurrencyId::Ring,
+            StakingBalance::Kton(_) => CurrencyId::Kton,
+        }
+    }
+}
+
 impl<
     RingBalance: Default,
     KtonBalance: Default> Default for StakingBalance<RingBalance, KtonBalance> {
@@ -303,10 +314,89 @@ type ExpoMap<T> = BTreeMap<
     Exposures<<T as system::Trait>::AccountId, ExtendedBalance>
 >;
 
+/// What happens to a validator reported offline.
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum OfflinePunishment {
+    /// Only warn, the validator keeps validating.
+    Warn,
+    /// Remove the validator from the candidates without slashing it.
+    Chill,
+    /// Slash the validator (and its nominators) by the ratio, in parts per billion,
+    /// and remove it from the candidates.
+    Slash(u32),
+}
+
+/// Decides the punishment of a validator reported offline.
+pub trait SlashPolicy {
+    /// The punishment of a validator at `slash_count` offline reports, given the
+    /// `OfflineSlash` ratio `base` in parts per billion, `OfflineSlashGrace` and the
+    /// validator's (capped) `unstake_threshold`.
+    fn offline_punishment(base: u32, slash_count: u32, grace: u32, unstake_threshold: u32) -> OfflinePunishment;
+}
+
+/// Slash by `base << unstake_threshold` once the reports exceed `grace + unstake_threshold`.
+pub struct ExponentialSlash;
+
+impl SlashPolicy for ExponentialSlash {
+    fn offline_punishment(base: u32, slash_count: u32, grace: u32, unstake_threshold: u32) -> OfflinePunishment {
+        if slash_count > grace + unstake_threshold {
+            // slash_ratio is ensured to be less than 1 in slash_validator
+            // don't worry here.
+            OfflinePunishment::Slash(base.checked_shl(unstake_threshold).unwrap_or_default())
+        } else {
+            OfflinePunishment::Warn
+        }
+    }
+}
+
+/// Slash by `base` for each report beyond `grace` once the reports exceed
+/// `grace + unstake_threshold`.
+pub struct LinearSlash;
+
+impl SlashPolicy for LinearSlash {
+    fn offline_punishment(base: u32, slash_count: u32, grace: u32, unstake_threshold: u32) -> OfflinePunishment {
+        if slash_count > grace + unstake_threshold {
+            OfflinePunishment::Slash(base.saturating_mul(slash_count - grace))
+        } else {
+            OfflinePunishment::Warn
+        }
+    }
+}
+
+/// Never slash, only chill once the reports exceed `grace + unstake_threshold`.
+pub struct ChillOnly;
+
+impl SlashPolicy for ChillOnly {
+    fn offline_punishment(_base: u32, slash_count: u32, grace: u32, unstake_threshold: u32) -> OfflinePunishment {
+        if slash_count > grace + unstake_threshold {
+            OfflinePunishment::Chill
+        } else {
+            OfflinePunishment::Warn
+        }
+    }
+}
+
+/// Something that knows the last finalized block.
+pub trait FinalizedNumber<BlockNumber> {
+    /// The last finalized block, or `None` if finality is not tracked.
+    fn finalized_number() -> Option<BlockNumber>;
+}
+
+impl<BlockNumber> FinalizedNumber<BlockNumber> for () {
+    fn finalized_number() -> Option<BlockNumber> {
+        None
+    }
+}
 
 pub trait Trait: timestamp::Trait + session::Trait {
     type Ring: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
-    type Kton: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+    type Kton: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber, Balance=RingBalanceOf<Self>>;
+
+    /// RING and KTON behind one interface, which bonds and locks both of them.
+    type Currencies: MultiLockableCurrency<
+        Self::AccountId, CurrencyId, Balance=RingBalanceOf<Self>, Moment=Self::BlockNumber
+    >;
 
     type CurrencyToVote: Convert<KtonBalanceOf<Self>, u64> + Convert<u128, KtonBalanceOf<Self>>;
 
@@ -322,12 +412,27 @@ pub trait Trait: timestamp::Trait + session::Trait {
     type KtonSlash: OnUnbalanced<KtonNegativeImbalanceOf<Self>>;
     type KtonReward: OnUnbalanced<KtonPositiveImbalanceOf<Self>>;
 
+    /// The punishment of validators reported offline.
+    type SlashPolicy: SlashPolicy;
+
     /// Number of sessions per era.
     type SessionsPerEra: Get<SessionIndex>;
 
     /// Number of eras that staked funds must remain bonded for.
     type BondingDuration: Get<EraIndex>;
 
+    /// The last finalized block. A new era is only enacted once the block it became due at
+    /// is finalized.
+    type Finality: FinalizedNumber<Self::BlockNumber>;
+
+    /// Number of blocks a due era waits before it is enacted when `Finality` knows no
+    /// finalized block.
+    type EnactmentDelay: Get<Self::BlockNumber>;
+
+    /// Number of blocks a due era waits for finality before `EraEnactmentStalled` is reported,
+    /// once per era.
+    type MaxEnactmentDelay: Get<Self::BlockNumber>;
+
     // custom
     type Cap: Get<<Self::Ring as Currency<Self::AccountId>>::Balance>;
     type ErasPerEpoch: Get<ErasNums>;
@@ -347,6 +452,9 @@ decl_storage! {
 
 		pub OfflineSlashGrace get(offline_slash_grace) config(): u32;
 
+		/// Share of the stake (own and nominated) slashed for a proven equivocation.
+		pub EquivocationSlash get(equivocation_slash): Perbill = Perbill::from_percent(10);
+
 		pub Invulnerables get(invulnerables) config(): Vec<T::AccountId>;
 
         pub Bonded get(bonded): map T::AccountId => Option<T::AccountId>;
@@ -377,6 +485,12 @@ decl_storage! {
 
 		pub ForceNewEra get(forcing_new_era): bool;
 
+		/// The block a new era became due at, while it waits for that block to be finalized.
+		pub PendingEraSince get(pending_era_since): Option<T::BlockNumber>;
+
+		/// Whether `EraEnactmentStalled` was reported for the pending era.
+		pub PendingEraStalled get(pending_era_stalled): bool;
+
 		pub EpochIndex get(epoch_index): T::BlockNumber = 0.into();
 
 		/// The accumulated reward for the current era. Reset to zero at the beginning of the era
@@ -434,7 +548,11 @@ decl_storage! {
 }
 
 decl_event!(
-    pub enum Event<T> where Balance = RingBalanceOf<T>, <T as system::Trait>::AccountId {
+    pub enum Event<T> where
+        Balance = RingBalanceOf<T>,
+        <T as system::Trait>::AccountId,
+        <T as system::Trait>::BlockNumber
+    {
         /// All validators have been rewarded by the given balance.
 		Reward(Balance),
 		/// One validator (and its nominators) has been given an offline-warning (it is still
@@ -442,8 +560,14 @@ decl_event!(
 		OfflineWarning(AccountId, u32),
 		/// One validator (and its nominators) has been slashed by the given ratio.
 		OfflineSlash(AccountId, u32),
+		/// One validator has been chilled for being offline, without a slash.
+		OfflineChill(AccountId),
+		/// One validator (and its nominators) has been slashed by the given ratio for equivocating.
+		EquivocationSlash(AccountId, u32),
 		/// NodeName changed
 	    NodeNameUpdated,
+		/// The era due at the given block is still not enacted, as the block is not finalized.
+		EraEnactmentStalled(BlockNumber),
     }
 );
 
@@ -455,6 +579,12 @@ decl_module! {
 		/// Number of eras that staked funds must remain bonded for.
 		const BondingDuration: EraIndex = T::BondingDuration::get();
 
+		/// Number of blocks a due era waits before it is enacted when finality is not tracked.
+		const EnactmentDelay: T::BlockNumber = T::EnactmentDelay::get();
+
+		/// Number of blocks a due era waits for finality before it is reported stalled.
+		const MaxEnactmentDelay: T::BlockNumber = T::MaxEnactmentDelay::get();
+
 		fn deposit_event<T>() = default;
 
         fn bond(origin,
@@ -480,16 +610,15 @@ decl_module! {
 			<Payee<T>>::insert(&stash, payee);
 
             let ledger = StakingLedgers {stash: stash.clone(), ..Default::default()};
+			let stash_balance = T::Currencies::free_balance(value.currency_id(), &stash);
 			match value {
 			    StakingBalance::Ring(r) => {
-			        let stash_balance = T::Ring::free_balance(&stash);
 			        let value = r.min(stash_balance);
 			        // increase ring pool
 			        <RingPool<T>>::mutate(|r| *r += value);
 			        Self::bond_helper_in_ring(stash.clone(), controller.clone(), value, promise_month, ledger);
 			    },
 			    StakingBalance::Kton(k) => {
-			        let stash_balance = T::Kton::free_balance(&stash);
 			        let value: KtonBalanceOf<T> = k.min(stash_balance);
 			        // increase kton pool
 			        <KtonPool<T>>::mutate(|k| *k += value);
@@ -506,9 +635,9 @@ decl_module! {
             ensure!( promise_month <= 36, "months at most is 36.");
 			let controller = Self::bonded(&stash).ok_or("not a stash")?;
 			let ledger = Self::ledger(&controller).ok_or("not a controller")?;
+			let stash_balance = T::Currencies::free_balance(value.currency_id(), &stash);
             match value {
                  StakingBalance::Ring(r) => {
-                    let stash_balance = T::Ring::free_balance(&stash);
                     if let Some(extra) = stash_balance.checked_sub(&(ledger.total_ring)) {
                         let extra = extra.min(r);
                         <RingPool<T>>::mutate(|r| *r = r.saturating_add(extra));
@@ -516,7 +645,6 @@ decl_module! {
                     }
                 },
                 StakingBalance::Kton(k) => {
-                    let stash_balance = T::Kton::free_balance(&stash);
                     if let Some(extra) = stash_balance.checked_sub(&(ledger.total_kton)) {
                         let extra = extra.min(k);
                         <KtonPool<T>>::mutate(|r| *r = r.saturating_add(extra));
@@ -639,11 +767,7 @@ decl_module! {
 
                     // check total free balance and locked one
                     // strict on punishing in kton
-                    let is_slashable = T::Kton::free_balance(&stash)
-                        .checked_sub(&kton_slash)
-                        .and_then(|new_balance|
-                            T::Kton::ensure_can_withdraw(&stash, kton_slash, WithdrawReason::Transfer, new_balance).ok())
-                        .is_some();
+                    let is_slashable = T::Currencies::ensure_can_withdraw(CurrencyId::Kton, &stash, kton_slash).is_ok();
 
                     let res = if is_slashable {
                         // update ring
@@ -825,6 +949,11 @@ decl_module! {
 		fn set_invulnerables(validators: Vec<T::AccountId>) {
 			<Invulnerables<T>>::put(validators);
 		}
+
+		/// Set the share of the stake slashed for a proven equivocation.
+		fn set_equivocation_slash(new: Perbill) {
+			EquivocationSlash::put(new);
+		}
     }
 }
 
@@ -891,23 +1020,18 @@ impl<T: Trait> Module<T> {
             StakingBalance<RingBalanceOf<T>, KtonBalanceOf<T>>, T::Moment>,
         staking_balance: StakingBalance<RingBalanceOf<T>, KtonBalanceOf<T>>,
     ) {
-        match staking_balance {
-            StakingBalance::Ring(_r) => T::Ring::set_lock(
-                STAKING_ID,
-                &ledger.stash,
-                ledger.total_ring,
-                T::BlockNumber::max_value(),
-                WithdrawReasons::all(),
-            ),
-
-            StakingBalance::Kton(_k) => T::Kton::set_lock(
-                STAKING_ID,
-                &ledger.stash,
-                ledger.total_kton,
-                T::BlockNumber::max_value(),
-                WithdrawReasons::all(),
-            ),
-        }
+        let locked = match staking_balance {
+            StakingBalance::Ring(_) => ledger.total_ring,
+            StakingBalance::Kton(_) => ledger.total_kton,
+        };
+        T::Currencies::set_lock(
+            staking_balance.currency_id(),
+            STAKING_ID,
+            &ledger.stash,
+            locked,
+            T::BlockNumber::max_value(),
+            WithdrawReasons::all(),
+        );
 
         <Ledger<T>>::insert(controller, ledger);
     }
@@ -931,24 +1055,24 @@ impl<T: Trait> Module<T> {
     }
 
 
+    /// Slash `slash_ratio` of the RING and KTON bonded by `stash`, returning what was slashed.
     fn slash_individual(stash: &T::AccountId, slash_ratio: Perbill,
     ) -> (RingNegativeImbalanceOf<T>, KtonNegativeImbalanceOf<T>) {
         let controller = Self::bonded(stash).unwrap();
         let mut ledger = Self::ledger(&controller).unwrap();
 
-        // slash ring
         let (ring_imbalance, _) = if !ledger.total_ring.is_zero() {
             let slashable_ring = slash_ratio * ledger.total_ring;
-            let value_slashed = Self::slash_helper(&controller, &mut ledger, StakingBalance::Ring(slashable_ring));
-            T::Ring::slash(stash, value_slashed.0)
+            let (value_slashed, _) = Self::slash_helper(&controller, &mut ledger, StakingBalance::Ring(slashable_ring));
+            T::Ring::slash(stash, value_slashed)
         } else {
             (<RingNegativeImbalanceOf<T>>::zero(), Zero::zero())
         };
 
         let (kton_imbalance, _) = if !ledger.total_kton.is_zero() {
             let slashable_kton = slash_ratio * ledger.total_kton;
-            let value_slashed = Self::slash_helper(&controller, &mut ledger, StakingBalance::Kton(slashable_kton));
-            T::Kton::slash(stash, value_slashed.1)
+            let (_, value_slashed) = Self::slash_helper(&controller, &mut ledger, StakingBalance::Kton(slashable_kton));
+            T::Kton::slash(stash, value_slashed)
         } else {
             (<KtonNegativeImbalanceOf<T>>::zero(), Zero::zero())
         };
@@ -1083,12 +1207,40 @@ impl<T: Trait> Module<T> {
     }
 
 
+    /// A forced era is enacted right away, so that governance can replace a validator set
+    /// which no longer finalizes. A due one waits until the block it became due at is final.
     fn new_session(session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+        if ForceNewEra::take() {
+            <PendingEraSince<T>>::kill();
+            PendingEraStalled::kill();
+            return Self::new_era();
+        }
 
-        if ForceNewEra::take() || session_index % T::SessionsPerEra::get() == 0 {
-            Self::new_era()
-        } else {
-            None
+        let now = <system::Module<T>>::block_number();
+        if session_index % T::SessionsPerEra::get() == 0 && Self::pending_era_since().is_none() {
+            <PendingEraSince<T>>::put(now);
+        }
+
+        let since = Self::pending_era_since()?;
+        if Self::is_enactable(since, now) {
+            <PendingEraSince<T>>::kill();
+            PendingEraStalled::kill();
+            return Self::new_era();
+        }
+
+        if now >= since + T::MaxEnactmentDelay::get() && !Self::pending_era_stalled() {
+            PendingEraStalled::put(true);
+            Self::deposit_event(RawEvent::EraEnactmentStalled(since));
+        }
+
+        None
+    }
+
+    /// Whether the era due at block `since` can be enacted at block `now`.
+    fn is_enactable(since: T::BlockNumber, now: T::BlockNumber) -> bool {
+        match T::Finality::finalized_number() {
+            Some(finalized) => finalized >= since,
+            None => now >= since + T::EnactmentDelay::get(),
         }
     }
 
@@ -1333,22 +1485,28 @@ impl<T: Trait> Module<T> {
             if <Validators<T>>::exists(&stash) {
                 let prefs = Self::validators(&stash);
                 let unstake_threshold = prefs.unstake_threshold.min(MAX_UNSTAKE_THRESHOLD);
-                let max_slashes = grace + unstake_threshold;
-
-                let event = if new_slash_count > max_slashes {
-                    let offline_slash_ratio_base = *Self::offline_slash().encode_as();
-                    // slash_ratio is ensured to be less than 1 in slash_validator
-                    // don't worry here.
-                    let slash_ratio_in_u32 = offline_slash_ratio_base
-                        .checked_shl(unstake_threshold)
-                        .unwrap_or_default();
-                    Self::slash_validator(&stash, slash_ratio_in_u32);
-                    <Validators<T>>::remove(&stash);
-                    let _ = <session::Module<T>>::disable(&controller);
-
-                    RawEvent::OfflineSlash(stash.clone(), slash_ratio_in_u32)
-                } else {
-                    RawEvent::OfflineWarning(stash.clone(), slash_count)
+                let punishment = T::SlashPolicy::offline_punishment(
+                    *Self::offline_slash().encode_as(),
+                    new_slash_count,
+                    grace,
+                    unstake_threshold,
+                );
+
+                let event = match punishment {
+                    OfflinePunishment::Warn => RawEvent::OfflineWarning(stash.clone(), slash_count),
+                    OfflinePunishment::Chill => {
+                        <Validators<T>>::remove(&stash);
+                        let _ = <session::Module<T>>::disable(&controller);
+
+                        RawEvent::OfflineChill(stash.clone())
+                    }
+                    OfflinePunishment::Slash(slash_ratio_in_u32) => {
+                        Self::slash_validator(&stash, slash_ratio_in_u32);
+                        <Validators<T>>::remove(&stash);
+                        let _ = <session::Module<T>>::disable(&controller);
+
+                        RawEvent::OfflineSlash(stash.clone(), slash_ratio_in_u32)
+                    }
                 };
 
                 Self::deposit_event(event);
@@ -1356,6 +1514,29 @@ impl<T: Trait> Module<T> {
         }
     }
 
+    /// Call when a validator has been proven to equivocate, e.g. authored two blocks
+    /// in one slot. The validator and its nominators are slashed by `EquivocationSlash`
+    /// of their stake and the validator is chilled.
+    ///
+    /// NOTE: This is called with the controller (not the stash) account id.
+    pub fn on_equivocation(controller: T::AccountId) {
+        if let Some(l) = Self::ledger(&controller) {
+            let stash = l.stash;
+
+            // Early exit if validator is invulnerable.
+            if Self::invulnerables().contains(&stash) {
+                return;
+            }
+
+            let slash_ratio_in_u32 = *Self::equivocation_slash().encode_as();
+            Self::slash_validator(&stash, slash_ratio_in_u32);
+            <Validators<T>>::remove(&stash);
+            let _ = <session::Module<T>>::disable(&controller);
+
+            Self::deposit_event(RawEvent::EquivocationSlash(stash, slash_ratio_in_u32));
+        }
+    }
+
     // total_kton * kton_vote_weight / ACCURACY = total_ring
     // it ensures that when rewarding validators
     // reward to ring_pool will be the same with the
@@ -1381,6 +1562,35 @@ impl<T: Trait> OnSessionEnding<T::AccountId> for Module<T> {
 }
 
 
+/// Punishes offences collected by the offences module.
+///
+/// NOTE: This is called with the controller (not the stash) account id.
+impl<T: Trait> OnOffenceHandler<T::AccountId> for Module<T> {
+    fn on_offence(controller: &T::AccountId, _kind: OffenceKind, slash: Perbill) {
+        if let Some(l) = Self::ledger(controller) {
+            let stash = l.stash;
+
+            // Early exit if validator is invulnerable.
+            if Self::invulnerables().contains(&stash) {
+                return;
+            }
+
+            let slash_ratio_in_u32 = *slash.encode_as();
+            // too few concurrent offenders to punish anyone (yet).
+            if slash_ratio_in_u32 == 0 {
+                return;
+            }
+
+            Self::slash_validator(&stash, slash_ratio_in_u32);
+            <Validators<T>>::remove(&stash);
+            let _ = <session::Module<T>>::disable(controller);
+
+            Self::deposit_event(RawEvent::EquivocationSlash(stash, slash_ratio_in_u32));
+        }
+    }
+}
+
+
 impl<T: Trait> OnFreeBalanceZero<T::AccountId> for Module<T> {
     fn on_free_balance_zero(stash: &T::AccountId) {
         if let Some(controller) = <Bonded<T>>::take(stash) {
@@ -8,7 +8,8 @@ use substrate_primitives::{H256, Blake2Hasher};
 use runtime_io;
 use srml_support::{assert_ok, impl_outer_origin, parameter_types, EnumerableStorageMap};
 use srml_support::traits::{Currency, Get};
-use crate::{EraIndex, ErasNums, GenesisConfig, Module, Trait, StakerStatus,
+use dsupport::traits::RingKtonCurrency;
+use crate::{EraIndex, ErasNums, FinalizedNumber, GenesisConfig, Module, Trait, StakerStatus,
             ValidatorPrefs, RewardDestination, Nominators, StakingBalance
 };
 
@@ -33,6 +34,7 @@ impl Convert<u128, u64> for CurrencyToVoteHandler {
 thread_local! {
 	static SESSION: RefCell<(Vec<AccountId>, HashSet<AccountId>)> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
+	static FINALIZED: RefCell<Option<BlockNumber>> = RefCell::new(None);
 }
 
 pub struct TestSessionHandler;
@@ -57,6 +59,17 @@ pub fn is_disabled(validator: AccountId) -> bool {
     SESSION.with(|d| d.borrow().1.contains(&validator))
 }
 
+pub struct TestFinality;
+impl FinalizedNumber<BlockNumber> for TestFinality {
+    fn finalized_number() -> Option<BlockNumber> {
+        FINALIZED.with(|f| *f.borrow())
+    }
+}
+
+pub fn set_finalized(finalized: Option<BlockNumber>) {
+    FINALIZED.with(|f| *f.borrow_mut() = finalized);
+}
+
 pub struct ExistentialDeposit;
 impl Get<u64> for ExistentialDeposit {
     fn get() -> u64 {
@@ -121,11 +134,21 @@ impl timestamp::Trait for Test {
 }
 
 
+parameter_types! {
+	pub const MaxLocks: u32 = 16;
+	pub const MaxLockSweeps: u32 = 8;
+	pub const MaxVestingSchedules: u32 = 8;
+}
+
 impl kton::Trait for Test {
     type Balance = Balance;
     type Event = ();
     type OnMinted = ();
     type OnRemoval = ();
+    type OnAccountBalanceChanged = ();
+    type MaxVestingSchedules = MaxVestingSchedules;
+    type MaxLocks = MaxLocks;
+    type MaxLockSweeps = MaxLockSweeps;
 }
 
 
@@ -133,6 +156,8 @@ parameter_types! {
 	pub const SessionsPerEra: session::SessionIndex = 3;
 	pub const BondingDuration: EraIndex = 3;
 	pub const ErasPerEpoch: ErasNums = 10;
+	pub const EnactmentDelay: BlockNumber = 0;
+	pub const MaxEnactmentDelay: BlockNumber = 5;
 }
 
 pub const COIN: u64 = 1_000_000_000;
@@ -145,14 +170,19 @@ parameter_types! {
 impl Trait for Test {
     type Ring = Ring;
     type Kton = Kton;
+    type Currencies = RingKtonCurrency<Ring, Kton>;
     type CurrencyToVote = CurrencyToVoteHandler;
     type Event = ();
     type RingSlash = ();
     type RingReward = ();
     type KtonSlash = ();
     type KtonReward = ();
+    type SlashPolicy = crate::ExponentialSlash;
     type SessionsPerEra = SessionsPerEra;
     type BondingDuration = BondingDuration;
+    type Finality = TestFinality;
+    type EnactmentDelay = EnactmentDelay;
+    type MaxEnactmentDelay = MaxEnactmentDelay;
     // customed
     type Cap = CAP;
     type ErasPerEpoch = ErasPerEpoch;
@@ -2,6 +2,7 @@ use primitives::traits::OnInitialize;
 use runtime_io::with_externalities;
 use srml_support::{assert_eq_uvec, assert_err, assert_noop, assert_ok, EnumerableStorageMap};
 use srml_support::traits::{Currency, ReservableCurrency, WithdrawReason, WithdrawReasons};
+use dsupport::traits::RingKtonCurrency;
 use mock::*;
 use super::*;
 use super::MONTH_IN_SECONDS;
@@ -85,6 +86,32 @@ fn normal_kton_should_work() {
     });
 }
 
+#[test]
+fn kton_should_be_bonded_from_an_account_at_max_locks() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 10 * COIN);
+        for id in 0..MaxLocks::get() as u8 {
+            Kton::set_lock([id; 8], &1001, COIN, u64::max_value(), WithdrawReasons::all());
+        }
+        assert_noop!(Kton::ensure_can_lock(&1001, STAKING_ID), "too many locks");
+
+        assert_ok!(Staking::bond(Origin::signed(1001), 1000, StakingBalance::Kton(10 * COIN), RewardDestination::Stash, 0));
+        assert_eq!(Kton::locks(&1001).len(), MaxLocks::get() as usize + 1);
+        assert!(Kton::locks(&1001).contains(&kton::BalanceLock {
+            id: STAKING_ID,
+            amount: 10 * COIN,
+            until: u64::max_value(),
+            reasons: WithdrawReasons::all(),
+        }));
+        assert_err!(
+            Kton::transfer(Origin::signed(1001), 1002, COIN),
+            "account liquidity restrictions prevent withdrawal"
+        );
+    });
+}
+
 #[test]
 fn time_deposit_ring_unbond_and_withdraw_should_work() {
     with_externalities(&mut ExtBuilder::default()
@@ -156,6 +183,59 @@ fn time_deposit_ring_unbond_and_withdraw_should_work() {
     });
 }
 
+#[test]
+fn currencies_should_bond_and_slash_ring_and_kton() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(10).build(), || {
+
+        assert_noop!(
+            RingKtonCurrency::<Ring, Kton>::deposit(CurrencyId::Ring, &1001, 5),
+            "deposit below the existential deposit"
+        );
+        assert_eq!(Ring::free_balance(&1001), 0);
+        assert_ok!(RingKtonCurrency::<Ring, Kton>::deposit(CurrencyId::Ring, &1001, 10 * COIN));
+        assert_ok!(RingKtonCurrency::<Ring, Kton>::deposit(CurrencyId::Kton, &1001, 10 * COIN));
+
+        assert_ok!(Staking::bond(Origin::signed(1001), 1000, StakingBalance::Ring(10 * COIN), RewardDestination::Stash, 0));
+        assert_ok!(Staking::bond_extra(Origin::signed(1001), StakingBalance::Kton(10 * COIN), 0));
+        assert_eq!(Ring::locks(&1001)[0].amount, 10 * COIN);
+        assert_eq!(Kton::locks(&1001)[0].amount, 10 * COIN);
+
+        Staking::slash_validator(&1001, Perbill::from_percent(10).into_parts());
+        assert_eq!(Ring::free_balance(&1001), 9 * COIN);
+        assert_eq!(Kton::free_balance(&1001), 9 * COIN);
+        assert_eq!(Staking::ledger(&1000).map(|l| (l.total_ring, l.total_kton)), Some((9 * COIN, 9 * COIN)));
+    });
+}
+
+#[test]
+fn normal_kton_unbond_and_withdraw_should_work() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        Kton::deposit_creating(&1001, 10 * COIN);
+        assert_ok!(Staking::bond(Origin::signed(1001), 1000, StakingBalance::Kton(10 * COIN), RewardDestination::Stash, 0));
+        assert_ok!(Staking::unbond(Origin::signed(1000), StakingBalance::Kton(10 * COIN)));
+        assert_eq!(StakingBalance::<Balance, Balance>::Kton(COIN).currency_id(), CurrencyId::Kton);
+
+        start_era(3);
+
+        assert_ok!(Staking::withdraw_unbonded(Origin::signed(1000)));
+        assert_eq!(Staking::ledger(&1000), Some(StakingLedgers {
+            stash: 1001,
+            total_ring: 0,
+            total_deposit_ring: 0,
+            active_deposit_ring: 0,
+            active_ring: 0,
+            total_kton: 0,
+            active_kton: 0,
+            deposit_items: vec![],
+            unlocking: vec![]
+        }));
+        assert_eq!(Kton::locks(&1001), vec![kton::BalanceLock {id: STAKING_ID, amount: 0, until: u64::max_value(), reasons: WithdrawReasons::all()}]);
+    });
+}
+
 #[test]
 fn normal_unbond_should_work() {
     with_externalities(&mut ExtBuilder::default()
@@ -400,4 +480,102 @@ fn slash_should_work() {
         assert_eq!(Staking::ledger(&1000).unwrap().active_ring, 495 * COIN / 10);
         assert_eq!(Ring::free_balance(&1001), 995 * COIN / 10);
     });
-}
\ No newline at end of file
+}
+#[test]
+fn equivocation_should_slash_and_chill() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        assert_eq!(Staking::equivocation_slash(), Perbill::from_percent(10));
+        Staking::on_equivocation(10);
+        assert_eq!(Staking::ledger(&10).unwrap().active_ring, 90 * COIN);
+        assert!(!<Validators<Test>>::exists(&11));
+        assert!(is_disabled(10));
+
+        // invulnerables are left alone
+        assert_ok!(Staking::set_invulnerables(vec![21]));
+        let active_ring = Staking::ledger(&20).unwrap().active_ring;
+        Staking::on_equivocation(20);
+        assert_eq!(Staking::ledger(&20).unwrap().active_ring, active_ring);
+        assert!(<Validators<Test>>::exists(&21));
+    });
+}
+
+/// The punishments `P` decides for a validator reported offline three times, with an
+/// `OfflineSlash` of 1000 parts per billion, no grace and an unstake threshold of 1.
+fn punishments_of<P: SlashPolicy>() -> Vec<OfflinePunishment> {
+    (1..=3).map(|slash_count| P::offline_punishment(1000, slash_count, 0, 1)).collect()
+}
+
+#[test]
+fn slash_policies_should_grade_punishments() {
+    use OfflinePunishment::*;
+
+    assert_eq!(punishments_of::<ExponentialSlash>(), vec![Warn, Slash(2000), Slash(2000)]);
+    assert_eq!(punishments_of::<LinearSlash>(), vec![Warn, Slash(2000), Slash(3000)]);
+    assert_eq!(punishments_of::<ChillOnly>(), vec![Warn, Chill, Chill]);
+}
+
+#[test]
+fn offences_should_slash_by_the_given_fraction() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        // nothing happens while the fraction is zero
+        Staking::on_offence(&10, OffenceKind::AuraEquivocation, Perbill::zero());
+        assert_eq!(Staking::ledger(&10).unwrap().active_ring, 100 * COIN);
+        assert!(<Validators<Test>>::exists(&11));
+
+        Staking::on_offence(&10, OffenceKind::GrandpaEquivocation, Perbill::from_percent(20));
+        assert_eq!(Staking::ledger(&10).unwrap().active_ring, 80 * COIN);
+        assert!(!<Validators<Test>>::exists(&11));
+        assert!(is_disabled(10));
+    });
+}
+
+#[test]
+fn new_era_should_wait_for_finality() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        // the era due at the end of session 2 (block 3) waits for block 3 to be finalized
+        set_finalized(Some(0));
+        start_session(3);
+        assert_eq!(Staking::current_era(), 0);
+        assert_eq!(Staking::pending_era_since(), Some(3));
+
+        set_finalized(Some(3));
+        start_session(4);
+        assert_eq!(Staking::current_era(), 1);
+        assert_eq!(Staking::pending_era_since(), None);
+
+        // a forced era does not wait
+        set_finalized(Some(0));
+        Staking::force_new_era();
+        start_session(5);
+        assert_eq!(Staking::current_era(), 2);
+        assert_eq!(Staking::pending_era_since(), None);
+    });
+}
+
+#[test]
+fn stalled_era_should_be_reported_once() {
+    with_externalities(&mut ExtBuilder::default()
+        .existential_deposit(0).build(), || {
+
+        // the era due at block 3 is reported stalled at block 8, 5 blocks later
+        set_finalized(Some(0));
+        start_session(7);
+        assert_eq!(Staking::pending_era_since(), Some(3));
+        assert!(!Staking::pending_era_stalled());
+        System::set_block_number(8);
+        Session::on_initialize(8);
+        assert!(Staking::pending_era_stalled());
+
+        set_finalized(Some(3));
+        System::set_block_number(9);
+        Session::on_initialize(9);
+        assert_eq!(Staking::current_era(), 1);
+        assert!(!Staking::pending_era_stalled());
+    });
+}
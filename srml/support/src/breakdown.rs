@@ -0,0 +1,80 @@
+//! What an account holds of a currency, split the way wallets show it.
+
+use codec::{Decode, Encode};
+use rstd::prelude::*;
+use sr_primitives::traits::{Saturating, SimpleArithmetic};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use srml_support::traits::{LockIdentifier, WithdrawReason, WithdrawReasons};
+
+/// A lock on the free balance of an account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct LockBreakdown<Balance, BlockNumber> {
+    pub id: LockIdentifier,
+    pub amount: Balance,
+    pub until: BlockNumber,
+    /// Bit set of the `WithdrawReason`s covered by the lock.
+    pub reasons: u8,
+}
+
+/// Free, reserved, locked and spendable balance of an account in one currency.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct CurrencyBreakdown<Balance, BlockNumber> {
+    pub free: Balance,
+    pub reserved: Balance,
+    /// Locks which have not expired yet.
+    pub locks: Vec<LockBreakdown<Balance, BlockNumber>>,
+    /// Amount still held back by vesting.
+    pub vesting: Balance,
+    /// Amount which can be transferred right now.
+    pub transferable: Balance,
+}
+
+/// Breakdown of both RING and KTON of an account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct BalancesBreakdown<Balance, BlockNumber> {
+    pub ring: CurrencyBreakdown<Balance, BlockNumber>,
+    pub kton: CurrencyBreakdown<Balance, BlockNumber>,
+}
+
+/// Encode `reasons` as the sum of the `WithdrawReason` flags it contains.
+pub fn reasons_bits(reasons: WithdrawReasons) -> u8 {
+    [
+        WithdrawReason::TransactionPayment,
+        WithdrawReason::Transfer,
+        WithdrawReason::Reserve,
+        WithdrawReason::Fee,
+    ].iter()
+        .filter(|r| reasons.contains(**r))
+        .fold(0, |acc, r| acc | *r as u8)
+}
+
+impl<Balance, BlockNumber> CurrencyBreakdown<Balance, BlockNumber>
+    where
+        Balance: SimpleArithmetic + Copy,
+        BlockNumber: PartialOrd + Copy,
+{
+    /// Build the breakdown from the raw account state, mirroring the checks of
+    /// `ensure_can_withdraw` for a transfer. Locks expired at `now` are left out.
+    pub fn new(
+        free: Balance,
+        reserved: Balance,
+        locks: Vec<(LockIdentifier, Balance, BlockNumber, WithdrawReasons)>,
+        vesting: Balance,
+        now: BlockNumber,
+    ) -> Self {
+        let locks = locks.into_iter()
+            .filter(|&(_, _, until, _)| until > now)
+            .map(|(id, amount, until, reasons)| LockBreakdown { id, amount, until, reasons: reasons_bits(reasons) })
+            .collect::<Vec<_>>();
+
+        let frozen = locks.iter()
+            .filter(|l| l.reasons & WithdrawReason::Transfer as u8 != 0)
+            .fold(vesting, |acc, l| acc.max(l.amount));
+
+        CurrencyBreakdown { free, reserved, locks, vesting, transferable: free.saturating_sub(frozen) }
+    }
+}
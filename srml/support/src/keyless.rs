@@ -0,0 +1,36 @@
+//! Accounts without a key, through which modules act on their own behalf.
+
+use codec::Decode;
+use sr_primitives::traits::Saturating;
+use srml_support::dispatch::Result;
+use srml_support::traits::{Currency, ExistenceRequirement, WithdrawReason};
+
+/// The account of the module named `id`, which nobody has the key of.
+pub fn account<AccountId: Decode + Default>(id: &[u8; 8]) -> AccountId {
+    let mut encoded = [0u8; 32];
+    encoded[..8].copy_from_slice(id);
+    AccountId::decode(&mut &encoded[..]).unwrap_or_default()
+}
+
+/// Lend `amount` of `payer` to the keyless `account` while `spend` spends from it, then return
+/// what is left of it to `payer`.
+///
+/// The existential deposit is lent as well, so that spending everything does not reap `account`.
+pub fn spend_through<AccountId, C: Currency<AccountId>>(
+    account: &AccountId,
+    payer: &AccountId,
+    amount: C::Balance,
+    spend: impl FnOnce(),
+) -> Result {
+    let before = C::free_balance(account);
+    let lent = amount.saturating_add(C::minimum_balance());
+    let imbalance = C::withdraw(payer, lent, WithdrawReason::Transfer, ExistenceRequirement::KeepAlive)?;
+    C::resolve_creating(account, imbalance);
+
+    spend();
+
+    let left = C::free_balance(account).saturating_sub(before);
+    let imbalance = C::withdraw(account, left, WithdrawReason::Transfer, ExistenceRequirement::AllowDeath)?;
+    C::resolve_creating(payer, imbalance);
+    Ok(())
+}
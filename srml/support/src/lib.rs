@@ -1,4 +1,6 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod breakdown;
+pub mod keyless;
 pub mod traits;
\ No newline at end of file
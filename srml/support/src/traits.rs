@@ -1,3 +1,15 @@
+use codec::{Codec, Decode, Encode};
+use rstd::marker::PhantomData;
+use sr_primitives::Perbill;
+use sr_primitives::traits::{CheckedSub, MaybeSerializeDebug, SimpleArithmetic};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use srml_support::dispatch::Result;
+use srml_support::ensure;
+use srml_support::traits::{
+    Currency, ExistenceRequirement, Imbalance, LockIdentifier, LockableCurrency, WithdrawReason, WithdrawReasons,
+};
+
 //pub trait LockRate {
 //    //TODO： ugly to use u64, ready for hacking
 //    //    type Balance: SimpleArithmetic + As<usize> + As<u64> + Codec + Copy + MaybeSerializeDebug + Default;
@@ -11,11 +23,270 @@
 //    fn on_dilution(treasury_income: Balance);
 //}
 
+/// Called when new units of a currency are issued.
 pub trait OnMinted<Balance> {
    fn on_minted(value: Balance);
 }
 
+/// Called when the total balance (free and reserved) of an account changes.
 pub trait OnAccountBalanceChanged<AccountId, Balance> {
     fn on_changed(who: &AccountId, old: Balance, new: Balance);
 }
 
+impl<Balance> OnMinted<Balance> for () {
+    fn on_minted(_value: Balance) {}
+}
+
+impl<AccountId, Balance> OnAccountBalanceChanged<AccountId, Balance> for () {
+    fn on_changed(_who: &AccountId, _old: Balance, _new: Balance) {}
+}
+
+// implement the hooks for tuples so that several listeners can subscribe,
+// each of them is called in order.
+macro_rules! impl_hooks_for_tuples {
+    () => {};
+    ($first:ident $(, $rest:ident)*) => {
+        impl<Balance: Copy, $first: OnMinted<Balance>, $($rest: OnMinted<Balance>),*>
+            OnMinted<Balance> for ($first, $($rest,)*)
+        {
+            fn on_minted(value: Balance) {
+                $first::on_minted(value);
+                $($rest::on_minted(value);)*
+            }
+        }
+
+        impl<
+            AccountId,
+            Balance: Copy,
+            $first: OnAccountBalanceChanged<AccountId, Balance>,
+            $($rest: OnAccountBalanceChanged<AccountId, Balance>),*
+        > OnAccountBalanceChanged<AccountId, Balance> for ($first, $($rest,)*) {
+            fn on_changed(who: &AccountId, old: Balance, new: Balance) {
+                $first::on_changed(who, old, new);
+                $($rest::on_changed(who, old, new);)*
+            }
+        }
+
+        impl_hooks_for_tuples!($($rest),*);
+    };
+}
+
+impl_hooks_for_tuples!(A, B, C, D, E, F, G, H);
+
+
+/// Identifier of the currencies native to Darwinia.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum CurrencyId {
+    Ring,
+    Kton,
+}
+
+impl Default for CurrencyId {
+    fn default() -> Self {
+        CurrencyId::Ring
+    }
+}
+
+/// Abstraction over several fungible currencies sharing one balance type,
+/// each of them picked by `CurrencyId`.
+pub trait MultiCurrency<AccountId, CurrencyId> {
+    type Balance: SimpleArithmetic + Codec + Copy + MaybeSerializeDebug + Default;
+
+    fn total_issuance(currency_id: CurrencyId) -> Self::Balance;
+
+    fn free_balance(currency_id: CurrencyId, who: &AccountId) -> Self::Balance;
+
+    fn total_balance(currency_id: CurrencyId, who: &AccountId) -> Self::Balance;
+
+    /// Check that `amount` could be withdrawn from the free balance of `who` for a transfer.
+    fn ensure_can_withdraw(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result;
+
+    fn transfer(currency_id: CurrencyId, from: &AccountId, to: &AccountId, amount: Self::Balance) -> Result;
+
+    /// Add `amount` to the free balance of `who`, increasing the total issuance, or fail
+    /// without depositing anything if it is too low to create the account.
+    fn deposit(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result;
+
+    /// Remove `amount` from the free balance of `who`, reducing the total issuance.
+    fn withdraw(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result;
+
+    /// Slash `amount` from `who`, returning the part which could not be slashed.
+    fn slash(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Self::Balance;
+}
+
+/// A `MultiCurrency` whose balances can be locked.
+pub trait MultiLockableCurrency<AccountId, CurrencyId>: MultiCurrency<AccountId, CurrencyId> {
+    type Moment;
+
+    fn set_lock(
+        currency_id: CurrencyId,
+        lock_id: LockIdentifier,
+        who: &AccountId,
+        amount: Self::Balance,
+        until: Self::Moment,
+        reasons: WithdrawReasons,
+    );
+
+    fn remove_lock(currency_id: CurrencyId, lock_id: LockIdentifier, who: &AccountId);
+}
+
+/// `MultiCurrency` over RING and KTON, dispatching on `CurrencyId` to
+/// the `Ring` (balances) or the `Kton` module.
+pub struct RingKtonCurrency<Ring, Kton>(PhantomData<(Ring, Kton)>);
+
+impl<AccountId, Ring, Kton> MultiCurrency<AccountId, CurrencyId> for RingKtonCurrency<Ring, Kton>
+    where
+        Ring: Currency<AccountId>,
+        Kton: Currency<AccountId, Balance=Ring::Balance>,
+        Ring::Balance: SimpleArithmetic + Codec + Copy + MaybeSerializeDebug + Default,
+{
+    type Balance = Ring::Balance;
+
+    fn total_issuance(currency_id: CurrencyId) -> Self::Balance {
+        match currency_id {
+            CurrencyId::Ring => Ring::total_issuance(),
+            CurrencyId::Kton => Kton::total_issuance(),
+        }
+    }
+
+    fn free_balance(currency_id: CurrencyId, who: &AccountId) -> Self::Balance {
+        match currency_id {
+            CurrencyId::Ring => Ring::free_balance(who),
+            CurrencyId::Kton => Kton::free_balance(who),
+        }
+    }
+
+    fn total_balance(currency_id: CurrencyId, who: &AccountId) -> Self::Balance {
+        match currency_id {
+            CurrencyId::Ring => Ring::total_balance(who),
+            CurrencyId::Kton => Kton::total_balance(who),
+        }
+    }
+
+    fn ensure_can_withdraw(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result {
+        let new_balance = Self::free_balance(currency_id, who)
+            .checked_sub(&amount)
+            .ok_or("too few free funds in account")?;
+        match currency_id {
+            CurrencyId::Ring => Ring::ensure_can_withdraw(who, amount, WithdrawReason::Transfer, new_balance),
+            CurrencyId::Kton => Kton::ensure_can_withdraw(who, amount, WithdrawReason::Transfer, new_balance),
+        }
+    }
+
+    fn transfer(currency_id: CurrencyId, from: &AccountId, to: &AccountId, amount: Self::Balance) -> Result {
+        match currency_id {
+            CurrencyId::Ring => Ring::transfer(from, to, amount),
+            CurrencyId::Kton => Kton::transfer(from, to, amount),
+        }
+    }
+
+    fn deposit(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result {
+        // the positive imbalance squares up the total issuance when dropped
+        let deposited = match currency_id {
+            CurrencyId::Ring => Ring::deposit_creating(who, amount).peek(),
+            CurrencyId::Kton => Kton::deposit_creating(who, amount).peek(),
+        };
+        // a new account is not created below the existential deposit
+        ensure!(deposited == amount, "deposit below the existential deposit");
+        Ok(())
+    }
+
+    fn withdraw(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Result {
+        // the negative imbalance squares up the total issuance when dropped
+        match currency_id {
+            CurrencyId::Ring => Ring::withdraw(who, amount, WithdrawReason::Transfer, ExistenceRequirement::AllowDeath)
+                .map(|_| ()),
+            CurrencyId::Kton => Kton::withdraw(who, amount, WithdrawReason::Transfer, ExistenceRequirement::AllowDeath)
+                .map(|_| ()),
+        }
+    }
+
+    fn slash(currency_id: CurrencyId, who: &AccountId, amount: Self::Balance) -> Self::Balance {
+        match currency_id {
+            CurrencyId::Ring => Ring::slash(who, amount).1,
+            CurrencyId::Kton => Kton::slash(who, amount).1,
+        }
+    }
+}
+
+impl<AccountId, Moment, Ring, Kton> MultiLockableCurrency<AccountId, CurrencyId> for RingKtonCurrency<Ring, Kton>
+    where
+        Ring: LockableCurrency<AccountId, Moment=Moment>,
+        Kton: LockableCurrency<AccountId, Moment=Moment, Balance=Ring::Balance>,
+        Ring::Balance: SimpleArithmetic + Codec + Copy + MaybeSerializeDebug + Default,
+{
+    type Moment = Moment;
+
+    fn set_lock(
+        currency_id: CurrencyId,
+        lock_id: LockIdentifier,
+        who: &AccountId,
+        amount: Self::Balance,
+        until: Self::Moment,
+        reasons: WithdrawReasons,
+    ) {
+        match currency_id {
+            CurrencyId::Ring => Ring::set_lock(lock_id, who, amount, until, reasons),
+            CurrencyId::Kton => Kton::set_lock(lock_id, who, amount, until, reasons),
+        }
+    }
+
+    fn remove_lock(currency_id: CurrencyId, lock_id: LockIdentifier, who: &AccountId) {
+        match currency_id {
+            CurrencyId::Ring => Ring::remove_lock(lock_id, who),
+            CurrencyId::Kton => Kton::remove_lock(lock_id, who),
+        }
+    }
+}
+
+
+/// The kinds of validator misbehaviour that are reported and punished.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum OffenceKind {
+    /// An Aura authority sealed two different blocks in the same slot.
+    AuraEquivocation,
+    /// A GRANDPA voter cast two different votes in the same round.
+    GrandpaEquivocation,
+}
+
+impl OffenceKind {
+    /// All kinds of offences.
+    pub const ALL: [OffenceKind; 2] = [
+        OffenceKind::AuraEquivocation,
+        OffenceKind::GrandpaEquivocation,
+    ];
+}
+
+/// Something that collects offences of validators, identified by their session account.
+pub trait ReportOffence<AccountId> {
+    fn report_offence(kind: OffenceKind, offender: AccountId);
+}
+
+impl<AccountId> ReportOffence<AccountId> for () {
+    fn report_offence(_kind: OffenceKind, _offender: AccountId) {}
+}
+
+/// Something that punishes an offending validator.
+pub trait OnOffenceHandler<AccountId> {
+    /// Slash `offender` (and its nominators) by `slash` of their stake.
+    ///
+    /// May be called more than once for the same offence, when more offenders
+    /// of the same kind show up and the fraction grows.
+    fn on_offence(offender: &AccountId, kind: OffenceKind, slash: Perbill);
+}
+
+impl<AccountId> OnOffenceHandler<AccountId> for () {
+    fn on_offence(_offender: &AccountId, _kind: OffenceKind, _slash: Perbill) {}
+}
+
+/// Something that provides randomness to other modules.
+pub trait Randomness<Output> {
+    /// Random value for `subject`, different for each subject in the same block.
+    ///
+    /// It is derived from the recent blocks, so it is known to everyone one block in
+    /// advance and can be influenced by block authors: do not decide anything of value
+    /// with it in the same block it is read.
+    fn random(subject: &[u8]) -> Output;
+}